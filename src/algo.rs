@@ -4,19 +4,32 @@
 //! - **CPS-Quicksort**: Quicksort extended with Common Prefix Skipping (CPS).
 //! - **Adaptive Radix Sort**: Used for large partitions to improve locality and avoid excessive comparisons.
 //! - **Insertion Sort**: Fallback for small partitions.
+//! - **Presortedness Detection**: Sorted, reverse-sorted and nearly sorted inputs are finished
+//!   by merging their existing runs, costing about one linear scan over the cached prefixes.
 //!
 //! The main entry points are [`orasort`] and [`orasort_mut`].
 
 use crate::core::{KeyAccessor, SortPtr};
+use crate::error::OrasortError;
+use crate::permutation::Permutation;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::mem::MaybeUninit;
 use cuneiform::cuneiform;
-use std::cmp::Ordering;
 
 const NO_ALLOC_THRESHOLD: usize = 32;
 const RADIX_SORT_THRESHOLD: usize = 1024;
 
+/// Maximum number of presorted runs that are merged instead of re-sorted.
+const PRESORTED_MAX_RUNS: usize = 8;
+/// An unsorted tail after the last accepted run is sorted separately and merged
+/// if it is at most `1 / PRESORTED_TAIL_DIVISOR` of the input.
+const PRESORTED_TAIL_DIVISOR: usize = 4;
+
 /// Performs an index-based sort on the provided collection.
 ///
-/// This function does not modify the input collection. Instead, it returns a `Vec<usize>`
+/// This function does not modify the input collection. Instead, it returns a [`Permutation`]
 /// containing the indices that would strictly order the collection.
 ///
 /// The input collection must implement the [`KeyAccessor`] trait, which abstracts
@@ -28,7 +41,8 @@ const RADIX_SORT_THRESHOLD: usize = 1024;
 ///
 /// # Returns
 ///
-/// A vector of indices such that `provider.get_key(indices[i]) <= provider.get_key(indices[i+1])`.
+/// A permutation of indices such that
+/// `provider.get_key(indices[i]) <= provider.get_key(indices[i+1])`.
 ///
 /// # Examples
 ///
@@ -40,10 +54,10 @@ const RADIX_SORT_THRESHOLD: usize = 1024;
 ///
 /// assert_eq!(indices, vec![1, 0, 2]); // apple, banana, cherry
 /// ```
-pub fn orasort<T: KeyAccessor + ?Sized>(provider: &T) -> Vec<usize> {
+pub fn orasort<T: KeyAccessor + ?Sized>(provider: &T) -> Permutation {
     let len = provider.len();
     if len == 0 {
-        return vec![];
+        return Permutation::default();
     }
 
     // Initialize SortPtrs with the first 8 bytes.
@@ -54,15 +68,31 @@ pub fn orasort<T: KeyAccessor + ?Sized>(provider: &T) -> Vec<usize> {
         })
         .collect();
 
-    cps_quicksort(provider, &mut pointers, 0, true);
+    sort_pointers(provider, &mut pointers, 0, &mut Scratch::default());
 
-    pointers.into_iter().map(|p| p.index).collect()
+    Permutation::from_vec_unchecked(pointers.into_iter().map(|p| p.index).collect())
 }
 
 /// Sorts the provided indices based on the key provider, skipping `offset` bytes.
 ///
 /// This is used for hybrid sorting strategies where a preliminary sort (e.g., 4-byte prefix)
 /// has already partitioned the data, and we need to resolve collisions.
+///
+/// # Skipping `offset` bytes
+///
+/// Items are ordered by the bytes of their keys from position `offset` on; the first
+/// `offset` bytes are never read. A key shorter than `offset` contributes no bytes, and
+/// keys whose remaining bytes are equal are ordered by total length, shorter first.
+/// This is the same for every input size.
+///
+/// The result is the plain byte order of the keys only if all of them share their first
+/// `offset` bytes, as in a hybrid sort resolving prefix collisions. Otherwise the skipped
+/// bytes are ignored rather than compared.
+///
+/// # Panics
+///
+/// Panics if an index is out of range for `provider`. Duplicate indices are sorted like
+/// any other entries. Use [`orasort_from_indices_checked`] to validate the input instead.
 pub fn orasort_from_indices<T: KeyAccessor + ?Sized>(
     provider: &T,
     indices: Vec<usize>,
@@ -73,8 +103,6 @@ pub fn orasort_from_indices<T: KeyAccessor + ?Sized>(
         return vec![];
     }
 
-    // Safety check: if offset is huge, get_u64_prefix handles it (returns 0).
-
     let mut pointers: Vec<SortPtr> = indices
         .into_iter()
         .map(|index| {
@@ -83,7 +111,7 @@ pub fn orasort_from_indices<T: KeyAccessor + ?Sized>(
         })
         .collect();
 
-    cps_quicksort(provider, &mut pointers, offset, true);
+    sort_pointers(provider, &mut pointers, offset, &mut Scratch::default());
 
     pointers.into_iter().map(|p| p.index).collect()
 }
@@ -108,31 +136,19 @@ pub fn orasort_from_indices<T: KeyAccessor + ?Sized>(
 /// assert_eq!(data, vec!["apple", "banana", "cherry"]);
 /// ```
 pub fn orasort_mut<T: AsRef<[u8]>>(data: &mut [T]) {
-    // 1. Get indices
-    let indices = orasort(data);
-
-    // 2. Permute in-place (simplest via auxiliary vector if T is Clone)
-    // Minimizing allocations for large T is hard without unsafe or specific traits.
-    // For now, use simple auxiliary buffer approach for safety.
-    apply_permutation(data, indices);
-}
-
-fn apply_permutation<T>(data: &mut [T], mut indices: Vec<usize>) {
-    for i in 0..data.len() {
-        let mut current = i;
-        while indices[current] != i {
-            let next = indices[current];
-            data.swap(current, next);
-            indices[current] = current; // Mark as visited/placed
-            current = next;
-        }
-        indices[current] = current;
-    }
+    orasort(data).apply_to(data);
 }
 
 /// Sorts the provided indices in-place based on the key provider, skipping `offset` bytes.
 ///
 /// Use this to avoid allocations when you already have a `Vec<usize>` or slice of indices.
+///
+/// See [`orasort_from_indices`] for how `offset` affects the order.
+///
+/// # Panics
+///
+/// Panics if an index is out of range for `provider`. Use [`orasort_slice_checked`] to
+/// validate the input instead.
 pub fn orasort_slice<T: KeyAccessor + ?Sized>(provider: &T, indices: &mut [usize], offset: usize) {
     let len = indices.len();
     if len == 0 {
@@ -143,20 +159,7 @@ pub fn orasort_slice<T: KeyAccessor + ?Sized>(provider: &T, indices: &mut [usize
     // Use simple insertion sort / sort_unstable_by with direct KeyAccessor calls.
     // The overhead of `get_u64_prefix` is small enough that calling it per-cmp is better than allocating `Vec<SortPtr>`.
     if len <= NO_ALLOC_THRESHOLD {
-        indices.sort_unstable_by(|&a, &b| {
-            let ka = provider.get_key(a);
-            let kb = provider.get_key(b);
-            let start = offset.min(ka.len()).min(kb.len());
-            // Safe to skip `offset` bytes as they are equal by caller guarantee (mostly)
-            // - actually orasort_slice contract says "skipping offset bytes".
-            // If they are not equal, they will be ordered correctly by suffix anyway?
-            // No, if prefix is skipped, we assume prefix is equal or don't care?
-            // Orasort contract: "skipping offset bytes". Implies we only sort based on suffix.
-            // If prefixes differ, this function doesn't guarantee global order unless we check prefix.
-            // But usually orasort is called recursively where prefixes ARE equal.
-            // In hybrid sort collision, prefixes ARE equal.
-            ka[start..].cmp(&kb[start..])
-        });
+        sort_small(provider, indices, offset);
         return;
     }
 
@@ -168,7 +171,7 @@ pub fn orasort_slice<T: KeyAccessor + ?Sized>(provider: &T, indices: &mut [usize
         })
         .collect();
 
-    cps_quicksort(provider, &mut pointers, offset, true);
+    sort_pointers(provider, &mut pointers, offset, &mut Scratch::default());
 
     // Write back sorted indices
     for (i, p) in pointers.into_iter().enumerate() {
@@ -176,22 +179,558 @@ pub fn orasort_slice<T: KeyAccessor + ?Sized>(provider: &T, indices: &mut [usize
     }
 }
 
+/// Validating variant of [`orasort_from_indices`].
+///
+/// # Errors
+///
+/// - [`OrasortError::IndexOutOfRange`] if an index is not below `provider.len()`.
+/// - [`OrasortError::DuplicateIndex`] if an index occurs more than once.
+/// - [`OrasortError::OffsetBeyondKeys`] if `offset > 0` and no selected key extends past
+///   `offset`, which would make every key compare by length alone.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::{OrasortError, orasort_from_indices_checked};
+///
+/// let data = vec!["xa", "xc", "xb"];
+/// assert_eq!(orasort_from_indices_checked(&data, vec![1, 2], 1), Ok(vec![2, 1]));
+/// assert_eq!(
+///     orasort_from_indices_checked(&data, vec![1, 3], 1),
+///     Err(OrasortError::IndexOutOfRange { index: 3, len: 3 })
+/// );
+/// ```
+pub fn orasort_from_indices_checked<T: KeyAccessor + ?Sized>(
+    provider: &T,
+    indices: Vec<usize>,
+    offset: usize,
+) -> Result<Vec<usize>, OrasortError> {
+    validate_indices(provider, &indices, offset)?;
+    Ok(orasort_from_indices(provider, indices, offset))
+}
+
+/// Validating variant of [`orasort_slice`].
+///
+/// # Errors
+///
+/// Same as [`orasort_from_indices_checked`]. `indices` is left unchanged on error.
+pub fn orasort_slice_checked<T: KeyAccessor + ?Sized>(
+    provider: &T,
+    indices: &mut [usize],
+    offset: usize,
+) -> Result<(), OrasortError> {
+    validate_indices(provider, indices, offset)?;
+    orasort_slice(provider, indices, offset);
+    Ok(())
+}
+
+/// Checks that `indices` are in range and distinct, and that `offset` does not skip
+/// past every selected key.
+pub(crate) fn validate_indices<T: KeyAccessor + ?Sized>(
+    provider: &T,
+    indices: &[usize],
+    offset: usize,
+) -> Result<(), OrasortError> {
+    check_indices(indices, provider.len())?;
+
+    if offset > 0 && !indices.is_empty() {
+        let max_key_len = indices
+            .iter()
+            .map(|&index| provider.get_key(index).len())
+            .max()
+            .unwrap_or(0);
+        if max_key_len <= offset {
+            return Err(OrasortError::OffsetBeyondKeys {
+                offset,
+                max_key_len,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that every index is below `len` and occurs at most once.
+pub(crate) fn check_indices(indices: &[usize], len: usize) -> Result<(), OrasortError> {
+    let mut seen = vec![0u64; len.div_ceil(64)];
+
+    for &index in indices {
+        if index >= len {
+            return Err(OrasortError::IndexOutOfRange { index, len });
+        }
+        let (word, bit) = (index / 64, 1u64 << (index % 64));
+        if seen[word] & bit != 0 {
+            return Err(OrasortError::DuplicateIndex { index });
+        }
+        seen[word] |= bit;
+    }
+
+    Ok(())
+}
+
+/// Returns the position of the first key that orders before its predecessor, or `None`
+/// if the keys of `provider` are already sorted.
+///
+/// Adjacent keys are compared exactly as during a sort: by their cached 8-byte prefixes
+/// first, and by their remaining bytes and lengths only when the prefixes tie. Sorted
+/// input whose neighbours differ within 8 bytes is verified without reading further.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::check_sorted;
+///
+/// assert_eq!(check_sorted(&vec!["apple", "apple", "banana"]), None);
+/// assert_eq!(check_sorted(&vec!["apple", "banana", "applesauce"]), Some(2));
+/// ```
+pub fn check_sorted<T: KeyAccessor + ?Sized>(provider: &T) -> Option<usize> {
+    if provider.is_empty() {
+        return None;
+    }
+
+    let entry = |index| SortPtr {
+        index,
+        cache: provider.get_u64_prefix(index, 0),
+    };
+
+    let mut previous = entry(0);
+    for index in 1..provider.len() {
+        let current = entry(index);
+        if compare_entries(provider, &previous, &current, 0) == Ordering::Greater {
+            return Some(index);
+        }
+        previous = current;
+    }
+    None
+}
+
+/// Returns the number of scratch slots [`orasort_in`] needs to sort `len` indices.
+///
+/// Inputs of up to 32 items are sorted directly and need no scratch space; larger inputs
+/// need two slots per item (the sort pointers and an equally sized auxiliary buffer).
+pub const fn required_scratch(len: usize) -> usize {
+    if len <= NO_ALLOC_THRESHOLD {
+        0
+    } else {
+        2 * len
+    }
+}
+
+/// Sorts the provided indices in-place without allocating.
+///
+/// All working memory comes from `scratch`, which must hold at least
+/// [`required_scratch(indices.len())`](required_scratch) slots; its previous contents are
+/// ignored. The radix work stack is kept in `indices` itself while the sort runs, so the
+/// call stack stays bounded as well.
+///
+/// # Errors
+///
+/// Returns [`OrasortError::ScratchTooSmall`] (leaving `indices` untouched) if `scratch` is
+/// too small.
+///
+/// # Examples
+///
+/// ```
+/// use core::mem::MaybeUninit;
+/// use orasort::{orasort_in, required_scratch};
+/// use orasort::core::SortPtr;
+///
+/// let data = ["delta", "alpha", "charlie", "bravo"];
+/// let mut indices = [0, 1, 2, 3];
+/// let mut scratch = [MaybeUninit::<SortPtr>::uninit(); 64];
+/// assert!(scratch.len() >= required_scratch(indices.len()));
+///
+/// orasort_in(&data[..], &mut indices, &mut scratch).unwrap();
+/// assert_eq!(indices, [1, 3, 2, 0]);
+/// ```
+pub fn orasort_in<T: KeyAccessor + ?Sized>(
+    provider: &T,
+    indices: &mut [usize],
+    scratch: &mut [MaybeUninit<SortPtr>],
+) -> Result<(), OrasortError> {
+    let len = indices.len();
+    let required = required_scratch(len);
+    if scratch.len() < required {
+        return Err(OrasortError::ScratchTooSmall {
+            required,
+            provided: scratch.len(),
+        });
+    }
+
+    if len <= NO_ALLOC_THRESHOLD {
+        sort_small(provider, indices, 0);
+        return Ok(());
+    }
+
+    let (pointer_slots, aux_slots) = scratch[..required].split_at_mut(len);
+    let pointers = init_slots(
+        pointer_slots,
+        indices.iter().map(|&index| {
+            let cache = provider.get_u64_prefix(index, 0);
+            SortPtr { index, cache }
+        }),
+    );
+    let aux = init_slots(aux_slots, core::iter::repeat(SortPtr::default()));
+
+    // `indices` is free until the write-back, and three words per task fit easily:
+    // radix passes only run for more than RADIX_SORT_THRESHOLD items.
+    debug_assert!(3 * max_radix_tasks(len) <= len);
+    let mut workspace = BorrowedScratch {
+        aux,
+        stack: SliceStack {
+            words: indices,
+            len: 0,
+        },
+    };
+    sort_pointers(provider, pointers, 0, &mut workspace);
+
+    // Write back sorted indices
+    let indices = workspace.stack.words;
+    for (slot, p) in indices.iter_mut().zip(pointers.iter()) {
+        *slot = p.index;
+    }
+    Ok(())
+}
+
+/// Sorts a small slice of indices directly, without sort pointers.
+///
+/// Uses the same ordering as the sort-pointer path (see [`compare_suffixes`]), so results
+/// do not depend on which side of `NO_ALLOC_THRESHOLD` the input falls, even when the
+/// skipped prefixes are not actually equal.
+fn sort_small<T: KeyAccessor + ?Sized>(provider: &T, indices: &mut [usize], offset: usize) {
+    indices.sort_unstable_by(|&a, &b| {
+        compare_suffixes(provider.get_key(a), provider.get_key(b), offset)
+    });
+}
+
+/// Initializes every slot from `values` and returns the slots as initialized pointers.
+///
+/// `values` must yield at least `slots.len()` items.
+fn init_slots(
+    slots: &mut [MaybeUninit<SortPtr>],
+    values: impl Iterator<Item = SortPtr>,
+) -> &mut [SortPtr] {
+    let mut written = 0;
+    for (slot, value) in slots.iter_mut().zip(values) {
+        slot.write(value);
+        written += 1;
+    }
+    assert_eq!(written, slots.len());
+
+    // SAFETY: Every slot was initialized above, and `MaybeUninit<SortPtr>` has the same
+    // layout as `SortPtr`.
+    unsafe { &mut *(slots as *mut [MaybeUninit<SortPtr>] as *mut [SortPtr]) }
+}
+
+/// Working memory of the sort: an auxiliary pointer buffer and the radix work stack.
+pub(crate) trait Workspace {
+    /// Storage of the radix work stack.
+    type Stack: TaskStack;
+
+    /// Returns an auxiliary buffer of exactly `len` pointers together with the work stack.
+    fn split(&mut self, len: usize) -> (&mut [SortPtr], &mut Self::Stack);
+}
+
+/// Storage for pending [`RadixTask`]s.
+pub(crate) trait TaskStack {
+    fn clear(&mut self);
+    fn push(&mut self, task: RadixTask);
+    fn pop(&mut self) -> Option<RadixTask>;
+}
+
+impl TaskStack for Vec<RadixTask> {
+    #[inline(always)]
+    fn clear(&mut self) {
+        Vec::clear(self);
+    }
+
+    #[inline(always)]
+    fn push(&mut self, task: RadixTask) {
+        Vec::push(self, task);
+    }
+
+    #[inline(always)]
+    fn pop(&mut self) -> Option<RadixTask> {
+        Vec::pop(self)
+    }
+}
+
+/// Reusable, heap-allocated working memory of the sort.
+///
+/// Holds the auxiliary buffer used by radix passes and run merging, and the radix work
+/// stack. Both grow on demand and keep their capacity, so a warmed-up `Scratch` lets
+/// repeated sorts run without allocating.
+#[derive(Debug, Default)]
+pub(crate) struct Scratch {
+    aux: Vec<SortPtr>,
+    stack: Vec<RadixTask>,
+}
+
+impl Scratch {
+    /// Reserves room for sorting `len` pointers without further allocation.
+    pub(crate) fn reserve(&mut self, len: usize) {
+        if self.aux.len() < len {
+            self.aux.resize(len, SortPtr::default());
+        }
+        let tasks = max_radix_tasks(len);
+        self.stack.reserve(tasks.saturating_sub(self.stack.len()));
+    }
+
+    /// Returns the number of heap bytes held by the scratch space.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.aux.capacity() * size_of::<SortPtr>() + self.stack.capacity() * size_of::<RadixTask>()
+    }
+
+    /// Releases all memory held by the scratch space.
+    pub(crate) fn release(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Workspace for Scratch {
+    type Stack = Vec<RadixTask>;
+
+    fn split(&mut self, len: usize) -> (&mut [SortPtr], &mut Self::Stack) {
+        self.reserve(len);
+        (&mut self.aux[..len], &mut self.stack)
+    }
+}
+
+/// Upper bound on the number of pending radix tasks when sorting `len` pointers.
+///
+/// Only buckets larger than `RADIX_SORT_THRESHOLD` are pushed and pending buckets never
+/// overlap.
+const fn max_radix_tasks(len: usize) -> usize {
+    len / RADIX_SORT_THRESHOLD + 1
+}
+
+/// Radix work stack stored in a borrowed `usize` buffer, three words per task.
+struct SliceStack<'a> {
+    words: &'a mut [usize],
+    len: usize,
+}
+
+impl TaskStack for SliceStack<'_> {
+    #[inline(always)]
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    #[inline(always)]
+    fn push(&mut self, task: RadixTask) {
+        let at = self.len * 3;
+        self.words[at..at + 3].copy_from_slice(&[task.start, task.end, task.cp_len]);
+        self.len += 1;
+    }
+
+    #[inline(always)]
+    fn pop(&mut self) -> Option<RadixTask> {
+        self.len = self.len.checked_sub(1)?;
+        let at = self.len * 3;
+        Some(RadixTask {
+            start: self.words[at],
+            end: self.words[at + 1],
+            cp_len: self.words[at + 2],
+        })
+    }
+}
+
+/// Caller-provided working memory used by [`orasort_in`].
+struct BorrowedScratch<'a> {
+    aux: &'a mut [SortPtr],
+    stack: SliceStack<'a>,
+}
+
+impl<'a> Workspace for BorrowedScratch<'a> {
+    type Stack = SliceStack<'a>;
+
+    fn split(&mut self, len: usize) -> (&mut [SortPtr], &mut Self::Stack) {
+        (&mut self.aux[..len], &mut self.stack)
+    }
+}
+
+/// Sorts freshly initialized sort pointers whose keys share `cp_len` skipped bytes.
+///
+/// Already (nearly) sorted input is detected first and finished by merging its runs;
+/// everything else goes through [`cps_quicksort`]. Returns `true` in the first case.
+pub(crate) fn sort_pointers<T: SortKeys + ?Sized, W: Workspace>(
+    provider: &T,
+    ptrs: &mut [SortPtr],
+    cp_len: usize,
+    scratch: &mut W,
+) -> bool {
+    if merge_presorted(provider, ptrs, cp_len, scratch) {
+        return true;
+    }
+
+    cps_quicksort(provider, ptrs, cp_len, scratch);
+    false
+}
+
+/// Presortedness detection.
+///
+/// Splits `ptrs` into maximal non-descending and strictly descending runs (reversing the
+/// latter in place), comparing cached prefixes first just like the sort itself does.
+/// If at most [`PRESORTED_MAX_RUNS`] runs cover the input, the runs are merged and `true`
+/// is returned. A short unsorted tail behind the runs (e.g. a batch appended to a sorted
+/// log) is sorted on its own and merged as one more run.
+///
+/// Returns `false` without sorting if the input is not presorted enough. Random input
+/// gives up after a handful of elements, so the detection costs almost nothing then.
+fn merge_presorted<T: SortKeys + ?Sized, W: Workspace>(
+    provider: &T,
+    ptrs: &mut [SortPtr],
+    cp_len: usize,
+    scratch: &mut W,
+) -> bool {
+    let len = ptrs.len();
+    let mut run_ends = [0usize; PRESORTED_MAX_RUNS + 1];
+    let mut runs = 0;
+    let mut start = 0;
+
+    while start < len {
+        if runs == PRESORTED_MAX_RUNS {
+            if (len - start) * PRESORTED_TAIL_DIVISOR > len {
+                return false;
+            }
+            cps_quicksort(provider, &mut ptrs[start..], cp_len, scratch);
+            run_ends[runs] = len;
+            runs += 1;
+            break;
+        }
+
+        let mut end = start + 1;
+        if end < len
+            && compare_entries(provider, &ptrs[end], &ptrs[start], cp_len) == Ordering::Less
+        {
+            while end < len
+                && compare_entries(provider, &ptrs[end], &ptrs[end - 1], cp_len) == Ordering::Less
+            {
+                end += 1;
+            }
+            ptrs[start..end].reverse();
+        } else {
+            while end < len
+                && compare_entries(provider, &ptrs[end], &ptrs[end - 1], cp_len) != Ordering::Less
+            {
+                end += 1;
+            }
+        }
+
+        run_ends[runs] = end;
+        runs += 1;
+        start = end;
+    }
+
+    if runs > 1 {
+        let (aux, _) = scratch.split(len);
+        merge_runs(provider, ptrs, aux, &mut run_ends[..runs], cp_len);
+    }
+    true
+}
+
+/// Merges adjacent sorted runs of `ptrs` pairwise until a single run remains.
+///
+/// `run_ends` holds the exclusive end of every run, in order. `aux` must be at least as
+/// long as `ptrs`.
+fn merge_runs<T: SortKeys + ?Sized>(
+    provider: &T,
+    ptrs: &mut [SortPtr],
+    aux: &mut [SortPtr],
+    run_ends: &mut [usize],
+    cp_len: usize,
+) {
+    let mut runs = run_ends.len();
+
+    while runs > 1 {
+        let mut merged = 0;
+        let mut start = 0;
+
+        for pair in (0..runs).step_by(2) {
+            if provider.failed() {
+                return;
+            }
+
+            let mid = run_ends[pair];
+            let end = if pair + 1 < runs {
+                run_ends[pair + 1]
+            } else {
+                mid
+            };
+
+            // Runs that are already in order relative to each other (e.g. appends) need no work.
+            if mid < end
+                && compare_entries(provider, &ptrs[mid], &ptrs[mid - 1], cp_len) == Ordering::Less
+            {
+                merge_into(
+                    provider,
+                    &ptrs[start..mid],
+                    &ptrs[mid..end],
+                    &mut aux[start..end],
+                    cp_len,
+                );
+                ptrs[start..end].copy_from_slice(&aux[start..end]);
+            }
+
+            run_ends[merged] = end;
+            merged += 1;
+            start = end;
+        }
+
+        runs = merged;
+    }
+}
+
+/// Stable two-way merge of `left` and `right` into `out`.
+fn merge_into<T: SortKeys + ?Sized>(
+    provider: &T,
+    left: &[SortPtr],
+    right: &[SortPtr],
+    out: &mut [SortPtr],
+    cp_len: usize,
+) {
+    let (mut i, mut j) = (0, 0);
+
+    for slot in out.iter_mut() {
+        let take_left = j == right.len()
+            || (i < left.len()
+                && compare_entries(provider, &right[j], &left[i], cp_len) != Ordering::Less);
+        if take_left {
+            *slot = left[i];
+            i += 1;
+        } else {
+            *slot = right[j];
+            j += 1;
+        }
+    }
+}
+
 /// Common Prefix Skipping Quicksort (CPS-QS).
 ///
-/// Recursively sorts the `ptrs` slice.
+/// Sorts the `ptrs` slice.
 /// * `cp_len`: The length of the common prefix shared by all keys in this slice.
-/// * `allow_radix`: Whether to attempt switching to Adaptive Radix Sort (AQS) for large inputs.
-fn cps_quicksort<T: KeyAccessor + ?Sized>(
+/// * `scratch`: Working memory for switching to Adaptive Radix Sort (AQS) on large inputs.
+fn cps_quicksort<T: SortKeys + ?Sized, W: Workspace>(
     provider: &T,
     ptrs: &mut [SortPtr],
     cp_len: usize,
-    allow_radix: bool,
+    scratch: &mut W,
 ) {
-    let len = ptrs.len();
+    // Use Adaptive Radix Sort for large inputs
+    if ptrs.len() > RADIX_SORT_THRESHOLD {
+        aqs_radix(provider, ptrs, cp_len, scratch);
+        return;
+    }
+
+    sort_leaf(provider, ptrs, cp_len);
+}
+
+/// Sorts a partition that does not benefit from (further) radix passes.
+fn sort_leaf<T: SortKeys + ?Sized>(provider: &T, ptrs: &mut [SortPtr], cp_len: usize) {
+    provider.shared_prefix(ptrs, cp_len);
 
-    // Use Adaptive Radix Sort for large inputs if allowed
-    if allow_radix && len > RADIX_SORT_THRESHOLD {
-        aqs_radix(provider, ptrs, cp_len);
+    if T::FALLIBLE {
+        merge_sort_leaf(provider, ptrs, cp_len);
         return;
     }
 
@@ -200,6 +739,21 @@ fn cps_quicksort<T: KeyAccessor + ?Sized>(
     ptrs.sort_unstable_by(|a, b| compare_entries(provider, a, b, cp_len));
 }
 
+/// Sorts a leaf of a provider whose key fetches can fail, stopping at the first failure.
+///
+/// After a failure keys read as empty, so later comparisons contradict earlier ones.
+/// [`merge_runs`] only ever indexes within its runs, whatever the comparisons say.
+#[cold]
+fn merge_sort_leaf<T: SortKeys + ?Sized>(provider: &T, ptrs: &mut [SortPtr], cp_len: usize) {
+    if provider.failed() {
+        return;
+    }
+
+    let mut aux = vec![SortPtr::default(); ptrs.len()];
+    let mut run_ends: Vec<usize> = (1..=ptrs.len()).collect();
+    merge_runs(provider, ptrs, &mut aux, &mut run_ends, cp_len);
+}
+
 /// Number of buckets for Radix sort (256 for byte-wise).
 const RADIX_BUCKETS: usize = 256;
 
@@ -209,16 +763,90 @@ struct RadixCounts {
     data: [usize; RADIX_BUCKETS],
 }
 
+/// A bucket waiting for another radix pass on the explicit work stack.
+///
+/// `start..end` is relative to the slice handed to [`aqs_radix`]; every key in the range
+/// shares its first `cp_len` bytes.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RadixTask {
+    start: usize,
+    end: usize,
+    cp_len: usize,
+}
+
+/// Adaptive Radix Sort.
+///
+/// Drives [`radix_pass`] over an explicit work stack instead of recursing into buckets, so
+/// the call-stack depth does not grow with key length or common prefix length. Buckets
+/// that need another radix pass are pushed onto the (heap-allocated) stack; all other
+/// buckets are finished immediately with pdqsort.
+///
+/// # Stack usage
+///
+/// The radix phase uses a single fixed-size frame of roughly 4 KiB on 64-bit targets (the
+/// 256-entry histogram and bucket offsets), independent of key length. Bucket leaves are
+/// sorted with `slice::sort_unstable_by`, whose recursion is bounded by `O(log n)`.
+///
+/// Pending work lives in `scratch`: only buckets larger than `RADIX_SORT_THRESHOLD` are
+/// pushed and pending buckets never overlap, so the work stack holds at most
+/// `n / RADIX_SORT_THRESHOLD + 1` entries (see `max_radix_tasks`).
+fn aqs_radix<T: SortKeys + ?Sized, W: Workspace>(
+    provider: &T,
+    ptrs: &mut [SortPtr],
+    cp_len: usize,
+    scratch: &mut W,
+) {
+    let (aux, stack) = scratch.split(ptrs.len());
+
+    stack.clear();
+    stack.push(RadixTask {
+        start: 0,
+        end: ptrs.len(),
+        cp_len,
+    });
+
+    while let Some(task) = stack.pop() {
+        if provider.failed() {
+            return;
+        }
+
+        let bucket = &mut ptrs[task.start..task.end];
+        let aux_slice = &mut aux[..bucket.len()];
+        radix_pass(
+            provider,
+            bucket,
+            aux_slice,
+            task.cp_len,
+            |start, end, cp_len| {
+                stack.push(RadixTask {
+                    start: task.start + start,
+                    end: task.start + end,
+                    cp_len,
+                })
+            },
+        );
+    }
+}
+
 /// Adaptive Radix Sort Step.
 ///
 /// Distributes keys into 256 buckets based on the next byte of the key (from cache).
 ///
 /// 1. Counts frequencies of each byte (histograms).
 /// 2. Computes prefix sums to determine bucket starting positions.
-/// 3. Permutes elements into a temporary buffer and writes them back in sorted bucket order.
-/// 4. Recursively calls `cps_quicksort` on each bucket.
-fn aqs_radix<T: KeyAccessor + ?Sized>(provider: &T, ptrs: &mut [SortPtr], mut cp_len: usize) {
-    let mut aux = vec![SortPtr { index: 0, cache: 0 }; ptrs.len()];
+/// 3. Permutes elements into `aux` and writes them back in sorted bucket order.
+/// 4. Finishes small (or degenerate) buckets with pdqsort and hands larger ones to `defer`
+///    as `(start, end, cp_len)` for another radix pass.
+fn radix_pass<T, F>(
+    provider: &T,
+    ptrs: &mut [SortPtr],
+    aux: &mut [SortPtr],
+    mut cp_len: usize,
+    mut defer: F,
+) where
+    T: SortKeys + ?Sized,
+    F: FnMut(usize, usize, usize),
+{
     let mut bytes_since_load = 0; // Track how many bytes we consumed from the current cache load
 
     loop {
@@ -261,94 +889,160 @@ fn aqs_radix<T: KeyAccessor + ?Sized>(provider: &T, ptrs: &mut [SortPtr], mut cp
             }
         }
 
-        let mut counts = RadixCounts {
-            data: [0; RADIX_BUCKETS],
-        };
-        let counts = &mut counts.data;
+        break;
+    }
 
-        // 1. Count frequencies via cache
-        // Note: cache >> 56 extracts the most significant byte (big-endian prefix)
-        ptrs.iter().for_each(|p| {
-            let b = (p.cache >> 56) as u8;
-            counts[b as usize] += 1;
-        });
+    provider.shared_prefix(ptrs, cp_len);
 
-        // Optimization: Degenerate Check removed (Block Skip handles it).
-        // Exceptions:
-        // - Degenerate Zero: Handled by falling through to standard Radix logic (which puts all in bucket 0 and recurses with !is_degenerate).
-
-        // 2. Compute offsets (prefix sum)
-        let mut offsets = [0usize; RADIX_BUCKETS];
-        let mut sum = 0;
-        offsets
-            .iter_mut()
-            .zip(counts.iter())
-            .for_each(|(offset, &count)| {
-                *offset = sum;
-                sum += count;
-            });
+    let mut counts = RadixCounts {
+        data: [0; RADIX_BUCKETS],
+    };
+    let counts = &mut counts.data;
 
-        // 3. Permute using aux buffer
-        // SAFETY: We use a split mutable slice approach or safe copy.
-        // For simplicity and safety in this implementation, we copy FROM `ptrs` TO `aux` then back.
-        // Note: aux must be at least as large as ptrs. In recursive calls, `aux` is large enough for the whole array,
-        // but we only need the slice corresponding to `ptrs`.
-        let aux_slice = &mut aux[..ptrs.len()];
-        let mut cur_offsets = offsets;
-
-        // This copy is necessary for stability/correctness in MSD Radix when doing permutation
-        // SAFETY: cur_offsets are computed from prefix sums of counts, so pos is always in bounds.
-        for p in ptrs.iter() {
-            let b = (p.cache >> 56) as u8;
-            let pos = cur_offsets[b as usize];
-            unsafe {
-                *aux_slice.get_unchecked_mut(pos) = *p;
-            }
-            cur_offsets[b as usize] += 1;
+    // 1. Count frequencies via cache
+    // Note: cache >> 56 extracts the most significant byte (big-endian prefix)
+    ptrs.iter().for_each(|p| {
+        let b = (p.cache >> 56) as u8;
+        counts[b as usize] += 1;
+    });
+
+    // Optimization: Degenerate Check removed (Block Skip handles it).
+    // Exceptions:
+    // - Degenerate Zero: Handled by falling through to standard Radix logic (which puts all in bucket 0 and sorts it without radix).
+
+    // 2. Compute offsets (prefix sum), in place: `counts[b]` becomes the start of bucket `b`.
+    let mut sum = 0;
+    counts.iter_mut().for_each(|count| {
+        let start = sum;
+        sum += *count;
+        *count = start;
+    });
+    let starts = &*counts;
+
+    // 3. Permute using aux buffer
+    // This copy is necessary for stability/correctness in MSD Radix when doing permutation.
+    // After the loop, `ends[b]` is the exclusive end of bucket `b`.
+    let mut ends = *starts;
+
+    // SAFETY: bucket offsets are computed from prefix sums of counts, so pos is always in bounds.
+    for p in ptrs.iter() {
+        let b = (p.cache >> 56) as u8;
+        let pos = ends[b as usize];
+        unsafe {
+            *aux.get_unchecked_mut(pos) = *p;
         }
+        ends[b as usize] += 1;
+    }
 
-        ptrs.copy_from_slice(aux_slice);
+    ptrs.copy_from_slice(aux);
 
-        // 4. Recurse on buckets
-        let mut start = 0;
-        let total_len = ptrs.len();
-        let new_cp = cp_len + 1;
-        counts.iter().for_each(|&count| {
-            let end = start + count;
-            if end > start {
-                let bucket = &mut ptrs[start..end];
+    // 4. Finish or defer buckets
+    let total_len = ptrs.len();
+    let new_cp = cp_len + 1;
+    starts
+        .iter()
+        .zip(ends.iter())
+        .filter(|(start, end)| end > start)
+        .for_each(|(&start, &end)| {
+            let bucket = &mut ptrs[start..end];
 
-                update_caches(provider, bucket, new_cp);
+            update_caches(provider, bucket, new_cp);
 
-                let is_degenerate = (end - start) == total_len;
-                cps_quicksort(provider, bucket, new_cp, !is_degenerate);
+            let is_degenerate = (end - start) == total_len;
+            if !is_degenerate && bucket.len() > RADIX_SORT_THRESHOLD {
+                defer(start, end, new_cp);
+            } else {
+                sort_leaf(provider, bucket, new_cp);
             }
-            start = end;
         });
-
-        break; // Done
-    }
 }
 
 /// Reloads caches for `SortPtr`s using the new common prefix length.
 ///
 /// This ensures that the `cache` field of each `SortPtr` contains the next 8 bytes
 /// of the key starting at `new_cp`.
-fn update_caches<T: KeyAccessor + ?Sized>(provider: &T, ptrs: &mut [SortPtr], new_cp: usize) {
+fn update_caches<T: SortKeys + ?Sized>(provider: &T, ptrs: &mut [SortPtr], new_cp: usize) {
     // Always reload to ensure correctness with 0-padding ambiguities.
     ptrs.iter_mut().for_each(|p| {
-        p.cache = provider.get_u64_prefix(p.index, new_cp);
+        p.cache = provider.prefix(p.index, new_cp);
     });
 }
 
+/// Key access as seen by the sort internals.
+///
+/// Every [`KeyAccessor`] is `SortKeys`. Wrappers can additionally observe the comparisons
+/// that found two keys equal, which is how tie information is gathered during the sort.
+/// The sorted keys need not be the stored bytes: collated sorts generate them as they go.
+pub(crate) trait SortKeys {
+    /// Returns the key at `index` (see [`KeyAccessor::get_key`]).
+    fn key(&self, index: usize) -> &[u8];
+
+    /// Returns the 8 key bytes at `offset` (see [`KeyAccessor::get_u64_prefix`]).
+    fn prefix(&self, index: usize, offset: usize) -> u64;
+
+    /// Orders the keys at `a` and `b`, whose 8 bytes at `offset` are equal, counting zero
+    /// padding.
+    ///
+    /// The bytes before `offset` are not compared. Keys whose remaining bytes are equal
+    /// are ordered by total length. The default compares the bytes of [`key`](Self::key).
+    #[inline(always)]
+    fn compare_tails(&self, a: usize, b: usize, offset: usize) -> Ordering {
+        compare_key_tails(self.key(a), self.key(b), offset)
+    }
+
+    /// Called whenever the keys at `a` and `b` compare equal.
+    ///
+    /// Every pair of equal keys that ends up adjacent is linked by a chain of such calls:
+    /// equal keys always share a radix bucket, and a comparison sort cannot place two keys
+    /// next to each other without relating them.
+    #[inline(always)]
+    fn keys_equal(&self, _a: usize, _b: usize) {}
+
+    /// Called when the keys of `ptrs` are known to share their first `cp_len` bytes,
+    /// counting the zero padding of shorter keys.
+    ///
+    /// `ptrs` is a sub-slice of the array handed to [`sort_pointers`] that already holds
+    /// its final set of pointers, so every pair of neighbours in the sorted output is
+    /// covered by such a call. This does not hold if `sort_pointers` returns `true`: merging
+    /// presorted runs moves pointers after the calls.
+    #[inline(always)]
+    fn shared_prefix(&self, _ptrs: &[SortPtr], _cp_len: usize) {}
+
+    /// Whether [`failed`](Self::failed) can ever return `true`.
+    ///
+    /// Leaves of such providers are sorted by a merge sort that stays in bounds however
+    /// inconsistent the comparisons get, instead of by `sort_unstable_by`, which may panic
+    /// when the order changes under it.
+    const FALLIBLE: bool = false;
+
+    /// Returns `true` once a key fetch has failed.
+    ///
+    /// The result of the sort is discarded then, so the sort stops as soon as it can.
+    #[inline(always)]
+    fn failed(&self) -> bool {
+        false
+    }
+}
+
+impl<T: KeyAccessor + ?Sized> SortKeys for T {
+    #[inline(always)]
+    fn key(&self, index: usize) -> &[u8] {
+        self.get_key(index)
+    }
+
+    #[inline(always)]
+    fn prefix(&self, index: usize, offset: usize) -> u64 {
+        self.get_u64_prefix(index, offset)
+    }
+}
+
 /// Compares a sort pointer against a pivot.
 ///
 /// 1. **Fast path**: Compares cached `u64` values.
-/// 2. **Slow path**: If caches match, loads full keys from `provider` and compares byte-by-byte
-///    starting from `offset + 8` (since the first 8 bytes are known equal).
-/// 3. Handles "ambiguous zones" where one key ends exactly within the cached region.
+/// 2. **Slow path**: If caches match, hands the rest of the comparison to
+///    [`SortKeys::compare_tails`].
 #[inline(always)]
-fn compare_entries<T: KeyAccessor + ?Sized>(
+fn compare_entries<T: SortKeys + ?Sized>(
     provider: &T,
     a: &SortPtr,
     pivot: &SortPtr,
@@ -359,33 +1053,42 @@ fn compare_entries<T: KeyAccessor + ?Sized>(
         return a.cache.cmp(&pivot.cache);
     }
 
-    // Slow path: resolve ambiguity or check beyond cache
-    let key_a = provider.get_key(a.index);
-    let key_p = provider.get_key(pivot.index);
+    let ordering = provider.compare_tails(a.index, pivot.index, offset);
+    if ordering == Ordering::Equal {
+        provider.keys_equal(a.index, pivot.index);
+    }
+    ordering
+}
 
+/// Compares two keys whose 8 bytes at `offset` are equal, counting zero padding.
+///
+/// Compares byte-by-byte starting from `offset + 8`, since the first 8 bytes are known
+/// equal, except in the "ambiguous zone" where one key ends within the cached region.
+#[inline(always)]
+fn compare_key_tails(key_a: &[u8], key_b: &[u8], offset: usize) -> Ordering {
     let start_safe = offset + 8;
 
     // Ambiguous zone check (short keys vs padding)
-    if key_a.len() < start_safe || key_p.len() < start_safe {
-        let slice_a = if offset < key_a.len() {
-            &key_a[offset..]
-        } else {
-            &[]
-        };
-        let slice_p = if offset < key_p.len() {
-            &key_p[offset..]
-        } else {
-            &[]
-        };
-        return match slice_a.cmp(slice_p) {
-            Ordering::Equal => key_a.len().cmp(&key_p.len()),
-            other => other,
-        };
+    if key_a.len() < start_safe || key_b.len() < start_safe {
+        compare_suffixes(key_a, key_b, offset)
+    } else {
+        // Full comparison beyond cache (the cached 8 bytes are known equal)
+        compare_suffixes(key_a, key_b, start_safe)
     }
+}
+
+/// Orders two keys by their bytes from `offset` on, breaking ties by total key length.
+///
+/// This is the ordering contract of every entry point that skips `offset` bytes: the
+/// sort-pointer path ([`compare_entries`]) and the small-input path both reduce to it.
+/// Keys shorter than `offset` contribute no bytes.
+#[inline(always)]
+fn compare_suffixes(key_a: &[u8], key_b: &[u8], offset: usize) -> Ordering {
+    let suffix_a = key_a.get(offset..).unwrap_or(&[]);
+    let suffix_b = key_b.get(offset..).unwrap_or(&[]);
 
-    // Full comparison beyond cache
-    match key_a[start_safe..].cmp(&key_p[start_safe..]) {
-        Ordering::Equal => key_a.len().cmp(&key_p.len()),
+    match suffix_a.cmp(suffix_b) {
+        Ordering::Equal => key_a.len().cmp(&key_b.len()),
         other => other,
     }
 }
@@ -0,0 +1,264 @@
+//! Packed contiguous key storage.
+//!
+//! Collections like `Vec<String>` scatter their keys across the heap, and the default
+//! [`KeyAccessor::get_u64_prefix`] has to copy through a stack buffer whenever fewer than
+//! 8 bytes of a key remain. [`KeyArena`] stores all keys back to back in a single buffer
+//! followed by [`SPLICE_PREFIX_SIZE`] zero bytes, so every prefix load is one unaligned
+//! 8-byte read (masked to the key's length), and sorting touches one allocation instead
+//! of one per key.
+
+use crate::algo::orasort;
+use crate::core::{KeyAccessor, SPLICE_PREFIX_SIZE};
+use crate::permutation::Permutation;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+pub(crate) mod sealed {
+    pub trait Sealed {
+        /// Size of a stored offset in bytes.
+        const WIDTH: usize;
+
+        /// Reads offset `index` of a little-endian offset table.
+        fn read_le(table: &[u8], index: usize) -> u64;
+    }
+
+    impl Sealed for u32 {
+        const WIDTH: usize = 4;
+
+        #[inline(always)]
+        fn read_le(table: &[u8], index: usize) -> u64 {
+            let at = index * 4;
+            u64::from(u32::from_le_bytes(table[at..at + 4].try_into().unwrap()))
+        }
+    }
+
+    impl Sealed for u64 {
+        const WIDTH: usize = 8;
+
+        #[inline(always)]
+        fn read_le(table: &[u8], index: usize) -> u64 {
+            let at = index * 8;
+            u64::from_le_bytes(table[at..at + 8].try_into().unwrap())
+        }
+    }
+}
+
+/// Integer type of a [`KeyArena`]'s offset table: `u32` or `u64`.
+///
+/// `u32` offsets halve the table's size but limit the arena to 4 GiB of key bytes. The
+/// same types select the width of on-disk offset tables read by `MmapKeys`.
+pub trait ArenaOffset: sealed::Sealed + Copy + Debug + Eq {
+    /// Converts a byte position, or returns `None` if it does not fit.
+    fn from_usize(value: usize) -> Option<Self>;
+
+    /// Converts back to a byte position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offset does not fit `usize`, which offsets made by
+    /// [`from_usize`](Self::from_usize) always do.
+    fn to_usize(self) -> usize;
+}
+
+impl ArenaOffset for u32 {
+    #[inline(always)]
+    fn from_usize(value: usize) -> Option<Self> {
+        u32::try_from(value).ok()
+    }
+
+    #[inline(always)]
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+}
+
+impl ArenaOffset for u64 {
+    #[inline(always)]
+    fn from_usize(value: usize) -> Option<Self> {
+        u64::try_from(value).ok()
+    }
+
+    #[inline(always)]
+    fn to_usize(self) -> usize {
+        // Only fails on targets with a `usize` narrower than 64 bits.
+        usize::try_from(self).expect("key arena offset exceeds usize")
+    }
+}
+
+/// Keys stored back to back in one zero-padded buffer, addressed by an offset table.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::{KeyArena, orasort};
+///
+/// let arena: KeyArena = ["pear", "fig", "apple"].into_iter().collect();
+/// let sorted = arena.sorted();
+///
+/// assert_eq!(sorted.iter().collect::<Vec<_>>(), vec![&b"apple"[..], b"fig", b"pear"]);
+/// assert_eq!(orasort(&arena), vec![2, 1, 0]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyArena<O: ArenaOffset = u32> {
+    /// All keys, followed by `SPLICE_PREFIX_SIZE` zero bytes.
+    bytes: Vec<u8>,
+    /// `offsets[i]..offsets[i + 1]` is key `i`; starts with `0`.
+    offsets: Vec<O>,
+}
+
+impl<O: ArenaOffset> Default for KeyArena<O> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O: ArenaOffset> KeyArena<O> {
+    /// Creates an empty arena.
+    pub fn new() -> Self {
+        Self::with_capacity(0, 0)
+    }
+
+    /// Creates an arena with room for `keys` keys of `bytes` total length.
+    pub fn with_capacity(keys: usize, bytes: usize) -> Self {
+        let mut arena = Self {
+            bytes: Vec::with_capacity(bytes + SPLICE_PREFIX_SIZE),
+            offsets: Vec::with_capacity(keys + 1),
+        };
+        arena.bytes.resize(SPLICE_PREFIX_SIZE, 0);
+        arena.offsets.extend(O::from_usize(0));
+        arena
+    }
+
+    /// Returns the number of keys.
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Returns `true` if the arena holds no keys.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the total length of all keys in bytes.
+    pub fn key_bytes(&self) -> usize {
+        self.bytes.len() - SPLICE_PREFIX_SIZE
+    }
+
+    /// Appends a key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key bytes would no longer be addressable by `O`; use `KeyArena<u64>`
+    /// for more than 4 GiB of keys.
+    pub fn push(&mut self, key: &[u8]) {
+        let start = self.key_bytes();
+        let end = O::from_usize(start + key.len()).expect("key arena offset overflow");
+
+        self.bytes.truncate(start);
+        self.bytes.extend_from_slice(key);
+        self.bytes.resize(start + key.len() + SPLICE_PREFIX_SIZE, 0);
+        self.offsets.push(end);
+    }
+
+    /// Returns the key at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        (index < self.len()).then(|| self.key(index))
+    }
+
+    /// Iterates over the keys in storage order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &[u8]> + '_ {
+        (0..self.len()).map(|index| self.key(index))
+    }
+
+    /// Returns a new arena holding the keys in the order given by `permutation`.
+    ///
+    /// Key `i` of the result is key `permutation[i]` of `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `permutation.len() != self.len()`.
+    pub fn permuted(&self, permutation: &Permutation) -> Self {
+        assert_eq!(
+            permutation.len(),
+            self.len(),
+            "arena and permutation must have the same length"
+        );
+
+        let mut arena = Self::with_capacity(self.len(), self.key_bytes());
+        arena.bytes.clear();
+        for &index in permutation.iter() {
+            arena.bytes.extend_from_slice(self.key(index));
+            // Offsets are bounded by our own, which fit into `O`.
+            arena.offsets.extend(O::from_usize(arena.bytes.len()));
+        }
+        arena.bytes.extend_from_slice(&[0; SPLICE_PREFIX_SIZE]);
+        arena
+    }
+
+    /// Returns a new arena whose keys are physically stored in sorted order.
+    pub fn sorted(&self) -> Self {
+        self.permuted(&orasort(self))
+    }
+
+    #[inline(always)]
+    fn bounds(&self, index: usize) -> (usize, usize) {
+        (
+            self.offsets[index].to_usize(),
+            self.offsets[index + 1].to_usize(),
+        )
+    }
+
+    #[inline(always)]
+    fn key(&self, index: usize) -> &[u8] {
+        let (start, end) = self.bounds(index);
+        &self.bytes[start..end]
+    }
+}
+
+impl<O: ArenaOffset> KeyAccessor for KeyArena<O> {
+    #[inline(always)]
+    fn get_key(&self, index: usize) -> &[u8] {
+        self.key(index)
+    }
+
+    fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    #[inline(always)]
+    fn get_u64_prefix(&self, index: usize, offset: usize) -> u64 {
+        let (start, end) = self.bounds(index);
+        let remaining = (end - start).saturating_sub(offset);
+        if remaining == 0 {
+            return 0;
+        }
+
+        let at = start + offset;
+        debug_assert!(at + SPLICE_PREFIX_SIZE <= self.bytes.len());
+        // SAFETY: `at < end`, and the buffer always extends `SPLICE_PREFIX_SIZE` bytes past
+        // the end of the last key.
+        let raw = unsafe { core::ptr::read_unaligned(self.bytes.as_ptr().add(at) as *const u64) };
+        let value = u64::from_be(raw);
+
+        // Bytes past the end of the key belong to the next key (or the padding).
+        if remaining >= SPLICE_PREFIX_SIZE {
+            value
+        } else {
+            value & !(u64::MAX >> (remaining * 8))
+        }
+    }
+}
+
+impl<O: ArenaOffset, K: AsRef<[u8]>> Extend<K> for KeyArena<O> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|key| self.push(key.as_ref()));
+    }
+}
+
+impl<O: ArenaOffset, K: AsRef<[u8]>> FromIterator<K> for KeyArena<O> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut arena = Self::new();
+        arena.extend(iter);
+        arena
+    }
+}
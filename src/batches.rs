@@ -0,0 +1,550 @@
+//! Incrementally maintained sorted collections.
+//!
+//! [`SortedBatches`] is a log-structured container for append-heavy workloads: every
+//! incoming batch is sorted once with [`orasort_mut`] and stored as a
+//! new sorted *level*. Adjacent levels of similar size are merged, so the collection
+//! consists of a few sorted runs whose sizes roughly double from the newest to the oldest.
+//! Each item takes part in `O(log n)` merges over its lifetime, instead of being re-sorted
+//! with every batch.
+//!
+//! Merges run in the background of inserts: every insert moves a number of keys
+//! proportional to its batch size through each merge in progress, so no insert pays for
+//! merging large levels at once. The levels of a merge in progress stay searchable, as its
+//! merged part and the rest of both levels. [`SortedBatches::compact`] finishes all merges.
+//!
+//! Every level keeps the 8-byte prefix of each of its keys next to them, as the sort does.
+//! Merges, lookups and ordered iteration compare those `u64`s first and only compare
+//! full keys on ties. Lookups binary-search every sorted run; ordered iteration merges the
+//! runs on the fly.
+
+use crate::algo::orasort_mut;
+use crate::core::{SPLICE_PREFIX_SIZE, key_prefix};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ops::Range;
+
+/// Adjacent levels are merged when the older one is less than `LEVEL_RATIO` times the
+/// size of the newer one.
+const LEVEL_RATIO: usize = 2;
+
+/// Number of keys every merge in progress moves per inserted key.
+///
+/// A merge moves less than `LEVEL_RATIO + 1` times as many keys as its newer level holds,
+/// and building a level of that size next to it takes as many inserted keys, so merges
+/// finish before their result is due to merge again.
+const MERGE_STEPS_PER_KEY: usize = 2 * LEVEL_RATIO;
+
+/// A sorted multiset of byte keys built from batches.
+///
+/// Keys are ordered bytewise by their `AsRef<[u8]>` representation. Equal keys are kept
+/// (this is a multiset); equal keys from different batches iterate in insertion order,
+/// while their order within one batch is unspecified. Every key takes 8 bytes for its
+/// cached prefix on top of its own size.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::SortedBatches;
+///
+/// let mut events = SortedBatches::new();
+/// events.insert_batch(vec!["2024-03-02", "2024-01-15"]);
+/// events.insert_batch(vec!["2024-02-11", "2024-01-01", "2024-03-30"]);
+///
+/// assert!(events.contains(b"2024-02-11"));
+///
+/// let january: Vec<_> = events.prefix_iter(b"2024-01").collect();
+/// assert_eq!(january, vec![&"2024-01-01", &"2024-01-15"]);
+///
+/// let all: Vec<_> = events.iter().copied().collect();
+/// assert_eq!(all, vec!["2024-01-01", "2024-01-15", "2024-02-11", "2024-03-02", "2024-03-30"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SortedBatches<K> {
+    /// Levels and merges in progress, oldest (and largest) first.
+    slots: Vec<Slot<K>>,
+    len: usize,
+}
+
+impl<K> Default for SortedBatches<K> {
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<K: AsRef<[u8]>> SortedBatches<K> {
+    /// Creates an empty collection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of keys in the collection.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the collection holds no keys.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of sorted levels currently held, counting both levels of every
+    /// merge in progress.
+    pub fn level_count(&self) -> usize {
+        self.slots
+            .iter()
+            .map(|slot| match slot {
+                Slot::Level(_) => 1,
+                Slot::Merging(_) => 2,
+            })
+            .sum()
+    }
+
+    /// Sorts `batch` and adds it to the collection as a new level.
+    ///
+    /// Levels due to merge start merging, and every merge in progress moves on by a few
+    /// keys per key of `batch`, so an insert costs `O(log n)` per key. A merge that is not
+    /// done yet keeps going with the next inserts; [`compact`](Self::compact) finishes it.
+    pub fn insert_batch(&mut self, mut batch: Vec<K>) {
+        if batch.is_empty() {
+            return;
+        }
+        orasort_mut(&mut batch);
+        let steps = MERGE_STEPS_PER_KEY * batch.len();
+        self.len += batch.len();
+        self.slots.push(Slot::Level(Level::new(batch)));
+
+        self.start_merges();
+        for slot in &mut self.slots {
+            slot.advance(steps);
+        }
+        self.start_merges();
+    }
+
+    /// Adds a single key to the collection (see [`insert_batch`](Self::insert_batch)).
+    pub fn insert(&mut self, key: K) {
+        self.insert_batch(vec![key]);
+    }
+
+    /// Finishes all merges in progress and merges all levels into one, making lookups a
+    /// single binary search.
+    pub fn compact(&mut self) {
+        let mut levels: Vec<Level<K>> = core::mem::take(&mut self.slots)
+            .into_iter()
+            .map(Slot::into_level)
+            .collect();
+        while levels.len() > 1 {
+            let newer = levels.pop().unwrap_or_default();
+            let older = levels.pop().unwrap_or_default();
+            levels.push(merge_levels(older, newer));
+        }
+        self.slots.extend(levels.pop().map(Slot::Level));
+    }
+
+    /// Returns `true` if some key equals `key`.
+    pub fn contains(&self, key: &[u8]) -> bool {
+        let probe = key_prefix(key, 0);
+        self.runs()
+            .any(|run| !run.equal_range(key, probe).is_empty())
+    }
+
+    /// Returns the number of keys equal to `key`.
+    pub fn count(&self, key: &[u8]) -> usize {
+        let probe = key_prefix(key, 0);
+        self.runs()
+            .map(|run| run.equal_range(key, probe).len())
+            .sum()
+    }
+
+    /// Returns a key equal to `key` from the most recent batch that contains one.
+    pub fn get(&self, key: &[u8]) -> Option<&K> {
+        let probe = key_prefix(key, 0);
+        self.runs()
+            .rev()
+            .find_map(|run| run.keys[run.equal_range(key, probe)].last())
+    }
+
+    /// Iterates over all keys in sorted order.
+    pub fn iter(&self) -> Iter<'_, K> {
+        Iter::new(self.runs().collect())
+    }
+
+    /// Iterates, in sorted order, over the keys starting with `prefix`.
+    pub fn prefix_iter(&self, prefix: &[u8]) -> Iter<'_, K> {
+        let probe = key_prefix(prefix, 0);
+        Iter::new(
+            self.runs()
+                .map(|run| run.slice(run.prefix_range(prefix, probe)))
+                .collect(),
+        )
+    }
+
+    /// Consumes the collection, returning all keys in sorted order.
+    pub fn into_sorted_vec(mut self) -> Vec<K> {
+        self.compact();
+        self.slots
+            .pop()
+            .map(Slot::into_level)
+            .unwrap_or_default()
+            .keys
+    }
+
+    /// Starts merging every pair of adjacent levels, newest first, whose older level is
+    /// less than [`LEVEL_RATIO`] times the size of the newer one. Levels that take part in
+    /// a merge in progress wait for it to finish.
+    fn start_merges(&mut self) {
+        let mut at = self.slots.len();
+        while at > 1 {
+            at -= 1;
+            let (Slot::Level(older), Slot::Level(newer)) = (&self.slots[at - 1], &self.slots[at])
+            else {
+                continue;
+            };
+            if older.len() >= LEVEL_RATIO * newer.len() {
+                continue;
+            }
+
+            let newer = self.slots.remove(at).into_level();
+            let older = self.slots.remove(at - 1).into_level();
+            self.slots
+                .insert(at - 1, Slot::Merging(Merge::new(older, newer)));
+        }
+    }
+
+    /// Returns the sorted runs of the collection, oldest first.
+    ///
+    /// Equal keys of different runs were inserted in the order of their runs.
+    fn runs(&self) -> impl DoubleEndedIterator<Item = Run<'_, K>> {
+        self.slots
+            .iter()
+            .flat_map(|slot| match slot {
+                Slot::Level(level) => [Some(level.run()), None, None],
+                Slot::Merging(merge) => merge.runs().map(Some),
+            })
+            .flatten()
+    }
+}
+
+impl<K: AsRef<[u8]>> Extend<K> for SortedBatches<K> {
+    /// Inserts all keys of `iter` as one batch.
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        self.insert_batch(iter.into_iter().collect());
+    }
+}
+
+impl<K: AsRef<[u8]>> FromIterator<K> for SortedBatches<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut batches = Self::new();
+        batches.extend(iter);
+        batches
+    }
+}
+
+impl<'a, K: AsRef<[u8]>> IntoIterator for &'a SortedBatches<K> {
+    type Item = &'a K;
+    type IntoIter = Iter<'a, K>;
+
+    fn into_iter(self) -> Iter<'a, K> {
+        self.iter()
+    }
+}
+
+/// Sorted iterator over the keys of a [`SortedBatches`], merging its levels on the fly.
+///
+/// Created by [`SortedBatches::iter`] and [`SortedBatches::prefix_iter`].
+#[derive(Clone, Debug)]
+pub struct Iter<'a, K> {
+    /// Remaining part of every level, oldest first.
+    runs: Vec<Run<'a, K>>,
+}
+
+impl<'a, K: AsRef<[u8]>> Iter<'a, K> {
+    fn new(mut runs: Vec<Run<'a, K>>) -> Self {
+        runs.retain(|run| !run.keys.is_empty());
+        Self { runs }
+    }
+}
+
+impl<'a, K: AsRef<[u8]>> Iterator for Iter<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        // There are only `O(log n)` levels, so a linear scan for the minimum is cheap.
+        // On ties the oldest level wins, which keeps equal keys in insertion order.
+        let mut best = 0;
+        for (run, candidate) in self.runs.iter().enumerate().skip(1) {
+            if candidate.compare_first(&self.runs[best]) == Ordering::Less {
+                best = run;
+            }
+        }
+
+        let run = self.runs.get_mut(best)?;
+        let (first, rest) = run.keys.split_first()?;
+        if rest.is_empty() {
+            self.runs.remove(best);
+        } else {
+            run.keys = rest;
+            run.prefixes = &run.prefixes[1..];
+        }
+        Some(first)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.runs.iter().map(|run| run.keys.len()).sum();
+        (len, Some(len))
+    }
+}
+
+impl<K: AsRef<[u8]>> ExactSizeIterator for Iter<'_, K> {}
+
+/// A level, or two adjacent levels being merged.
+#[derive(Clone, Debug)]
+enum Slot<K> {
+    Level(Level<K>),
+    Merging(Merge<K>),
+}
+
+impl<K: AsRef<[u8]>> Slot<K> {
+    /// Moves a merge in progress on by up to `steps` keys, settling it once it is done.
+    fn advance(&mut self, steps: usize) {
+        if let Slot::Merging(merge) = self {
+            merge.advance(steps);
+            if merge.is_done() {
+                let merged = core::mem::take(&mut merge.merged);
+                *self = Slot::Level(merged);
+            }
+        }
+    }
+
+    /// Returns the level, finishing a merge in progress first.
+    fn into_level(self) -> Level<K> {
+        match self {
+            Slot::Level(level) => level,
+            Slot::Merging(mut merge) => {
+                merge.advance(usize::MAX);
+                merge.merged
+            }
+        }
+    }
+}
+
+/// A sorted run of keys with the 8-byte prefix of every key.
+#[derive(Clone, Debug)]
+struct Level<K> {
+    keys: Vec<K>,
+    prefixes: Vec<u64>,
+}
+
+// Derived `Default` would require `K: Default`.
+impl<K> Default for Level<K> {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new(),
+            prefixes: Vec::new(),
+        }
+    }
+}
+
+impl<K: AsRef<[u8]>> Level<K> {
+    /// Wraps the sorted `keys`, loading their prefixes.
+    fn new(keys: Vec<K>) -> Self {
+        let prefixes = keys.iter().map(|key| key_prefix(key.as_ref(), 0)).collect();
+        Self { keys, prefixes }
+    }
+
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn run(&self) -> Run<'_, K> {
+        Run {
+            keys: &self.keys,
+            prefixes: &self.prefixes,
+        }
+    }
+}
+
+impl<K> Extend<(K, u64)> for Level<K> {
+    fn extend<I: IntoIterator<Item = (K, u64)>>(&mut self, iter: I) {
+        for (key, prefix) in iter {
+            self.keys.push(key);
+            self.prefixes.push(prefix);
+        }
+    }
+}
+
+/// Two adjacent levels being merged, a bounded number of keys at a time.
+///
+/// At any time, `merged` holds the smallest keys of both levels and the rest of them wait
+/// in `older` and `newer`.
+#[derive(Clone, Debug)]
+struct Merge<K> {
+    merged: Level<K>,
+    older: Unmerged<K>,
+    newer: Unmerged<K>,
+}
+
+impl<K: AsRef<[u8]>> Merge<K> {
+    fn new(older: Level<K>, newer: Level<K>) -> Self {
+        let len = older.len() + newer.len();
+        Self {
+            merged: Level {
+                keys: Vec::with_capacity(len),
+                prefixes: Vec::with_capacity(len),
+            },
+            older: Unmerged::new(older),
+            newer: Unmerged::new(newer),
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.older.keys.len() == 0 && self.newer.keys.len() == 0
+    }
+
+    /// Moves up to `steps` keys to `merged`. On ties, keys of `older` go first.
+    fn advance(&mut self, steps: usize) {
+        for _ in 0..steps {
+            let (older, newer) = (self.older.run(), self.newer.run());
+            let source = match (older.keys.is_empty(), newer.keys.is_empty()) {
+                (true, true) => return,
+                (true, false) => &mut self.newer,
+                (false, true) => &mut self.older,
+                (false, false) if newer.compare_first(&older) == Ordering::Less => &mut self.newer,
+                (false, false) => &mut self.older,
+            };
+            self.merged.extend(source.next());
+        }
+    }
+
+    /// Returns the merged keys and the rest of both levels.
+    ///
+    /// Equal keys are in the order they were inserted in across the three runs: a key of
+    /// `newer` is only merged once all equal keys of `older` are.
+    fn runs(&self) -> [Run<'_, K>; 3] {
+        [self.merged.run(), self.older.run(), self.newer.run()]
+    }
+}
+
+/// The keys of a level that a merge has not reached yet.
+#[derive(Clone, Debug)]
+struct Unmerged<K> {
+    keys: vec::IntoIter<K>,
+    prefixes: vec::IntoIter<u64>,
+}
+
+impl<K> Unmerged<K> {
+    fn new(level: Level<K>) -> Self {
+        Self {
+            keys: level.keys.into_iter(),
+            prefixes: level.prefixes.into_iter(),
+        }
+    }
+
+    fn run(&self) -> Run<'_, K> {
+        Run {
+            keys: self.keys.as_slice(),
+            prefixes: self.prefixes.as_slice(),
+        }
+    }
+}
+
+impl<K> Iterator for Unmerged<K> {
+    type Item = (K, u64);
+
+    fn next(&mut self) -> Option<(K, u64)> {
+        Some((self.keys.next()?, self.prefixes.next()?))
+    }
+}
+
+/// A sorted run of keys with their prefixes, borrowed from a level.
+#[derive(Debug)]
+struct Run<'a, K> {
+    keys: &'a [K],
+    prefixes: &'a [u64],
+}
+
+// Derived `Clone` would require `K: Clone`.
+impl<K> Clone for Run<'_, K> {
+    fn clone(&self) -> Self {
+        Self {
+            keys: self.keys,
+            prefixes: self.prefixes,
+        }
+    }
+}
+
+impl<'a, K: AsRef<[u8]>> Run<'a, K> {
+    fn slice(&self, range: Range<usize>) -> Self {
+        Run {
+            keys: &self.keys[range.clone()],
+            prefixes: &self.prefixes[range],
+        }
+    }
+
+    /// Compares the first keys of two non-empty runs.
+    fn compare_first(&self, other: &Self) -> Ordering {
+        compare_keys(
+            self.prefixes[0],
+            self.keys[0].as_ref(),
+            other.prefixes[0],
+            other.keys[0].as_ref(),
+        )
+    }
+
+    /// Returns the range of keys equal to `key`, whose prefix is `probe`.
+    fn equal_range(&self, key: &[u8], probe: u64) -> Range<usize> {
+        let compare = |i: usize| compare_keys(self.prefixes[i], self.keys[i].as_ref(), probe, key);
+        let start = partition_point(0..self.keys.len(), |i| compare(i) == Ordering::Less);
+        let end = partition_point(start..self.keys.len(), |i| compare(i) != Ordering::Greater);
+        start..end
+    }
+
+    /// Returns the range of keys starting with `prefix`, whose own prefix is `probe`.
+    fn prefix_range(&self, prefix: &[u8], probe: u64) -> Range<usize> {
+        let mask = match prefix.len() {
+            0 => 0,
+            len if len >= SPLICE_PREFIX_SIZE => u64::MAX,
+            len => !(u64::MAX >> (len * 8)),
+        };
+        let start = partition_point(0..self.keys.len(), |i| {
+            compare_keys(self.prefixes[i], self.keys[i].as_ref(), probe, prefix) == Ordering::Less
+        });
+        let end = partition_point(start..self.keys.len(), |i| {
+            let key = self.keys[i].as_ref();
+            let truncated = &key[..key.len().min(prefix.len())];
+            compare_keys(self.prefixes[i] & mask, truncated, probe & mask, prefix)
+                != Ordering::Greater
+        });
+        start..end
+    }
+}
+
+/// Compares two keys given with their prefixes, loading the keys only on a tie.
+#[inline(always)]
+fn compare_keys(prefix_a: u64, a: &[u8], prefix_b: u64, b: &[u8]) -> Ordering {
+    prefix_a.cmp(&prefix_b).then_with(|| a.cmp(b))
+}
+
+/// Returns the first index of `range` for which `pred` is false; `pred` must be monotone.
+fn partition_point(range: Range<usize>, mut pred: impl FnMut(usize) -> bool) -> usize {
+    let (mut low, mut high) = (range.start, range.end);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if pred(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+/// Stable merge of two sorted levels; on ties, keys of `older` come first.
+fn merge_levels<K: AsRef<[u8]>>(older: Level<K>, newer: Level<K>) -> Level<K> {
+    let mut merge = Merge::new(older, newer);
+    merge.advance(usize::MAX);
+    merge.merged
+}
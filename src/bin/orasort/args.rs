@@ -0,0 +1,208 @@
+//! Command-line argument parsing.
+
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: orasort [OPTION]... [FILE]...
+Write the sorted concatenation of all FILEs to standard output.
+With no FILE, or when FILE is -, read standard input.
+
+Lines are compared bytewise, as by `sort` with LC_ALL=C.
+
+Ordering options:
+  -b, --ignore-leading-blanks  ignore leading blanks
+  -f, --ignore-case         fold lower case to upper case characters
+  -r, --reverse             reverse the result of comparisons
+
+Other options:
+  -c, --check               check whether input is sorted; do not sort
+  -m, --merge               merge already sorted files; do not sort
+  -k, --key=KEYDEF          sort via a key; KEYDEF gives location and type
+  -s, --stable              stabilize sort by disabling last-resort comparison
+  -t, --field-separator=SEP use SEP instead of non-blank to blank transition
+  -u, --unique              output only the first of a run of equal lines
+  -z, --zero-terminated     line delimiter is NUL, not newline
+  -o, --output=FILE         write result to FILE instead of standard output
+      --parallel=N          sort with N threads
+  -h, --help                display this help and exit
+  -V, --version             output version information and exit
+
+KEYDEF is F[.C][OPTS][,F[.C][OPTS]] for start and stop position, where F is a
+field number and C a character position in the field; both are origin 1, and
+the stop position defaults to the line's end. OPTS is one or more of b, f, r,
+which override global ordering options for that key.
+";
+
+/// Parsed command-line options.
+#[derive(Debug, Default)]
+pub struct Options {
+    pub reverse: bool,
+    pub unique: bool,
+    pub zero_terminated: bool,
+    /// Only check that the input is sorted.
+    pub check: bool,
+    /// Merge inputs that are already sorted.
+    pub merge: bool,
+    pub output: Option<PathBuf>,
+    pub parallel: Option<NonZeroUsize>,
+    /// Key selection options (`-b`, `-f`, `-k`, `-s`, `-t`), handed to `KeySpec`.
+    pub keys: Vec<String>,
+    /// Input files; `-` is standard input. Empty means standard input.
+    pub inputs: Vec<OsString>,
+}
+
+/// What the command line asks for.
+#[derive(Debug)]
+pub enum Command {
+    Sort(Options),
+    Help,
+    Version,
+}
+
+/// A command-line usage error.
+#[derive(Debug)]
+pub struct UsageError(String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Options {
+    /// Returns the line delimiter byte.
+    pub fn delimiter(&self) -> u8 {
+        if self.zero_terminated { b'\0' } else { b'\n' }
+    }
+}
+
+/// Parses the arguments following the program name.
+pub fn parse<I: IntoIterator<Item = OsString>>(args: I) -> Result<Command, UsageError> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let Some(text) = arg.to_str() else {
+            options.inputs.push(arg);
+            continue;
+        };
+
+        if text == "--" {
+            options.inputs.extend(args.by_ref());
+            break;
+        }
+
+        if let Some(long) = text.strip_prefix("--") {
+            let (name, inline) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (long, None),
+            };
+            let mut value = |name: &str| -> Result<String, UsageError> {
+                inline
+                    .clone()
+                    .or_else(|| args.next().and_then(|v| v.into_string().ok()))
+                    .ok_or_else(|| UsageError(format!("option '--{}' requires an argument", name)))
+            };
+
+            match name {
+                "reverse" => options.reverse = true,
+                "check" => options.check = true,
+                "merge" => options.merge = true,
+                "ignore-leading-blanks" | "ignore-case" | "stable" => {
+                    options.keys.push(format!("--{}", name));
+                }
+                "key" | "field-separator" => {
+                    options.keys.push(format!("--{}={}", name, value(name)?));
+                }
+                "unique" => options.unique = true,
+                "zero-terminated" => options.zero_terminated = true,
+                "output" => options.output = Some(PathBuf::from(value(name)?)),
+                "parallel" => options.parallel = Some(parse_threads(&value(name)?)?),
+                "help" => return Ok(Command::Help),
+                "version" => return Ok(Command::Version),
+                _ => return Err(UsageError(format!("unrecognized option '--{}'", name))),
+            }
+            continue;
+        }
+
+        if text.len() > 1 && text.starts_with('-') {
+            let flags = &text[1..];
+            for (at, flag) in flags.char_indices() {
+                match flag {
+                    'r' => options.reverse = true,
+                    'c' => options.check = true,
+                    'm' => options.merge = true,
+                    'u' => options.unique = true,
+                    'b' | 'f' | 's' => options.keys.push(format!("-{}", flag)),
+                    'z' => options.zero_terminated = true,
+                    'h' => return Ok(Command::Help),
+                    'V' => return Ok(Command::Version),
+                    'o' | 'k' | 't' => {
+                        // The rest of this argument, or the next one, is the value.
+                        let rest = &flags[at + 1..];
+                        let value = if rest.is_empty() {
+                            args.next().ok_or_else(|| {
+                                UsageError(format!("option requires an argument -- '{}'", flag))
+                            })?
+                        } else {
+                            OsString::from(rest)
+                        };
+                        if flag == 'o' {
+                            options.output = Some(PathBuf::from(value));
+                        } else {
+                            let value = value.into_string().map_err(|_| {
+                                UsageError(format!("invalid argument for -- '{}'", flag))
+                            })?;
+                            options.keys.push(format!("-{}", flag));
+                            options.keys.push(value);
+                        }
+                        break;
+                    }
+                    _ => return Err(UsageError(format!("invalid option -- '{}'", flag))),
+                }
+            }
+            continue;
+        }
+
+        options.inputs.push(arg);
+    }
+
+    if options.check && options.merge {
+        return Err(UsageError("options '-cm' are incompatible".to_string()));
+    }
+    // Merging reads its inputs side by side, and standard input can only be read once.
+    if options.merge
+        && options
+            .inputs
+            .iter()
+            .filter(|input| is_stdin(input))
+            .count()
+            > 1
+    {
+        return Err(UsageError(
+            "standard input ('-') given more than once with -m".to_string(),
+        ));
+    }
+    if options.check && options.inputs.len() > 1 {
+        return Err(UsageError(format!(
+            "extra operand '{}' not allowed with -c",
+            options.inputs[1].to_string_lossy()
+        )));
+    }
+
+    Ok(Command::Sort(options))
+}
+
+fn parse_threads(value: &str) -> Result<NonZeroUsize, UsageError> {
+    value
+        .parse()
+        .map_err(|_| UsageError(format!("invalid number of threads: '{}'", value)))
+}
+
+/// Returns `true` if `input` names standard input.
+pub fn is_stdin(input: &OsStr) -> bool {
+    input == "-"
+}
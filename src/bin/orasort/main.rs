@@ -0,0 +1,496 @@
+//! `orasort`: sort lines of text files bytewise.
+//!
+//! A drop-in replacement for `LC_ALL=C sort` on line-oriented data, including key
+//! selection with `-t` and `-k`, and the check (`-c`) and merge (`-m`) modes. File inputs
+//! are memory-mapped and lines are sorted as slices into the mapping, so no line is copied
+//! or allocated individually. Merging streams its inputs instead, with bounded memory.
+
+mod args;
+
+use args::{Command, Options};
+use memmap2::Mmap;
+use orasort::keyspec::KeySpec;
+use orasort::lines::{Delimiter, Lines, TrailingDelimiter};
+use orasort::merge::LineMerger;
+use orasort::{ConcatMany, KeyAccessor, Permutation, Subset, check_sorted, orasort};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::ops::Deref;
+use std::path::Path;
+use std::process::ExitCode;
+use std::thread;
+
+/// Inputs with fewer lines are always sorted on the calling thread.
+const PARALLEL_MIN_LINES: usize = 1 << 16;
+
+/// Upper bound on the default number of sort threads.
+const DEFAULT_MAX_THREADS: usize = 8;
+
+/// Lines of all inputs, in input order.
+type InputLines<'a> = ConcatMany<Lines<'a>>;
+
+/// Size of the output buffer.
+const OUTPUT_BUFFER: usize = 1 << 20;
+
+fn main() -> ExitCode {
+    let command = match args::parse(std::env::args_os().skip(1)) {
+        Ok(command) => command,
+        Err(error) => {
+            eprintln!("orasort: {}", error);
+            eprintln!("Try 'orasort --help' for more information.");
+            return ExitCode::from(2);
+        }
+    };
+
+    let options = match command {
+        Command::Help => {
+            print!("{}", args::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Command::Version => {
+            println!("orasort {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Command::Sort(options) => options,
+    };
+
+    match run(&options) {
+        Ok(code) => code,
+        // The reader went away (e.g. `orasort big.txt | head`); nothing left to do.
+        Err(error) if error.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("orasort: {}", error);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(options: &Options) -> io::Result<ExitCode> {
+    let order = Order::new(options)?;
+    if options.merge {
+        merge(options, &order)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let mut inputs = load_inputs(options)?;
+    if let Some(path) = &options.output {
+        unmap_output(&mut inputs, options, path);
+    }
+    let delimiter = options.delimiter();
+
+    let lines = ConcatMany::new(
+        inputs
+            .iter()
+            .map(|input| split_lines(input, delimiter))
+            .collect(),
+    );
+
+    if options.check {
+        return Ok(check(&lines, &order, options));
+    }
+
+    let sorted = sort_lines(&lines, &order, threads(options, lines.len()));
+
+    match &options.output {
+        Some(path) => write_lines(create_output(path)?, &lines, sorted, &order, options),
+        None => write_lines(io::stdout().lock(), &lines, sorted, &order, options),
+    }?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Reports the first line that is out of order, and fails if there is one.
+fn check(lines: &InputLines<'_>, order: &Order, options: &Options) -> ExitCode {
+    let disorder = match order {
+        Order::Bytes if !options.reverse && !options.unique => check_sorted(lines),
+        _ => (1..lines.len()).find(|&i| {
+            let ordering = order.compare_output(lines.get_key(i - 1), lines.get_key(i), options);
+            ordering.is_gt() || (options.unique && ordering.is_eq())
+        }),
+    };
+
+    let Some(index) = disorder else {
+        return ExitCode::SUCCESS;
+    };
+    let name = options
+        .inputs
+        .first()
+        .map_or("-".into(), |name| name.to_string_lossy());
+    eprintln!(
+        "orasort: {}:{}: disorder: {}",
+        name,
+        index + 1,
+        String::from_utf8_lossy(lines.get_key(index))
+    );
+    ExitCode::FAILURE
+}
+
+/// Streams the merge of the sorted inputs to the output.
+fn merge(options: &Options, order: &Order) -> io::Result<()> {
+    let readers = if options.inputs.is_empty() {
+        vec![Box::new(io::stdin().lock()) as Box<dyn BufRead>]
+    } else {
+        options
+            .inputs
+            .iter()
+            .map(|name| -> io::Result<Box<dyn BufRead>> {
+                if args::is_stdin(name) {
+                    return Ok(Box::new(io::stdin().lock()));
+                }
+                let path = Path::new(name);
+                let mut file = File::open(path).map_err(|error| annotate(error, path))?;
+                if options
+                    .output
+                    .as_deref()
+                    .is_some_and(|output| same_file(path, output))
+                {
+                    // The output is truncated before this input is read; read it now.
+                    let mut bytes = Vec::new();
+                    file.read_to_end(&mut bytes)
+                        .map_err(|error| annotate(error, path))?;
+                    return Ok(Box::new(io::Cursor::new(bytes)));
+                }
+                Ok(Box::new(BufReader::new(file)))
+            })
+            .collect::<io::Result<_>>()?
+    };
+
+    let mut merger = LineMerger::new()
+        .delimiter(options.delimiter())
+        .unique(options.unique)
+        .compare_by(|a: &[u8], b: &[u8]| order.compare_output(a, b, options));
+
+    match &options.output {
+        Some(path) => merger.merge(readers, create_output(path)?).map(drop),
+        None => merger.merge(readers, io::stdout().lock()).map(drop),
+    }
+}
+
+/// How lines are ordered.
+enum Order {
+    /// Whole lines, bytewise. `-r` is applied when writing the output.
+    Bytes,
+    /// Selected keys, including `-r`.
+    Keys(KeySpec),
+}
+
+impl Order {
+    fn new(options: &Options) -> io::Result<Self> {
+        if options.keys.is_empty() {
+            return Ok(Order::Bytes);
+        }
+
+        let mut args = options.keys.clone();
+        if options.reverse {
+            args.push("-r".to_string());
+        }
+        // As with `sort`, `-u` compares keys only and keeps the first of equal lines.
+        if options.unique {
+            args.push("-s".to_string());
+        }
+        KeySpec::from_args(args)
+            .map(Order::Keys)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))
+    }
+
+    fn sort<T: KeyAccessor + ?Sized>(&self, lines: &T) -> Permutation {
+        match self {
+            Order::Bytes => orasort(lines),
+            Order::Keys(spec) => spec.sort(lines),
+        }
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        match self {
+            Order::Bytes => a.cmp(b),
+            Order::Keys(spec) => spec.compare(a, b),
+        }
+    }
+
+    /// Returns `true` if `-u` treats `a` and `b` as duplicates.
+    fn duplicates(&self, a: &[u8], b: &[u8]) -> bool {
+        match self {
+            Order::Bytes => a == b,
+            Order::Keys(spec) => spec.keys_equal(a, b),
+        }
+    }
+
+    /// Returns `true` if the sorted lines are written back to front.
+    fn reverses_output(&self, options: &Options) -> bool {
+        matches!(self, Order::Bytes) && options.reverse
+    }
+
+    /// Compares two lines in the order they are written, i.e. including `-r`.
+    fn compare_output(&self, a: &[u8], b: &[u8], options: &Options) -> Ordering {
+        let ordering = self.compare(a, b);
+        if self.reverses_output(options) {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+/// Bytes of one input: a file mapping or a buffer read from a stream.
+enum Input {
+    Mapped(Mmap),
+    Read(Vec<u8>),
+}
+
+impl Deref for Input {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Input::Mapped(map) => map,
+            Input::Read(bytes) => bytes,
+        }
+    }
+}
+
+fn load_inputs(options: &Options) -> io::Result<Vec<Input>> {
+    if options.inputs.is_empty() {
+        return Ok(vec![read_stdin()?]);
+    }
+    options
+        .inputs
+        .iter()
+        .map(|name| {
+            if args::is_stdin(name) {
+                read_stdin()
+            } else {
+                load_file(name).map_err(|error| annotate(error, Path::new(name)))
+            }
+        })
+        .collect()
+}
+
+fn read_stdin() -> io::Result<Input> {
+    let mut bytes = Vec::new();
+    io::stdin().lock().read_to_end(&mut bytes)?;
+    Ok(Input::Read(bytes))
+}
+
+/// Maps regular files; anything else (pipes, devices, empty files) is read.
+fn load_file(name: &OsStr) -> io::Result<Input> {
+    let mut file = File::open(name)?;
+    let metadata = file.metadata()?;
+
+    if metadata.is_file() && metadata.len() > 0 {
+        // SAFETY: The mapping is only read. As with any memory-mapped input, concurrent
+        // modification of the file by another process would change the lines under us.
+        if let Ok(map) = unsafe { Mmap::map(&file) } {
+            return Ok(Input::Mapped(map));
+        }
+    }
+
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    Ok(Input::Read(bytes))
+}
+
+/// Splits `bytes` into lines. A missing final delimiter is tolerated.
+fn split_lines(bytes: &[u8], delimiter: u8) -> Lines<'_> {
+    let delimiter = if delimiter == b'\0' {
+        Delimiter::Nul
+    } else {
+        Delimiter::Newline
+    };
+    Lines::with_options(bytes, delimiter, TrailingDelimiter::Optional)
+}
+
+/// Number of threads to sort `lines` lines with.
+fn threads(options: &Options, lines: usize) -> usize {
+    let requested = match options.parallel {
+        Some(threads) => threads.get(),
+        None => thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(DEFAULT_MAX_THREADS),
+    };
+    if lines < PARALLEL_MIN_LINES {
+        1
+    } else {
+        requested.min(lines / (PARALLEL_MIN_LINES / 4))
+    }
+}
+
+/// Sorts `lines` by `order`, splitting the work across `threads` threads.
+fn sort_lines(lines: &InputLines<'_>, order: &Order, threads: usize) -> Permutation {
+    if threads <= 1 {
+        return order.sort(lines);
+    }
+
+    let chunk = lines.len().div_ceil(threads);
+    let runs: Vec<Vec<usize>> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..lines.len())
+            .step_by(chunk)
+            .map(|start| {
+                scope.spawn(move || {
+                    let end = (start + chunk).min(lines.len());
+                    let part = Subset::new(lines, (start..end).collect());
+                    let sorted = order.sort(&part);
+                    sorted.iter().map(|&i| part.indices()[i]).collect()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("sort thread panicked"))
+            .collect()
+    });
+
+    merge_runs(lines, runs, order)
+}
+
+/// K-way merge of sorted runs of line indices. Equal lines are taken from earlier runs
+/// first.
+fn merge_runs(lines: &InputLines<'_>, runs: Vec<Vec<usize>>, order: &Order) -> Permutation {
+    let mut merged = Vec::with_capacity(lines.len());
+
+    let mut cursors: Vec<_> = runs.iter().map(|run| run.iter()).collect();
+    let mut heap: BinaryHeap<Reverse<Head<'_, '_>>> = cursors
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(run, cursor)| {
+            let &index = cursor.next()?;
+            let line = lines.get_key(index);
+            Some(Reverse(Head {
+                line,
+                index,
+                run,
+                order,
+            }))
+        })
+        .collect();
+
+    while let Some(Reverse(head)) = heap.pop() {
+        merged.push(head.index);
+        if let Some(&index) = cursors[head.run].next() {
+            let line = lines.get_key(index);
+            heap.push(Reverse(Head {
+                line,
+                index,
+                ..head
+            }));
+        }
+    }
+
+    Permutation::try_from(merged).expect("sorted runs partition the lines")
+}
+
+/// The next line of a run during a merge.
+struct Head<'a, 'o> {
+    line: &'a [u8],
+    index: usize,
+    run: usize,
+    order: &'o Order,
+}
+
+impl Ord for Head<'_, '_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.order
+            .compare(self.line, other.line)
+            .then(self.run.cmp(&other.run))
+    }
+}
+
+impl PartialOrd for Head<'_, '_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Head<'_, '_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Head<'_, '_> {}
+
+fn write_lines<W: Write>(
+    mut out: W,
+    lines: &InputLines<'_>,
+    sorted: Permutation,
+    order: &Order,
+    options: &Options,
+) -> io::Result<()> {
+    let sorted = if order.reverses_output(options) {
+        let reversed: Vec<usize> = sorted.into_vec().into_iter().rev().collect();
+        Permutation::try_from(reversed).expect("reversed permutation")
+    } else {
+        sorted
+    };
+    let delimiter = [options.delimiter()];
+
+    // A single input is written straight from its buffer, in large chunks.
+    if let [input] = lines.sources()
+        && !options.unique
+    {
+        input.write_sorted(&sorted, &mut out)?;
+        // `sort` terminates the last line even if the input did not.
+        if !input.is_empty() && !input.data().ends_with(&delimiter) {
+            out.write_all(&delimiter)?;
+        }
+        return out.flush();
+    }
+
+    let mut out = BufWriter::with_capacity(OUTPUT_BUFFER, out);
+    let mut previous: Option<&[u8]> = None;
+    for &index in sorted.iter() {
+        let line = lines.get_key(index);
+        if options.unique && previous.is_some_and(|previous| order.duplicates(previous, line)) {
+            continue;
+        }
+        previous = Some(line);
+        out.write_all(line)?;
+        out.write_all(&delimiter)?;
+    }
+    out.flush()
+}
+
+/// Opens `path` for the result, truncating it in place like `sort -o` does. Symlinks,
+/// devices, and the permissions and owner of an existing file are left as they are.
+///
+/// Inputs must have been read, or detached from `path`, before this is called.
+fn create_output(path: &Path) -> io::Result<File> {
+    File::create(path).map_err(|error| annotate(error, path))
+}
+
+/// Copies mapped inputs that are the output file into memory, since truncating the output
+/// would pull the mapping out from under the lines.
+fn unmap_output(inputs: &mut [Input], options: &Options, output: &Path) {
+    for (input, name) in inputs.iter_mut().zip(&options.inputs) {
+        if let Input::Mapped(map) = input
+            && same_file(Path::new(name), output)
+        {
+            *input = Input::Read(map.to_vec());
+        }
+    }
+}
+
+/// Returns `true` if `a` and `b` are the same file, following symlinks.
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => same_inode(&a, &b),
+        _ => false,
+    }
+}
+
+#[cfg(unix)]
+fn same_inode(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+/// Without inode numbers, any existing output may be an input.
+#[cfg(not(unix))]
+fn same_inode(_: &fs::Metadata, _: &fs::Metadata) -> bool {
+    true
+}
+
+fn annotate(error: io::Error, path: &Path) -> io::Error {
+    io::Error::new(error.kind(), format!("{}: {}", path.display(), error))
+}
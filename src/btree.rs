@@ -0,0 +1,75 @@
+//! Bulk construction of `BTreeMap` and `BTreeSet` from unsorted input.
+//!
+//! Collecting into a B-tree sorts the input with a comparison sort before building the
+//! tree bottom-up. These helpers sort with Orasort first, so the standard library only
+//! sees presorted input, which it detects and builds in linear time.
+//!
+//! The key type's `Ord` implementation must agree with the bytewise order of its
+//! `AsRef<[u8]>` representation, as it does for `String`, `&str`, `Vec<u8>` and `&[u8]`.
+
+use crate::algo::{orasort, orasort_mut};
+use crate::core::KeyAccessor;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+
+/// Builds a `BTreeSet` from unsorted keys.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::btree::btree_set_from_unsorted;
+///
+/// let set = btree_set_from_unsorted(vec!["pear", "fig", "pear", "apple"]);
+///
+/// assert_eq!(set.into_iter().collect::<Vec<_>>(), vec!["apple", "fig", "pear"]);
+/// ```
+pub fn btree_set_from_unsorted<K: AsRef<[u8]> + Ord>(mut keys: Vec<K>) -> BTreeSet<K> {
+    orasort_mut(&mut keys);
+    keys.into_iter().collect()
+}
+
+/// Builds a `BTreeMap` from unsorted key-value pairs.
+///
+/// As with `BTreeMap::from_iter`, the last value wins if a key occurs more than once.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::btree::btree_map_from_unsorted;
+///
+/// let map = btree_map_from_unsorted(vec![("b", 1), ("a", 2), ("b", 3)]);
+///
+/// assert_eq!(map.into_iter().collect::<Vec<_>>(), vec![("a", 2), ("b", 3)]);
+/// ```
+pub fn btree_map_from_unsorted<K: AsRef<[u8]> + Ord, V>(entries: Vec<(K, V)>) -> BTreeMap<K, V> {
+    let order = orasort(&EntryKeys(&entries));
+
+    // The sort is unstable, so pick the last occurrence of every key explicitly.
+    let mut keep: Vec<usize> = Vec::with_capacity(order.len());
+    for &index in order.iter() {
+        match keep.last_mut() {
+            Some(last) if entries[*last].0.as_ref() == entries[index].0.as_ref() => {
+                *last = index.max(*last);
+            }
+            _ => keep.push(index),
+        }
+    }
+
+    let mut entries: Vec<Option<(K, V)>> = entries.into_iter().map(Some).collect();
+    keep.into_iter()
+        .filter_map(|index| entries[index].take())
+        .collect()
+}
+
+/// Exposes the keys of key-value pairs to the sort.
+struct EntryKeys<'a, K, V>(&'a [(K, V)]);
+
+impl<K: AsRef<[u8]>, V> KeyAccessor for EntryKeys<'_, K, V> {
+    fn get_key(&self, index: usize) -> &[u8] {
+        self.0[index].0.as_ref()
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
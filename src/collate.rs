@@ -0,0 +1,554 @@
+//! Pluggable collation (non-byte orderings).
+//!
+//! Plain byte order is rarely what a human expects from a sorted list. This module lets
+//! callers plug in an arbitrary ordering through the [`Collator`] trait, which maps a key to
+//! a *collation key*: a byte string whose byte order is the desired order.
+//!
+//! Collation keys are produced incrementally. The sort only asks for the first
+//! [`SPLICE_PREFIX_SIZE`] bytes of each collation key up
+//! front and caches them in the sort pointer, exactly like [`orasort`](crate::orasort) does
+//! with raw key bytes. Further collation bytes are generated only for keys whose cached
+//! prefixes tie, so expensive collations (e.g. the Unicode Collation Algorithm) never have
+//! to materialize full sort keys for every row.
+//!
+//! Generation is resumable: a collator keeps its position in a [`Collator::State`]. The
+//! sort keeps one for every key, together with a chunk of bytes generated ahead of where it
+//! last read the key, so radix passes that walk a long shared prefix generate every
+//! collation byte once and ties pick up where the radix passes stopped. Large inputs go
+//! through the same radix passes and presorted-run detection as byte keys.
+
+use crate::algo::{Scratch, SortKeys, sort_pointers};
+use crate::core::{KeyAccessor, SPLICE_PREFIX_SIZE, SortPtr, key_prefix};
+use crate::permutation::Permutation;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::cmp::Ordering;
+
+/// Number of collation bytes generated per step when resolving ties beyond the cache.
+const COLLATION_CHUNK: usize = 32;
+
+/// A trait for producing collation-key bytes for a key.
+///
+/// The collation key of `key` is a (conceptually infinite) byte stream that ends after a
+/// finite number of bytes. Two keys are ordered by comparing their collation keys
+/// lexicographically, with a shorter collation key ordering before any longer key it is a
+/// prefix of.
+///
+/// Implementations must be deterministic: generating the collation key of the same `key`
+/// from a fresh [`State`](Collator::State) must always produce the same bytes, however the
+/// output is split into calls.
+///
+/// # Examples
+///
+/// A collator that ignores ASCII case:
+///
+/// ```
+/// use orasort::collate::{Collator, orasort_collated};
+///
+/// struct IgnoreCase;
+///
+/// impl Collator for IgnoreCase {
+///     // Position in the key, which is also the position in the collation key.
+///     type State = usize;
+///
+///     fn collation_bytes(&self, key: &[u8], pos: &mut usize, out: &mut [u8]) -> usize {
+///         let tail = key.get(*pos..).unwrap_or(&[]);
+///         let n = tail.len().min(out.len());
+///         for (dst, src) in out.iter_mut().zip(&tail[..n]) {
+///             *dst = src.to_ascii_lowercase();
+///         }
+///         *pos += n;
+///         n
+///     }
+/// }
+///
+/// let data = vec!["banana", "Apple", "cherry"];
+/// let indices = orasort_collated(&data, &IgnoreCase);
+///
+/// assert_eq!(indices, vec![1, 0, 2]); // Apple, banana, cherry
+/// ```
+pub trait Collator {
+    /// Where generation of a collation key stopped. The default value is its start.
+    type State: Clone + Default;
+
+    /// Writes the next collation-key bytes of `key` into `out`, continuing where `state`
+    /// says, and advances `state` past them.
+    ///
+    /// Returns the number of bytes written. Returning fewer than `out.len()` bytes signals
+    /// that the collation key ends there; `0` means it has already ended.
+    fn collation_bytes(&self, key: &[u8], state: &mut Self::State, out: &mut [u8]) -> usize;
+
+    /// Advances `state` past the next `count` collation-key bytes of `key`.
+    ///
+    /// Returns the number of bytes skipped, which is less than `count` only if the collation
+    /// key ends first. The default generates the bytes and discards them; override it if
+    /// the collator can skip faster.
+    fn skip_collation_bytes(&self, key: &[u8], state: &mut Self::State, count: usize) -> usize {
+        let mut buf = [0u8; COLLATION_CHUNK];
+        let mut skipped = 0;
+        while skipped < count {
+            let want = (count - skipped).min(COLLATION_CHUNK);
+            let n = self.collation_bytes(key, state, &mut buf[..want]);
+            skipped += n;
+            if n < want {
+                break;
+            }
+        }
+        skipped
+    }
+
+    /// Returns the first 8 collation-key bytes as a big-endian `u64`, zero-padded.
+    ///
+    /// This is the value cached in the sort pointer. Override it if the collator can
+    /// produce the prefix faster than through a temporary buffer.
+    #[inline(always)]
+    fn collation_prefix(&self, key: &[u8]) -> u64 {
+        let mut buf = [0u8; SPLICE_PREFIX_SIZE];
+        self.collation_bytes(key, &mut Self::State::default(), &mut buf);
+        u64::from_be_bytes(buf)
+    }
+}
+
+impl<C: Collator + ?Sized> Collator for &C {
+    type State = C::State;
+
+    #[inline(always)]
+    fn collation_bytes(&self, key: &[u8], state: &mut C::State, out: &mut [u8]) -> usize {
+        (**self).collation_bytes(key, state, out)
+    }
+
+    #[inline(always)]
+    fn skip_collation_bytes(&self, key: &[u8], state: &mut C::State, count: usize) -> usize {
+        (**self).skip_collation_bytes(key, state, count)
+    }
+
+    #[inline(always)]
+    fn collation_prefix(&self, key: &[u8]) -> u64 {
+        (**self).collation_prefix(key)
+    }
+}
+
+/// The identity collation: collation keys are the raw key bytes.
+///
+/// Sorting with this collator produces the same order as [`orasort`](crate::orasort).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ByteCollator;
+
+impl Collator for ByteCollator {
+    /// Position in the key.
+    type State = usize;
+
+    #[inline(always)]
+    fn collation_bytes(&self, key: &[u8], pos: &mut usize, out: &mut [u8]) -> usize {
+        let tail = key.get(*pos..).unwrap_or(&[]);
+        let n = tail.len().min(out.len());
+        out[..n].copy_from_slice(&tail[..n]);
+        *pos += n;
+        n
+    }
+
+    #[inline(always)]
+    fn skip_collation_bytes(&self, key: &[u8], pos: &mut usize, count: usize) -> usize {
+        skip_key_bytes(key, pos, count)
+    }
+
+    #[inline(always)]
+    fn collation_prefix(&self, key: &[u8]) -> u64 {
+        key_prefix(key, 0)
+    }
+}
+
+/// Case-insensitive collation for ASCII letters.
+///
+/// Upper-case ASCII letters collate as their lower-case counterparts; all other bytes
+/// (including non-ASCII UTF-8 sequences) collate by their byte value.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AsciiCaseInsensitive;
+
+impl Collator for AsciiCaseInsensitive {
+    /// Position in the key.
+    type State = usize;
+
+    #[inline(always)]
+    fn collation_bytes(&self, key: &[u8], pos: &mut usize, out: &mut [u8]) -> usize {
+        let tail = key.get(*pos..).unwrap_or(&[]);
+        let n = tail.len().min(out.len());
+        out.iter_mut()
+            .zip(&tail[..n])
+            .for_each(|(dst, src)| *dst = src.to_ascii_lowercase());
+        *pos += n;
+        n
+    }
+
+    #[inline(always)]
+    fn skip_collation_bytes(&self, key: &[u8], pos: &mut usize, count: usize) -> usize {
+        skip_key_bytes(key, pos, count)
+    }
+}
+
+/// Skips up to `count` bytes of `key` from `pos` on, for collators whose collation key
+/// has one byte per key byte.
+#[inline(always)]
+fn skip_key_bytes(key: &[u8], pos: &mut usize, count: usize) -> usize {
+    let n = key.len().saturating_sub(*pos).min(count);
+    *pos += n;
+    n
+}
+
+/// Performs an index-based sort of `provider` under the order defined by `collator`.
+///
+/// The first 8 collation-key bytes of every item are cached up front; the remaining bytes
+/// are generated only for items whose cached prefixes tie, or by radix passes over large
+/// groups of items that share their cached bytes.
+///
+/// # Arguments
+///
+/// * `provider` - The collection to be sorted.
+/// * `collator` - The collation used to order the keys.
+///
+/// # Returns
+///
+/// A permutation of indices such that the collation keys of `provider.get_key(indices[i])` are
+/// non-decreasing.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::collate::{AsciiCaseInsensitive, orasort_collated};
+///
+/// let data = vec!["b", "A", "a", "C"];
+/// let indices = orasort_collated(&data, &AsciiCaseInsensitive);
+///
+/// let sorted: Vec<&str> = indices.iter().map(|&i| data[i]).collect();
+/// assert_eq!(sorted[2..], ["b", "C"]);
+/// ```
+pub fn orasort_collated<T, C>(provider: &T, collator: &C) -> Permutation
+where
+    T: KeyAccessor + ?Sized,
+    C: Collator + ?Sized,
+{
+    let len = provider.len();
+    if len == 0 {
+        return Permutation::default();
+    }
+
+    // Initialize SortPtrs with the first 8 collation-key bytes.
+    let mut pointers: Vec<SortPtr> = (0..len)
+        .map(|index| {
+            let cache = collator.collation_prefix(provider.get_key(index));
+            SortPtr { index, cache }
+        })
+        .collect();
+
+    sort_pointers(
+        &Collated::new(provider, collator),
+        &mut pointers,
+        0,
+        &mut Scratch::default(),
+    );
+
+    Permutation::from_vec_unchecked(pointers.into_iter().map(|p| p.index).collect())
+}
+
+/// Sorts a mutable slice in-place under the order defined by `collator`.
+///
+/// This is the collated counterpart of [`orasort_mut`](crate::orasort_mut).
+///
+/// # Examples
+///
+/// ```
+/// use orasort::collate::{AsciiCaseInsensitive, orasort_collated_mut};
+///
+/// let mut data = vec!["banana", "Apple", "cherry"];
+/// orasort_collated_mut(&mut data, &AsciiCaseInsensitive);
+///
+/// assert_eq!(data, vec!["Apple", "banana", "cherry"]);
+/// ```
+pub fn orasort_collated_mut<T, C>(data: &mut [T], collator: &C)
+where
+    T: AsRef<[u8]>,
+    C: Collator + ?Sized,
+{
+    orasort_collated(data, collator).apply_to(data);
+}
+
+/// Presents the collation keys of `provider` to the sort internals.
+struct Collated<'a, T: ?Sized, C: Collator + ?Sized> {
+    provider: &'a T,
+    collator: &'a C,
+    /// Where generation of every collation key stands; `None` before it starts.
+    cursors: RefCell<Vec<Option<Cursor<C::State>>>>,
+}
+
+/// A position in a collation key, with the bytes after it generated ahead.
+#[derive(Clone)]
+struct Cursor<S> {
+    /// Where generation stopped: after the bytes of `ahead`.
+    state: S,
+    /// Number of collation-key bytes before `ahead`.
+    pos: usize,
+    /// The next `len` collation-key bytes from `pos` on.
+    ahead: [u8; COLLATION_CHUNK],
+    len: usize,
+    /// Whether the collation key ends after `ahead`.
+    ended: bool,
+}
+
+impl<S: Default> Cursor<S> {
+    /// Starts generating the collation key of `key`.
+    fn new<C: Collator<State = S> + ?Sized>(collator: &C, key: &[u8]) -> Self {
+        let mut cursor = Self {
+            state: S::default(),
+            pos: 0,
+            ahead: [0; COLLATION_CHUNK],
+            len: 0,
+            ended: false,
+        };
+        cursor.fill(collator, key);
+        cursor
+    }
+
+    /// Generates bytes ahead until `ahead` is full or the collation key ends.
+    fn fill<C: Collator<State = S> + ?Sized>(&mut self, collator: &C, key: &[u8]) {
+        if !self.ended && self.len < COLLATION_CHUNK {
+            self.len += collator.collation_bytes(key, &mut self.state, &mut self.ahead[self.len..]);
+            self.ended = self.len < COLLATION_CHUNK;
+        }
+    }
+
+    /// Moves on to `offset`, or to the end of the collation key if it is shorter, with at
+    /// least the 8 bytes at it generated.
+    fn advance<C: Collator<State = S> + ?Sized>(
+        &mut self,
+        collator: &C,
+        key: &[u8],
+        offset: usize,
+    ) {
+        if offset > self.pos {
+            let step = offset - self.pos;
+            if step <= self.len {
+                self.ahead.copy_within(step..self.len, 0);
+                self.len -= step;
+                self.pos = offset;
+            } else {
+                self.pos += self.len;
+                self.len = 0;
+                if !self.ended {
+                    let want = offset - self.pos;
+                    let skipped = collator.skip_collation_bytes(key, &mut self.state, want);
+                    self.pos += skipped;
+                    self.ended = skipped < want;
+                }
+            }
+        }
+
+        if self.len < SPLICE_PREFIX_SIZE {
+            self.fill(collator, key);
+        }
+    }
+
+    /// Returns the 8 collation-key bytes at `pos`, zero-padded.
+    fn prefix(&self) -> u64 {
+        let mut buf = [0u8; SPLICE_PREFIX_SIZE];
+        let n = self.len.min(SPLICE_PREFIX_SIZE);
+        buf[..n].copy_from_slice(&self.ahead[..n]);
+        u64::from_be_bytes(buf)
+    }
+}
+
+impl<'a, T, C> Collated<'a, T, C>
+where
+    T: KeyAccessor + ?Sized,
+    C: Collator + ?Sized,
+{
+    fn new(provider: &'a T, collator: &'a C) -> Self {
+        Self {
+            provider,
+            collator,
+            cursors: RefCell::default(),
+        }
+    }
+
+    /// Moves the cursor of the key at `index`, whose bytes are `key`, to `offset` and
+    /// calls `f` with it.
+    ///
+    /// The sort only ever moves a key on to larger offsets, so generation continues from
+    /// the previous call and every collation byte is generated once.
+    fn seek<R>(
+        &self,
+        index: usize,
+        key: &[u8],
+        offset: usize,
+        f: impl FnOnce(&mut Cursor<C::State>) -> R,
+    ) -> R {
+        let mut cursors = self.cursors.borrow_mut();
+        if cursors.is_empty() {
+            cursors.resize_with(self.provider.len(), || None);
+        }
+
+        let slot = &mut cursors[index];
+        let cursor = match slot {
+            Some(cursor) if cursor.pos <= offset => cursor,
+            _ => slot.insert(Cursor::new(self.collator, key)),
+        };
+        cursor.advance(self.collator, key, offset);
+        f(cursor)
+    }
+}
+
+impl<T, C> SortKeys for Collated<'_, T, C>
+where
+    T: KeyAccessor + ?Sized,
+    C: Collator + ?Sized,
+{
+    /// Returns the key before collation; [`compare_tails`](Self::compare_tails) never
+    /// compares it.
+    fn key(&self, index: usize) -> &[u8] {
+        self.provider.get_key(index)
+    }
+
+    /// Returns the 8 collation-key bytes at `offset`.
+    fn prefix(&self, index: usize, offset: usize) -> u64 {
+        let key = self.provider.get_key(index);
+        self.seek(index, key, offset, |cursor| cursor.prefix())
+    }
+
+    /// Resumes generating both collation keys past the 8 bytes at `offset`, which are
+    /// known to be equal.
+    fn compare_tails(&self, a: usize, b: usize, offset: usize) -> Ordering {
+        let (key_a, key_b) = (self.provider.get_key(a), self.provider.get_key(b));
+        let resume = |index: usize, key: &[u8]| {
+            self.seek(index, key, offset, |cursor| {
+                cursor.fill(self.collator, key);
+                cursor.clone()
+            })
+        };
+        let (mut cursor_a, mut cursor_b) = (resume(a, key_a), resume(b, key_b));
+        let (len_a, len_b) = (cursor_a.pos + cursor_a.len, cursor_b.pos + cursor_b.len);
+
+        // Where a collation key ends within the cache, the cache cannot tell its end from
+        // zero padding: the lengths decide.
+        let start = offset + SPLICE_PREFIX_SIZE;
+        if len_a < start || len_b < start {
+            return len_a.cmp(&len_b);
+        }
+
+        // Both cursors are at `offset` with as many bytes ahead as their keys have, up to a
+        // full chunk. Equal chunks are full unless both keys end.
+        let ahead_a = &cursor_a.ahead[SPLICE_PREFIX_SIZE..cursor_a.len];
+        let ahead_b = &cursor_b.ahead[SPLICE_PREFIX_SIZE..cursor_b.len];
+        match ahead_a.cmp(ahead_b) {
+            Ordering::Equal if !cursor_a.ended => compare_remaining(
+                self.collator,
+                key_a,
+                &mut cursor_a.state,
+                key_b,
+                &mut cursor_b.state,
+            ),
+            other => other,
+        }
+    }
+}
+
+/// Compares the full collation keys of `key_a` and `key_b`, generating them chunk by chunk
+/// until they differ or one of them ends.
+pub(crate) fn compare_collation_keys<C>(collator: &C, key_a: &[u8], key_b: &[u8]) -> Ordering
+where
+    C: Collator + ?Sized,
+{
+    compare_remaining(
+        collator,
+        key_a,
+        &mut C::State::default(),
+        key_b,
+        &mut C::State::default(),
+    )
+}
+
+/// Compares the rest of two collation keys from where `state_a` and `state_b` stand.
+fn compare_remaining<C>(
+    collator: &C,
+    key_a: &[u8],
+    state_a: &mut C::State,
+    key_b: &[u8],
+    state_b: &mut C::State,
+) -> Ordering
+where
+    C: Collator + ?Sized,
+{
+    let mut buf_a = [0u8; COLLATION_CHUNK];
+    let mut buf_b = [0u8; COLLATION_CHUNK];
+
+    loop {
+        let len_a = collator.collation_bytes(key_a, state_a, &mut buf_a);
+        let len_b = collator.collation_bytes(key_b, state_b, &mut buf_b);
+
+        match buf_a[..len_a].cmp(&buf_b[..len_b]) {
+            // Equal chunks have equal lengths; a short chunk means both keys ended.
+            Ordering::Equal if len_a < COLLATION_CHUNK => return Ordering::Equal,
+            Ordering::Equal => {}
+            other => return other,
+        }
+    }
+}
+
+/// Writes the next window of a generated byte stream into `out`.
+///
+/// Collators build their collation key from pieces: escapes, markers and runs of key
+/// bytes. A piece that did not fit is emitted again by the next call, which skips the
+/// `skip` bytes of it that were already written.
+pub(crate) struct Emitter<'a> {
+    skip: usize,
+    out: &'a mut [u8],
+    written: usize,
+}
+
+impl<'a> Emitter<'a> {
+    #[inline(always)]
+    pub(crate) fn new(skip: usize, out: &'a mut [u8]) -> Self {
+        Self {
+            skip,
+            out,
+            written: 0,
+        }
+    }
+
+    /// Returns the number of bytes written to `out`.
+    #[inline(always)]
+    pub(crate) fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Returns `true` once nothing more can be emitted. An emitter with an empty `out`
+    /// only skips.
+    #[inline(always)]
+    pub(crate) fn is_full(&self) -> bool {
+        self.room() == 0
+    }
+
+    /// Returns how many more bytes are skipped before the next one is written.
+    #[inline(always)]
+    pub(crate) fn skipping(&self) -> usize {
+        self.skip
+    }
+
+    /// Returns how many more bytes can be emitted (skipped or written).
+    #[inline(always)]
+    pub(crate) fn room(&self) -> usize {
+        self.skip + self.out.len() - self.written
+    }
+
+    /// Emits `bytes` and returns how many of them were skipped or written: all of them
+    /// unless `out` filled up.
+    #[inline(always)]
+    pub(crate) fn extend(&mut self, bytes: &[u8]) -> usize {
+        let skipped = self.skip.min(bytes.len());
+        self.skip -= skipped;
+        let rest = &bytes[skipped..];
+
+        let n = rest.len().min(self.out.len() - self.written);
+        self.out[self.written..self.written + n].copy_from_slice(&rest[..n]);
+        self.written += n;
+        skipped + n
+    }
+}
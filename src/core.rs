@@ -2,19 +2,29 @@
 //!
 //! This module defines:
 //! - [`KeyAccessor`]: The main trait users implement to sort their custom types.
-//! - SortPtr: Internal pointer/cache structure.
+//! - [`TryKeyAccessor`]: Its fallible counterpart for keys that may fail to load.
+//! - [`SortPtr`]: Pointer/cache structure, opaque outside the crate.
+//! - [`Concat`], [`ConcatMany`] and [`Subset`]: Accessors combining or selecting the keys
+//!   of other accessors without copying them.
 
-use std::collections::VecDeque;
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::ops::Range;
 
 /// Size of the prefix to be cached in the sort pointer.
 pub const SPLICE_PREFIX_SIZE: usize = 8;
 
 /// Pointer to an item, storing index and cached 8-byte key prefix.
+///
+/// The fields are private; the type is only public so that callers can provide scratch
+/// space for [`orasort_in`](crate::orasort_in).
 #[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(crate) struct SortPtr {
-    pub index: usize,
-    pub cache: u64,
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SortPtr {
+    pub(crate) index: usize,
+    pub(crate) cache: u64,
 }
 
 /// A trait for accessing key data from a collection without copying.
@@ -64,26 +74,147 @@ pub trait KeyAccessor {
     /// Returns 0 if offset is out of bounds or for padding.
     #[inline(always)]
     fn get_u64_prefix(&self, index: usize, offset: usize) -> u64 {
-        let key = self.get_key(index);
-        let len = key.len();
+        key_prefix(self.get_key(index), offset)
+    }
+}
 
-        if offset >= len {
-            return 0;
-        }
+/// Returns the 8 bytes of `key` starting at `offset` as a big-endian `u64`.
+///
+/// Bytes past the end of the key are zero; an `offset` at or past the end yields 0.
+#[inline(always)]
+pub(crate) fn key_prefix(key: &[u8], offset: usize) -> u64 {
+    let len = key.len();
 
-        let remaining = len - offset;
-        if remaining >= SPLICE_PREFIX_SIZE {
-            unsafe {
-                let ptr = key.as_ptr().add(offset);
-                let raw = std::ptr::read_unaligned(ptr as *const u64);
-                u64::from_be(raw)
-            }
-        } else {
-            let mut buf = [0u8; SPLICE_PREFIX_SIZE];
-            // Safety: Checked bounds above
-            buf[..remaining].copy_from_slice(&key[offset..]);
-            u64::from_be_bytes(buf)
+    if offset >= len {
+        return 0;
+    }
+
+    let remaining = len - offset;
+    if remaining >= SPLICE_PREFIX_SIZE {
+        unsafe {
+            let ptr = key.as_ptr().add(offset);
+            let raw = core::ptr::read_unaligned(ptr as *const u64);
+            u64::from_be(raw)
         }
+    } else {
+        let mut buf = [0u8; SPLICE_PREFIX_SIZE];
+        // Safety: Checked bounds above
+        buf[..remaining].copy_from_slice(&key[offset..]);
+        u64::from_be_bytes(buf)
+    }
+}
+
+/// Returns the 8 bytes at `offset` of the key `buffer[range]` as a big-endian `u64`, like
+/// [`key_prefix`].
+///
+/// Unless the key ends within the last 8 bytes of `buffer`, this is a single load whose
+/// bytes past the end of the key are masked off.
+#[inline(always)]
+pub(crate) fn buffer_prefix(buffer: &[u8], range: Range<usize>, offset: usize) -> u64 {
+    let remaining = range.len().saturating_sub(offset);
+    let at = range.start + offset;
+    if remaining == 0 || at + SPLICE_PREFIX_SIZE > buffer.len() {
+        return key_prefix(&buffer[range], offset);
+    }
+
+    let bytes: [u8; SPLICE_PREFIX_SIZE] = buffer[at..at + SPLICE_PREFIX_SIZE].try_into().unwrap();
+    let value = u64::from_be_bytes(bytes);
+    if remaining >= SPLICE_PREFIX_SIZE {
+        value
+    } else {
+        value & !(u64::MAX >> (remaining * 8))
+    }
+}
+
+/// A fallible counterpart of [`KeyAccessor`] for keys that may fail to load.
+///
+/// Implement this trait when fetching a key can fail, e.g. because it lives in a
+/// compressed page or a remote-mapped segment. The `try_orasort*` functions in
+/// [`fallible`](crate::fallible) stop at the first failed fetch and return its error.
+///
+/// Every [`KeyAccessor`] is also a `TryKeyAccessor` that never fails.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::core::TryKeyAccessor;
+/// use orasort::fallible::try_orasort;
+///
+/// struct Pages(Vec<Option<Vec<u8>>>);
+///
+/// impl TryKeyAccessor for Pages {
+///     type Error = String;
+///
+///     fn try_get_key(&self, index: usize) -> Result<&[u8], String> {
+///         self.0[index]
+///             .as_deref()
+///             .ok_or_else(|| format!("page for row {} is unavailable", index))
+///     }
+///
+///     fn len(&self) -> usize {
+///         self.0.len()
+///     }
+/// }
+///
+/// let pages = Pages(vec![Some(b"b".to_vec()), None, Some(b"a".to_vec())]);
+/// assert_eq!(try_orasort(&pages).unwrap_err(), "page for row 1 is unavailable");
+/// ```
+pub trait TryKeyAccessor {
+    /// The error returned when a key cannot be fetched.
+    type Error;
+
+    /// Returns a byte slice representing the key at the given index, or the fetch error.
+    fn try_get_key(&self, index: usize) -> Result<&[u8], Self::Error>;
+
+    /// Returns the number of items in the collection.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the collection is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Fallible counterpart of [`KeyAccessor::get_u64_prefix`].
+    #[inline(always)]
+    fn try_get_u64_prefix(&self, index: usize, offset: usize) -> Result<u64, Self::Error> {
+        self.try_get_key(index).map(|key| key_prefix(key, offset))
+    }
+}
+
+impl<T: KeyAccessor + ?Sized> TryKeyAccessor for T {
+    type Error = Infallible;
+
+    #[inline(always)]
+    fn try_get_key(&self, index: usize) -> Result<&[u8], Infallible> {
+        Ok(self.get_key(index))
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        KeyAccessor::len(self)
+    }
+
+    #[inline(always)]
+    fn try_get_u64_prefix(&self, index: usize, offset: usize) -> Result<u64, Infallible> {
+        Ok(self.get_u64_prefix(index, offset))
+    }
+}
+
+// Forwarding implementation, so that types owning an accessor can also be given a borrowed one.
+impl<T: KeyAccessor + ?Sized> KeyAccessor for &T {
+    #[inline(always)]
+    fn get_key(&self, index: usize) -> &[u8] {
+        (**self).get_key(index)
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    #[inline(always)]
+    fn get_u64_prefix(&self, index: usize, offset: usize) -> u64 {
+        (**self).get_u64_prefix(index, offset)
     }
 }
 
@@ -127,7 +258,7 @@ impl<T: AsRef<[u8]>> KeyAccessor for VecDeque<T> {
 // But for searching/indexing it is valid.
 impl KeyAccessor for str {
     fn get_key(&self, index: usize) -> &[u8] {
-        std::slice::from_ref(&self.as_bytes()[index])
+        core::slice::from_ref(&self.as_bytes()[index])
     }
 
     fn len(&self) -> usize {
@@ -137,10 +268,304 @@ impl KeyAccessor for str {
 
 impl KeyAccessor for String {
     fn get_key(&self, index: usize) -> &[u8] {
-        std::slice::from_ref(&self.as_bytes()[index])
+        core::slice::from_ref(&self.as_bytes()[index])
     }
 
     fn len(&self) -> usize {
         self.len()
     }
 }
+
+/// The keys of `first` followed by the keys of `second`, as one collection.
+///
+/// Index `i` refers to key `i` of `first` if `i < first.len()`, and to key
+/// `i - first.len()` of `second` otherwise. [`provenance`](Self::provenance) maps sorted
+/// indices back to where the keys came from.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::core::Concat;
+/// use orasort::orasort;
+///
+/// let cold = vec!["pear", "fig"];
+/// let hot = vec!["apple", "kiwi"];
+/// let both = Concat::new(&cold, &hot);
+///
+/// let order = orasort(&both);
+/// assert_eq!(both.provenance(&order), vec![(1, 0), (0, 1), (1, 1), (0, 0)]);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Concat<A, B> {
+    first: A,
+    second: B,
+    /// Length of `first`.
+    split: usize,
+}
+
+impl<A: KeyAccessor, B: KeyAccessor> Concat<A, B> {
+    /// Concatenates the keys of `first` and `second`.
+    pub fn new(first: A, second: B) -> Self {
+        let split = first.len();
+        Self {
+            first,
+            second,
+            split,
+        }
+    }
+
+    /// Returns the first collection.
+    pub fn first(&self) -> &A {
+        &self.first
+    }
+
+    /// Returns the second collection.
+    pub fn second(&self) -> &B {
+        &self.second
+    }
+
+    /// Returns the two collections.
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+
+    /// Returns the source (0 for `first`, 1 for `second`) and the index within it of key
+    /// `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= self.len()`.
+    #[inline(always)]
+    pub fn locate(&self, index: usize) -> (usize, usize) {
+        if index < self.split {
+            (0, index)
+        } else {
+            assert!(
+                index - self.split < self.second.len(),
+                "index {} out of range for length {}",
+                index,
+                KeyAccessor::len(self)
+            );
+            (1, index - self.split)
+        }
+    }
+
+    /// Returns the source and local index of every index in `indices`, e.g. of a sorted
+    /// order. See [`locate`](Self::locate).
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is not below `self.len()`.
+    pub fn provenance(&self, indices: &[usize]) -> Vec<(usize, usize)> {
+        indices.iter().map(|&index| self.locate(index)).collect()
+    }
+}
+
+impl<A: KeyAccessor, B: KeyAccessor> KeyAccessor for Concat<A, B> {
+    #[inline(always)]
+    fn get_key(&self, index: usize) -> &[u8] {
+        if index < self.split {
+            self.first.get_key(index)
+        } else {
+            self.second.get_key(index - self.split)
+        }
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.split + self.second.len()
+    }
+
+    #[inline(always)]
+    fn get_u64_prefix(&self, index: usize, offset: usize) -> u64 {
+        if index < self.split {
+            self.first.get_u64_prefix(index, offset)
+        } else {
+            self.second.get_u64_prefix(index - self.split, offset)
+        }
+    }
+}
+
+/// The keys of any number of collections of the same type, one after another.
+///
+/// Like [`Concat`], but each key access finds its source by binary search over the
+/// collections' start indices.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::core::ConcatMany;
+/// use orasort::orasort;
+///
+/// let shards = vec![vec!["m", "c"], vec![], vec!["x", "a"]];
+/// let all = ConcatMany::new(shards.iter().collect());
+///
+/// let order = orasort(&all);
+/// assert_eq!(all.provenance(&order), vec![(2, 1), (0, 1), (0, 0), (2, 0)]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ConcatMany<A> {
+    sources: Vec<A>,
+    /// Global index of the first key of every source.
+    starts: Vec<usize>,
+    len: usize,
+}
+
+impl<A: KeyAccessor> ConcatMany<A> {
+    /// Concatenates the keys of `sources`, in order.
+    pub fn new(sources: Vec<A>) -> Self {
+        let mut len = 0;
+        let starts = sources
+            .iter()
+            .map(|source| {
+                let start = len;
+                len += source.len();
+                start
+            })
+            .collect();
+        Self {
+            sources,
+            starts,
+            len,
+        }
+    }
+
+    /// Returns the collections.
+    pub fn sources(&self) -> &[A] {
+        &self.sources
+    }
+
+    /// Returns the collections.
+    pub fn into_inner(self) -> Vec<A> {
+        self.sources
+    }
+
+    /// Returns the position in `sources` and the index within that source of key `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= self.len()`.
+    #[inline(always)]
+    pub fn locate(&self, index: usize) -> (usize, usize) {
+        assert!(
+            index < self.len,
+            "index {} out of range for length {}",
+            index,
+            self.len
+        );
+        // The last source starting at or before `index`; empty sources are skipped since
+        // they start where the next one does.
+        let source = self.starts.partition_point(|&start| start <= index) - 1;
+        (source, index - self.starts[source])
+    }
+
+    /// Returns the source and local index of every index in `indices`, e.g. of a sorted
+    /// order. See [`locate`](Self::locate).
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is not below `self.len()`.
+    pub fn provenance(&self, indices: &[usize]) -> Vec<(usize, usize)> {
+        indices.iter().map(|&index| self.locate(index)).collect()
+    }
+}
+
+impl<A: KeyAccessor> KeyAccessor for ConcatMany<A> {
+    #[inline(always)]
+    fn get_key(&self, index: usize) -> &[u8] {
+        let (source, local) = self.locate(index);
+        self.sources[source].get_key(local)
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline(always)]
+    fn get_u64_prefix(&self, index: usize, offset: usize) -> u64 {
+        let (source, local) = self.locate(index);
+        self.sources[source].get_u64_prefix(local, offset)
+    }
+}
+
+/// Selected keys of a collection, as a collection of their own.
+///
+/// Key `i` of the subset is key `indices[i]` of `inner`. Indices may repeat and need not
+/// be ordered. Sorting a subset yields positions in `indices`; map them back with
+/// [`indices`](Self::indices), or use [`orasort_from_indices`](crate::orasort_from_indices)
+/// to sort the selected indices directly.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::core::Subset;
+/// use orasort::orasort;
+///
+/// let words = vec!["kiwi", "Fig", "apple", "Plum"];
+/// let lowercase = Subset::filter(&words, |key| key[0].is_ascii_lowercase());
+///
+/// let order = orasort(&lowercase);
+/// let sorted: Vec<usize> = order.iter().map(|&i| lowercase.indices()[i]).collect();
+/// assert_eq!(sorted, vec![2, 0]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Subset<A> {
+    inner: A,
+    indices: Vec<usize>,
+}
+
+impl<A: KeyAccessor> Subset<A> {
+    /// Selects the keys of `inner` at `indices`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is not below `inner.len()`.
+    pub fn new(inner: A, indices: Vec<usize>) -> Self {
+        let len = inner.len();
+        if let Some(&index) = indices.iter().find(|&&index| index >= len) {
+            panic!("index {} out of range for length {}", index, len);
+        }
+        Self { inner, indices }
+    }
+
+    /// Selects the keys of `inner` for which `predicate` returns `true`.
+    pub fn filter(inner: A, mut predicate: impl FnMut(&[u8]) -> bool) -> Self {
+        let indices = (0..inner.len())
+            .filter(|&index| predicate(inner.get_key(index)))
+            .collect();
+        Self { inner, indices }
+    }
+
+    /// Returns the underlying collection.
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    /// Returns the index in the underlying collection of every key of the subset.
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Returns the underlying collection and the selected indices.
+    pub fn into_inner(self) -> (A, Vec<usize>) {
+        (self.inner, self.indices)
+    }
+}
+
+impl<A: KeyAccessor> KeyAccessor for Subset<A> {
+    #[inline(always)]
+    fn get_key(&self, index: usize) -> &[u8] {
+        self.inner.get_key(self.indices[index])
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    #[inline(always)]
+    fn get_u64_prefix(&self, index: usize, offset: usize) -> u64 {
+        self.inner.get_u64_prefix(self.indices[index], offset)
+    }
+}
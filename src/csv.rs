@@ -0,0 +1,370 @@
+//! Sorting CSV records by column ([RFC 4180](https://www.rfc-editor.org/rfc/rfc4180)).
+//!
+//! Line-based sorting breaks CSV files whose quoted fields contain delimiters or line
+//! breaks. [`CsvIndex`] finds the record boundaries of a CSV buffer once, honouring quotes
+//! and `""` escapes, and [`CsvIndex::column`] exposes one column of every record as a
+//! [`KeyAccessor`] over the unquoted field bytes. Keys point into the buffer; only fields
+//! containing escaped quotes are unescaped into a side buffer.
+//!
+//! Sorted records are written back with their original bytes, quoting and line breaks
+//! included, after the header row.
+
+use crate::algo::orasort;
+use crate::core::KeyAccessor;
+use crate::error::CsvError;
+use crate::permutation::Permutation;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Line break appended to a final record without one if it is moved before other records,
+/// unless the buffer uses a different line break elsewhere.
+const DEFAULT_TERMINATOR: &[u8] = b"\n";
+
+/// Record boundaries of a CSV buffer.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::csv::CsvIndex;
+///
+/// let data = b"name,city\n\"Smith, Jo\",Oslo\nAdams,\"New\nYork\"\nBrown,Bergen\n";
+/// let index = CsvIndex::new(data).unwrap();
+///
+/// let order = index.sort_by(1);
+/// assert_eq!(
+///     index.to_sorted_vec(&order),
+///     b"name,city\nBrown,Bergen\nAdams,\"New\nYork\"\n\"Smith, Jo\",Oslo\n"
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct CsvIndex<'a> {
+    data: &'a [u8],
+    delimiter: u8,
+    /// Content (without line break) of the header row.
+    header: Option<Range<usize>>,
+    /// Start of the first record after the header row.
+    body_start: usize,
+    /// Content (without line break) of every record.
+    records: Vec<Range<usize>>,
+    /// Line break of the buffer's first terminated record.
+    terminator: &'a [u8],
+}
+
+impl<'a> CsvIndex<'a> {
+    /// Indexes comma-separated `data` whose first record is a header row.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CsvError`] if a quoted field is malformed.
+    pub fn new(data: &'a [u8]) -> Result<Self, CsvError> {
+        Self::with_options(data, b',', true)
+    }
+
+    /// Indexes `data` with fields separated by `delimiter`, treating the first record as a
+    /// header row if `has_header` is set.
+    ///
+    /// Records end at `\n` or `\r\n` outside of quotes. A line break after the last record
+    /// is optional.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CsvError`] if a quoted field is not closed, or if a closing quote is
+    /// followed by anything but the delimiter or a line break.
+    pub fn with_options(data: &'a [u8], delimiter: u8, has_header: bool) -> Result<Self, CsvError> {
+        let mut index = Self {
+            data,
+            delimiter,
+            header: None,
+            body_start: 0,
+            records: Vec::new(),
+            terminator: DEFAULT_TERMINATOR,
+        };
+
+        let mut pos = 0;
+        let mut terminator_seen = false;
+        while pos < data.len() {
+            let (content_end, next) = index.scan_record(pos)?;
+            if !terminator_seen && next > content_end {
+                index.terminator = &data[content_end..next];
+                terminator_seen = true;
+            }
+
+            if has_header && index.header.is_none() {
+                index.header = Some(pos..content_end);
+                index.body_start = next;
+            } else {
+                index.records.push(pos..content_end);
+            }
+            pos = next;
+        }
+
+        Ok(index)
+    }
+
+    /// Returns the number of records, not counting the header row.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns `true` if there are no records besides the header row.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Returns the header row without its line break, if the index has one.
+    pub fn header(&self) -> Option<&'a [u8]> {
+        Some(&self.data[self.header.clone()?])
+    }
+
+    /// Returns the original bytes of record `index` without its line break.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= self.len()`.
+    pub fn record(&self, index: usize) -> &'a [u8] {
+        &self.data[self.records[index].clone()]
+    }
+
+    /// Returns the unquoted values of `column` (zero-based) as sort keys.
+    ///
+    /// Records with fewer fields have an empty key.
+    pub fn column(&self, column: usize) -> CsvColumn<'a> {
+        let mut keys = CsvColumn {
+            data: self.data,
+            spans: Vec::with_capacity(self.records.len()),
+            unescaped: Vec::new(),
+        };
+
+        for record in &self.records {
+            let span = match self.field(record.clone(), column) {
+                None => Span::default(),
+                Some((range, false)) => Span {
+                    start: range.start,
+                    end: range.end,
+                    unescaped: false,
+                },
+                Some((range, true)) => {
+                    let start = keys.unescaped.len();
+                    unescape(&self.data[range], &mut keys.unescaped);
+                    Span {
+                        start,
+                        end: keys.unescaped.len(),
+                        unescaped: true,
+                    }
+                }
+            };
+            keys.spans.push(span);
+        }
+
+        keys
+    }
+
+    /// Returns the column whose header field is `name`, if there is a header row.
+    pub fn column_named(&self, name: &[u8]) -> Option<CsvColumn<'a>> {
+        let header = self.header.clone()?;
+
+        let mut value = Vec::new();
+        for column in 0.. {
+            let (range, escaped) = self.field(header.clone(), column)?;
+            value.clear();
+            match escaped {
+                true => unescape(&self.data[range], &mut value),
+                false => value.extend_from_slice(&self.data[range]),
+            }
+            if value == name {
+                return Some(self.column(column));
+            }
+        }
+        None
+    }
+
+    /// Sorts the records by the bytes of `column`.
+    pub fn sort_by(&self, column: usize) -> Permutation {
+        orasort(&self.column(column))
+    }
+
+    /// Returns the buffer with the header row first, followed by the records in the order
+    /// given by `permutation`.
+    ///
+    /// Every record keeps its original bytes. A final record without a line break gets
+    /// one if other records follow it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `permutation.len() != self.len()`.
+    pub fn to_sorted_vec(&self, permutation: &Permutation) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.data.len() + self.terminator.len());
+        self.write_pieces(permutation, |piece| {
+            out.extend_from_slice(piece);
+            Ok::<(), core::convert::Infallible>(())
+        })
+        .unwrap_or_else(|never| match never {});
+        out
+    }
+
+    /// Writes the header row, followed by the records in the order given by `permutation`.
+    ///
+    /// See [`to_sorted_vec`](Self::to_sorted_vec).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `permutation.len() != self.len()`.
+    #[cfg(feature = "std")]
+    pub fn write_sorted<W: std::io::Write>(
+        &self,
+        permutation: &Permutation,
+        mut out: W,
+    ) -> std::io::Result<()> {
+        self.write_pieces(permutation, |piece| out.write_all(piece))
+    }
+
+    fn write_pieces<E>(
+        &self,
+        permutation: &Permutation,
+        mut write: impl FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        assert_eq!(
+            permutation.len(),
+            self.len(),
+            "CSV index and permutation must have the same length"
+        );
+
+        write(&self.data[..self.body_start])?;
+
+        let last = permutation.len().saturating_sub(1);
+        for (position, &index) in permutation.iter().enumerate() {
+            let record = self.records[index].clone();
+            let next = self
+                .records
+                .get(index + 1)
+                .map_or(self.data.len(), |next| next.start);
+
+            write(&self.data[record.start..next])?;
+            if next == record.end && position != last {
+                write(self.terminator)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Scans the record starting at `start`.
+    ///
+    /// Returns the end of its content and the start of the next record.
+    fn scan_record(&self, start: usize) -> Result<(usize, usize), CsvError> {
+        let data = self.data;
+        let mut pos = start;
+
+        loop {
+            if data.get(pos) == Some(&b'"') {
+                pos = closing_quote(data, pos)? + 1;
+                match data.get(pos) {
+                    None => return Ok((pos, pos)),
+                    Some(&byte) if byte == self.delimiter => pos += 1,
+                    Some(b'\n') => return Ok((pos, pos + 1)),
+                    Some(b'\r') if data.get(pos + 1) == Some(&b'\n') => return Ok((pos, pos + 2)),
+                    Some(_) => return Err(CsvError::UnexpectedByte { offset: pos }),
+                }
+                continue;
+            }
+
+            let stop = data[pos..]
+                .iter()
+                .position(|&b| b == self.delimiter || b == b'\n')
+                .map(|n| pos + n);
+            match stop {
+                None => return Ok((data.len(), data.len())),
+                Some(at) if data[at] == self.delimiter => pos = at + 1,
+                Some(at) if at > pos && data[at - 1] == b'\r' => return Ok((at - 1, at + 1)),
+                Some(at) => return Ok((at, at + 1)),
+            }
+        }
+    }
+
+    /// Returns the bytes of field `column` of a validated record, without quotes, and
+    /// whether they contain escaped quotes.
+    fn field(&self, record: Range<usize>, column: usize) -> Option<(Range<usize>, bool)> {
+        let data = self.data;
+        let mut pos = record.start;
+        let mut current = 0;
+
+        loop {
+            let (range, escaped, next) = if pos < record.end && data[pos] == b'"' {
+                // Validated by `scan_record`.
+                let close = closing_quote(data, pos).ok()?;
+                let escaped = data[pos + 1..close].contains(&b'"');
+                (pos + 1..close, escaped, close + 1)
+            } else {
+                let stop = data[pos..record.end]
+                    .iter()
+                    .position(|&b| b == self.delimiter)
+                    .map_or(record.end, |n| pos + n);
+                (pos..stop, false, stop)
+            };
+
+            if current == column {
+                return Some((range, escaped));
+            }
+            if next >= record.end {
+                return None;
+            }
+            pos = next + 1;
+            current += 1;
+        }
+    }
+}
+
+/// One column of a [`CsvIndex`], as sort keys.
+///
+/// Key `i` is the unquoted value of the column in record `i`.
+#[derive(Clone, Debug)]
+pub struct CsvColumn<'a> {
+    data: &'a [u8],
+    spans: Vec<Span>,
+    /// Values of fields with escaped quotes.
+    unescaped: Vec<u8>,
+}
+
+/// Location of a key, in the buffer or in the unescaped values.
+#[derive(Clone, Copy, Debug, Default)]
+struct Span {
+    start: usize,
+    end: usize,
+    unescaped: bool,
+}
+
+impl KeyAccessor for CsvColumn<'_> {
+    #[inline(always)]
+    fn get_key(&self, index: usize) -> &[u8] {
+        let span = self.spans[index];
+        match span.unescaped {
+            true => &self.unescaped[span.start..span.end],
+            false => &self.data[span.start..span.end],
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.spans.len()
+    }
+}
+
+/// Returns the position of the quote closing the quoted field opened at `open`.
+fn closing_quote(data: &[u8], open: usize) -> Result<usize, CsvError> {
+    let mut pos = open + 1;
+    loop {
+        match data[pos..].iter().position(|&b| b == b'"') {
+            None => return Err(CsvError::UnterminatedQuote { offset: open }),
+            Some(n) if data.get(pos + n + 1) == Some(&b'"') => pos += n + 2,
+            Some(n) => return Ok(pos + n),
+        }
+    }
+}
+
+/// Appends `quoted` with every `""` replaced by `"`.
+fn unescape(quoted: &[u8], out: &mut Vec<u8>) {
+    let mut rest = quoted;
+    while let Some(n) = rest.iter().position(|&b| b == b'"') {
+        out.extend_from_slice(&rest[..=n]);
+        rest = &rest[n + 2..];
+    }
+    out.extend_from_slice(rest);
+}
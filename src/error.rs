@@ -0,0 +1,310 @@
+//! Error types for Orasort.
+
+use alloc::string::String;
+use core::fmt;
+
+/// Errors reported by the fallible Orasort entry points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OrasortError {
+    /// The caller-provided scratch space cannot hold the sort's working memory.
+    ScratchTooSmall {
+        /// Number of scratch slots needed for the input.
+        required: usize,
+        /// Number of scratch slots provided.
+        provided: usize,
+    },
+    /// An index is not below the length of the key provider.
+    IndexOutOfRange {
+        /// The offending index.
+        index: usize,
+        /// Length of the key provider.
+        len: usize,
+    },
+    /// An index occurs more than once.
+    DuplicateIndex {
+        /// The repeated index.
+        index: usize,
+    },
+    /// The number of skipped bytes reaches past the end of every key.
+    OffsetBeyondKeys {
+        /// The requested number of skipped bytes.
+        offset: usize,
+        /// Length of the longest selected key.
+        max_key_len: usize,
+    },
+}
+
+impl fmt::Display for OrasortError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ScratchTooSmall { required, provided } => write!(
+                f,
+                "scratch space too small: {} slots required, {} provided",
+                required, provided
+            ),
+            Self::IndexOutOfRange { index, len } => {
+                write!(f, "index {} out of range for length {}", index, len)
+            }
+            Self::DuplicateIndex { index } => write!(f, "index {} occurs more than once", index),
+            Self::OffsetBeyondKeys {
+                offset,
+                max_key_len,
+            } => write!(
+                f,
+                "offset {} skips past every key (longest key is {} bytes)",
+                offset, max_key_len
+            ),
+        }
+    }
+}
+
+impl core::error::Error for OrasortError {}
+
+/// Errors reported when parsing a [`KeySpec`](crate::keyspec::KeySpec).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum KeySpecError {
+    /// An option is not recognized.
+    UnknownOption(String),
+    /// An option that takes a value is the last argument.
+    MissingValue(String),
+    /// The field separator is not a single byte.
+    InvalidSeparator(String),
+    /// A key definition is malformed.
+    InvalidKey {
+        /// The key definition as given.
+        key: String,
+        /// What is wrong with it.
+        reason: &'static str,
+    },
+    /// A quoted word is not closed.
+    UnterminatedQuote,
+}
+
+impl fmt::Display for KeySpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownOption(option) => write!(f, "unknown option '{}'", option),
+            Self::MissingValue(option) => write!(f, "option '{}' requires a value", option),
+            Self::InvalidSeparator(separator) => {
+                write!(f, "field separator '{}' is not a single byte", separator)
+            }
+            Self::InvalidKey { key, reason } => write!(f, "invalid key '{}': {}", key, reason),
+            Self::UnterminatedQuote => f.write_str("unterminated quote"),
+        }
+    }
+}
+
+impl core::error::Error for KeySpecError {}
+
+/// Errors reported when indexing a CSV buffer with [`CsvIndex`](crate::csv::CsvIndex).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CsvError {
+    /// A quoted field is not closed before the end of the buffer.
+    UnterminatedQuote {
+        /// Byte offset of the opening quote.
+        offset: usize,
+    },
+    /// A closing quote is followed by something other than a delimiter or a line break.
+    UnexpectedByte {
+        /// Byte offset of the unexpected byte.
+        offset: usize,
+    },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnterminatedQuote { offset } => {
+                write!(f, "quoted field starting at byte {} is not closed", offset)
+            }
+            Self::UnexpectedByte { offset } => {
+                write!(f, "unexpected byte after closing quote at byte {}", offset)
+            }
+        }
+    }
+}
+
+impl core::error::Error for CsvError {}
+
+/// Errors reported by the [`jsonl`](crate::jsonl) module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum JsonlError {
+    /// A field path is empty or has an empty segment.
+    InvalidPath(String),
+    /// A line is not valid JSON where it was scanned.
+    Malformed {
+        /// Zero-based line number.
+        line: usize,
+        /// Byte offset of the error within the line.
+        offset: usize,
+    },
+}
+
+impl fmt::Display for JsonlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPath(path) => write!(f, "invalid field path '{}'", path),
+            Self::Malformed { line, offset } => {
+                write!(f, "malformed JSON on line {} at byte {}", line + 1, offset)
+            }
+        }
+    }
+}
+
+impl core::error::Error for JsonlError {}
+
+/// Errors reported when opening a sorted string table with
+/// [`SstReader`](crate::sst::SstReader).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SstError {
+    /// The buffer is too short for a footer or does not end with the table magic.
+    NotATable,
+    /// The stored checksum of a block or the footer does not match its contents.
+    ChecksumMismatch {
+        /// Byte offset of the block.
+        offset: usize,
+    },
+    /// A block or block reference is malformed.
+    Corrupt {
+        /// Byte offset of the malformed block.
+        offset: usize,
+    },
+}
+
+impl fmt::Display for SstError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotATable => f.write_str("not a sorted string table"),
+            Self::ChecksumMismatch { offset } => {
+                write!(f, "checksum mismatch in block at byte {}", offset)
+            }
+            Self::Corrupt { offset } => write!(f, "corrupt block at byte {}", offset),
+        }
+    }
+}
+
+impl core::error::Error for SstError {}
+
+/// Errors reported when reading a saved permutation with
+/// [`PermutationView`](crate::persist::PermutationView).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PersistError {
+    /// The buffer does not start with a permutation file header.
+    NotAPermutationFile,
+    /// The file was written in a format version this library cannot read.
+    UnsupportedVersion(u32),
+    /// The file was written on a machine with a different byte order.
+    ByteOrderMismatch,
+    /// The buffer does not start at an 8-byte aligned address.
+    Misaligned,
+    /// The stored checksum does not match the file contents.
+    ChecksumMismatch,
+    /// The header or the saved indices are malformed.
+    Corrupt,
+    /// The file was saved for different keys.
+    Stale,
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAPermutationFile => f.write_str("not a permutation file"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported permutation file version {}", version)
+            }
+            Self::ByteOrderMismatch => {
+                f.write_str("permutation file was written with a different byte order")
+            }
+            Self::Misaligned => f.write_str("permutation file buffer is not 8-byte aligned"),
+            Self::ChecksumMismatch => f.write_str("permutation file checksum mismatch"),
+            Self::Corrupt => f.write_str("corrupt permutation file"),
+            Self::Stale => f.write_str("permutation file was saved for different keys"),
+        }
+    }
+}
+
+impl core::error::Error for PersistError {}
+
+/// Errors reported when validating the offsets of [`MmapKeys`](crate::mmap::MmapKeys).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OffsetsError {
+    /// The offsets file is empty or not a whole number of offsets.
+    InvalidLength {
+        /// Length of the offsets file in bytes.
+        len: usize,
+        /// Width of one offset in bytes.
+        width: usize,
+    },
+    /// An offset is smaller than its predecessor.
+    Decreasing {
+        /// Position of the offset in the offsets file.
+        index: usize,
+    },
+    /// The last offset lies past the end of the data.
+    PastEnd {
+        /// The last offset.
+        offset: u64,
+        /// Length of the data in bytes.
+        data_len: usize,
+    },
+}
+
+impl fmt::Display for OffsetsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength { len, width } => write!(
+                f,
+                "offsets file of {} bytes does not hold one or more {}-byte offsets",
+                len, width
+            ),
+            Self::Decreasing { index } => {
+                write!(f, "offset {} is smaller than the offset before it", index)
+            }
+            Self::PastEnd { offset, data_len } => write!(
+                f,
+                "offset {} lies past the end of the {} data bytes",
+                offset, data_len
+            ),
+        }
+    }
+}
+
+impl core::error::Error for OffsetsError {}
+
+/// Errors reported when indexing a buffer of [`FramedRecords`](crate::framed::FramedRecords).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FrameError {
+    /// The buffer ends inside the frame starting at `offset`.
+    Truncated {
+        /// Byte offset of the frame in the buffer.
+        offset: usize,
+    },
+    /// The length prefix of the frame starting at `offset` is not a valid length.
+    InvalidLength {
+        /// Byte offset of the frame in the buffer.
+        offset: usize,
+    },
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated { offset } => {
+                write!(f, "buffer ends inside the frame at byte {}", offset)
+            }
+            Self::InvalidLength { offset } => {
+                write!(f, "invalid length prefix in the frame at byte {}", offset)
+            }
+        }
+    }
+}
+
+impl core::error::Error for FrameError {}
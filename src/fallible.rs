@@ -0,0 +1,189 @@
+//! Fallible sorting for keys that may fail to load.
+//!
+//! The `try_orasort*` functions sort collections implementing
+//! [`TryKeyAccessor`]. They behave like their infallible
+//! counterparts in [`algo`](crate::algo), except that the first failed key fetch ends the
+//! sort and its error is returned:
+//!
+//! - Initial prefix loading stops immediately at the first error.
+//! - A failure while resolving ties during the sort is recorded, no further fetches reach
+//!   the accessor, and the sort stops at its next check: before every radix bucket and
+//!   leaf, and between the merges that sort a leaf. The partial result is discarded.
+//! - Nothing the caller passed in is modified unless the whole sort succeeds, so
+//!   [`try_orasort_mut`] and [`try_orasort_slice`] never leave data half-permuted.
+
+use crate::algo::{Scratch, SortKeys, sort_pointers};
+use crate::core::{SortPtr, TryKeyAccessor};
+use crate::permutation::Permutation;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+
+/// Performs an index-based sort like [`orasort`](crate::orasort), stopping at the first
+/// failed key fetch.
+///
+/// # Errors
+///
+/// Returns the first error reported by `provider`.
+pub fn try_orasort<T: TryKeyAccessor + ?Sized>(provider: &T) -> Result<Permutation, T::Error> {
+    try_sort_indices(provider, 0..provider.len(), 0).map(Permutation::from_vec_unchecked)
+}
+
+/// Sorts the provided indices like [`orasort_from_indices`](crate::orasort_from_indices),
+/// stopping at the first failed key fetch.
+///
+/// # Errors
+///
+/// Returns the first error reported by `provider`.
+pub fn try_orasort_from_indices<T: TryKeyAccessor + ?Sized>(
+    provider: &T,
+    indices: Vec<usize>,
+    offset: usize,
+) -> Result<Vec<usize>, T::Error> {
+    try_sort_indices(provider, indices, offset)
+}
+
+/// Sorts the provided indices in-place like [`orasort_slice`](crate::orasort_slice),
+/// stopping at the first failed key fetch.
+///
+/// # Errors
+///
+/// Returns the first error reported by `provider`. `indices` is left unchanged then.
+pub fn try_orasort_slice<T: TryKeyAccessor + ?Sized>(
+    provider: &T,
+    indices: &mut [usize],
+    offset: usize,
+) -> Result<(), T::Error> {
+    let sorted = try_sort_indices(provider, indices.iter().copied(), offset)?;
+    indices.copy_from_slice(&sorted);
+    Ok(())
+}
+
+/// Sorts `data` in-place by the keys `provider` holds for the same positions.
+///
+/// `provider.try_get_key(i)` is the key of `data[i]`. This is the fallible counterpart of
+/// [`orasort_mut`](crate::orasort_mut) for data whose keys are stored (or decoded) elsewhere.
+///
+/// # Errors
+///
+/// Returns the first error reported by `provider`. `data` is left unchanged then: the
+/// permutation is only applied once every key has been compared successfully.
+///
+/// # Panics
+///
+/// Panics if `provider.len() != data.len()`.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::fallible::try_orasort_mut;
+///
+/// let keys = vec!["b", "c", "a"];
+/// let mut rows = vec![20, 30, 10];
+/// try_orasort_mut(&keys, &mut rows).unwrap();
+///
+/// assert_eq!(rows, vec![10, 20, 30]);
+/// ```
+pub fn try_orasort_mut<T: TryKeyAccessor + ?Sized, D>(
+    provider: &T,
+    data: &mut [D],
+) -> Result<(), T::Error> {
+    assert_eq!(
+        provider.len(),
+        data.len(),
+        "provider and data must have the same length"
+    );
+
+    try_orasort(provider)?.apply_to(data);
+    Ok(())
+}
+
+/// Loads prefixes for `indices` (stopping at the first error) and sorts them.
+fn try_sort_indices<T, I>(provider: &T, indices: I, offset: usize) -> Result<Vec<usize>, T::Error>
+where
+    T: TryKeyAccessor + ?Sized,
+    I: IntoIterator<Item = usize>,
+{
+    let mut pointers = indices
+        .into_iter()
+        .map(|index| {
+            let cache = provider.try_get_u64_prefix(index, offset)?;
+            Ok(SortPtr { index, cache })
+        })
+        .collect::<Result<Vec<SortPtr>, T::Error>>()?;
+
+    let fallible = Fallible::new(provider);
+    sort_pointers(&fallible, &mut pointers, offset, &mut Scratch::default());
+    fallible.finish()?;
+
+    Ok(pointers.into_iter().map(|p| p.index).collect())
+}
+
+/// Adapts a [`TryKeyAccessor`] to the sort internals.
+///
+/// The first error is recorded; after it, every key reads as empty without calling the
+/// underlying accessor, and the sort stops early.
+struct Fallible<'a, T: TryKeyAccessor + ?Sized> {
+    inner: &'a T,
+    failed: Cell<bool>,
+    error: RefCell<Option<T::Error>>,
+}
+
+impl<'a, T: TryKeyAccessor + ?Sized> Fallible<'a, T> {
+    fn new(inner: &'a T) -> Self {
+        Self {
+            inner,
+            failed: Cell::new(false),
+            error: RefCell::new(None),
+        }
+    }
+
+    #[cold]
+    fn record(&self, error: T::Error) {
+        self.failed.set(true);
+        *self.error.borrow_mut() = Some(error);
+    }
+
+    /// Returns the recorded error, if any.
+    fn finish(self) -> Result<(), T::Error> {
+        match self.error.into_inner() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: TryKeyAccessor + ?Sized> SortKeys for Fallible<'_, T> {
+    fn key(&self, index: usize) -> &[u8] {
+        if self.failed.get() {
+            return &[];
+        }
+        match self.inner.try_get_key(index) {
+            Ok(key) => key,
+            Err(error) => {
+                self.record(error);
+                &[]
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn prefix(&self, index: usize, offset: usize) -> u64 {
+        if self.failed.get() {
+            return 0;
+        }
+        match self.inner.try_get_u64_prefix(index, offset) {
+            Ok(prefix) => prefix,
+            Err(error) => {
+                self.record(error);
+                0
+            }
+        }
+    }
+
+    const FALLIBLE: bool = true;
+
+    #[inline(always)]
+    fn failed(&self) -> bool {
+        self.failed.get()
+    }
+}
@@ -0,0 +1,240 @@
+//! Sorting buffers of length-prefixed records.
+//!
+//! Spill files and wire buffers often hold a sequence of records, each preceded by its
+//! length. [`FramedRecords`] indexes such a buffer in one pass and exposes the record
+//! bodies, or a fixed byte range of every body, as a [`KeyAccessor`]. Sorted buffers are
+//! written back frame by frame, length prefixes included, without copying records into
+//! owned allocations first.
+
+use crate::algo::orasort;
+use crate::codec::get_varint;
+use crate::core::{KeyAccessor, buffer_prefix};
+use crate::error::FrameError;
+use crate::permutation::Permutation;
+use alloc::vec::Vec;
+use core::ops::{Bound, Range, RangeBounds};
+
+/// How the length of every record is encoded in front of its body.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Framing {
+    /// Unsigned LEB128 varint, as used by Protocol Buffers.
+    Varint,
+    /// Big-endian `u32`.
+    U32Be,
+    /// Little-endian `u32`.
+    U32Le,
+}
+
+/// Index of the records of a length-prefixed buffer.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::framed::{FramedRecords, Framing};
+///
+/// // Three records, each a 2-byte id followed by a name.
+/// let data = b"\x0502bob\x0701alice\x0403al";
+/// let records = FramedRecords::new(data, Framing::Varint).unwrap();
+/// assert_eq!(records.body(1), b"01alice");
+///
+/// let by_name = records.clone().with_key_range(2..);
+/// let order = by_name.sort();
+/// assert_eq!(by_name.to_sorted_vec(&order), b"\x0403al\x0701alice\x0502bob");
+/// assert_eq!(records.to_sorted_vec(&records.sort()), b"\x0701alice\x0502bob\x0403al");
+/// ```
+#[derive(Clone, Debug)]
+pub struct FramedRecords<'a> {
+    data: &'a [u8],
+    /// Body of every record; frame `i` ends where body `i` ends.
+    bodies: Vec<Range<usize>>,
+    /// Part of every body used as the key, clamped to the body.
+    key_start: usize,
+    key_end: Option<usize>,
+}
+
+impl<'a> FramedRecords<'a> {
+    /// Indexes the records of `data`, which must consist of whole frames.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`FrameError`] if a length prefix is malformed or a frame extends past the
+    /// end of `data`.
+    pub fn new(data: &'a [u8], framing: Framing) -> Result<Self, FrameError> {
+        let mut bodies = Vec::new();
+        let mut pos = 0;
+
+        while pos < data.len() {
+            let frame = pos;
+            let len = match framing {
+                Framing::Varint => {
+                    let len = get_varint(data, &mut pos).ok_or_else(|| {
+                        // Either the buffer ran out before the last varint byte, or the
+                        // varint does not fit 64 bits.
+                        let tail = &data[frame..];
+                        if tail.len() < 10 && tail.iter().all(|byte| byte & 0x80 != 0) {
+                            FrameError::Truncated { offset: frame }
+                        } else {
+                            FrameError::InvalidLength { offset: frame }
+                        }
+                    })?;
+                    usize::try_from(len).map_err(|_| FrameError::InvalidLength { offset: frame })?
+                }
+                Framing::U32Be | Framing::U32Le => {
+                    let bytes: [u8; 4] = data
+                        .get(pos..pos + 4)
+                        .ok_or(FrameError::Truncated { offset: frame })?
+                        .try_into()
+                        .unwrap();
+                    pos += 4;
+                    match framing {
+                        Framing::U32Be => u32::from_be_bytes(bytes) as usize,
+                        _ => u32::from_le_bytes(bytes) as usize,
+                    }
+                }
+            };
+
+            let end = pos
+                .checked_add(len)
+                .filter(|&end| end <= data.len())
+                .ok_or(FrameError::Truncated { offset: frame })?;
+            bodies.push(pos..end);
+            pos = end;
+        }
+
+        Ok(Self {
+            data,
+            bodies,
+            key_start: 0,
+            key_end: None,
+        })
+    }
+
+    /// Uses `range` of every body as its key instead of the whole body.
+    ///
+    /// The range is clamped to each body, so shorter bodies have shorter (or empty) keys.
+    pub fn with_key_range<R: RangeBounds<usize>>(mut self, range: R) -> Self {
+        self.key_start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        self.key_end = match range.end_bound() {
+            Bound::Included(&end) => Some(end.saturating_add(1)),
+            Bound::Excluded(&end) => Some(end),
+            Bound::Unbounded => None,
+        };
+        self
+    }
+
+    /// Returns the number of records.
+    pub fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    /// Returns `true` if the buffer holds no records.
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+    }
+
+    /// Returns the body of record `index`, without its length prefix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= self.len()`.
+    pub fn body(&self, index: usize) -> &'a [u8] {
+        &self.data[self.bodies[index].clone()]
+    }
+
+    /// Returns record `index` as stored: its length prefix followed by its body.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= self.len()`.
+    pub fn frame(&self, index: usize) -> &'a [u8] {
+        &self.data[self.frame_range(index)]
+    }
+
+    /// Sorts the records by their keys.
+    pub fn sort(&self) -> Permutation {
+        orasort(self)
+    }
+
+    /// Returns the frames in the order given by `permutation`, unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `permutation.len() != self.len()`.
+    pub fn to_sorted_vec(&self, permutation: &Permutation) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.data.len());
+        self.write_pieces(permutation, |frame| {
+            out.extend_from_slice(frame);
+            Ok::<(), core::convert::Infallible>(())
+        })
+        .unwrap_or_else(|never| match never {});
+        out
+    }
+
+    /// Writes the frames in the order given by `permutation`, unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `permutation.len() != self.len()`.
+    #[cfg(feature = "std")]
+    pub fn write_sorted<W: std::io::Write>(
+        &self,
+        permutation: &Permutation,
+        mut out: W,
+    ) -> std::io::Result<()> {
+        self.write_pieces(permutation, |frame| out.write_all(frame))
+    }
+
+    fn write_pieces<E>(
+        &self,
+        permutation: &Permutation,
+        mut write: impl FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        assert_eq!(
+            permutation.len(),
+            self.len(),
+            "records and permutation must have the same length"
+        );
+        permutation
+            .iter()
+            .try_for_each(|&index| write(self.frame(index)))
+    }
+
+    fn frame_range(&self, index: usize) -> Range<usize> {
+        let start = match index {
+            0 => 0,
+            _ => self.bodies[index - 1].end,
+        };
+        start..self.bodies[index].end
+    }
+
+    /// Returns the position of the key of record `index` in the buffer.
+    #[inline(always)]
+    fn key_range(&self, index: usize) -> Range<usize> {
+        let body = self.bodies[index].clone();
+        let end = self
+            .key_end
+            .map_or(body.end, |end| body.start.saturating_add(end).min(body.end));
+        let start = body.start.saturating_add(self.key_start).min(end);
+        start..end
+    }
+}
+
+impl KeyAccessor for FramedRecords<'_> {
+    #[inline(always)]
+    fn get_key(&self, index: usize) -> &[u8] {
+        &self.data[self.key_range(index)]
+    }
+
+    fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    #[inline(always)]
+    fn get_u64_prefix(&self, index: usize, offset: usize) -> u64 {
+        buffer_prefix(self.data, self.key_range(index), offset)
+    }
+}
@@ -0,0 +1,210 @@
+//! Searchable sorted indices.
+//!
+//! [`SortedIndex`] keeps a collection together with its sorted order, so that point
+//! lookups and prefix scans can run as binary searches over the sorted positions.
+//!
+//! Searches work like the sort itself: the probe key is reduced to its first
+//! [`SPLICE_PREFIX_SIZE`] bytes once, every step compares
+//! that `u64` against the cached prefix of the key at the probed position, and only ties
+//! load the full key from the provider. With cached prefixes, a search whose probe is
+//! distinguished within 8 bytes touches no key data until its last few steps.
+
+use crate::algo::orasort;
+use crate::core::{KeyAccessor, SPLICE_PREFIX_SIZE, key_prefix};
+use crate::permutation::Permutation;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ops::Range;
+
+/// A collection together with its sorted order, supporting binary searches.
+///
+/// Positions refer to the sorted order: position `0` holds the smallest key, and
+/// [`indices`](Self::indices)`[p]` is the index of the item at position `p` in the
+/// collection. Keys are ordered bytewise, as by [`orasort`].
+///
+/// The index optionally stores the 8-byte prefix of every key (8 extra bytes per item),
+/// which lets searches skip most key loads. Without them, prefixes are obtained from
+/// [`KeyAccessor::get_u64_prefix`] on demand.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::SortedIndex;
+///
+/// let keys = vec!["user:bob", "group:admins", "user:alice", "user:carol", "group:staff"];
+/// let index = SortedIndex::new(&keys);
+///
+/// assert!(index.contains(b"user:carol"));
+/// assert!(!index.contains(b"user:dave"));
+///
+/// let users: Vec<&str> = index.prefix_range(b"user:").map(|p| keys[index.indices()[p]]).collect();
+/// assert_eq!(users, vec!["user:alice", "user:bob", "user:carol"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SortedIndex<A> {
+    provider: A,
+    indices: Vec<usize>,
+    prefixes: Option<Vec<u64>>,
+}
+
+impl<A: KeyAccessor> SortedIndex<A> {
+    /// Sorts `provider` and caches the prefix of every key.
+    pub fn new(provider: A) -> Self {
+        let permutation = orasort(&provider);
+        Self::from_permutation(provider, permutation)
+    }
+
+    /// Sorts `provider` without caching prefixes.
+    pub fn without_prefixes(provider: A) -> Self {
+        let indices = orasort(&provider).into_vec();
+        Self {
+            provider,
+            indices,
+            prefixes: None,
+        }
+    }
+
+    /// Builds an index from a sort result obtained earlier, caching the prefix of every key.
+    ///
+    /// `permutation` must order `provider` bytewise, e.g. be the result of
+    /// [`orasort`] on it; searches return unspecified positions otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `permutation.len() != provider.len()`.
+    pub fn from_permutation(provider: A, permutation: Permutation) -> Self {
+        assert_eq!(
+            provider.len(),
+            permutation.len(),
+            "provider and permutation must have the same length"
+        );
+        let indices = permutation.into_vec();
+        let prefixes = indices
+            .iter()
+            .map(|&index| provider.get_u64_prefix(index, 0))
+            .collect();
+        Self {
+            provider,
+            indices,
+            prefixes: Some(prefixes),
+        }
+    }
+
+    /// Returns the number of indexed items.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Returns `true` if the index holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Returns the indexed collection.
+    pub fn provider(&self) -> &A {
+        &self.provider
+    }
+
+    /// Returns the sorted order: the collection index of the item at every position.
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Returns the key at sorted position `position`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position >= self.len()`.
+    pub fn key_at(&self, position: usize) -> &[u8] {
+        self.provider.get_key(self.indices[position])
+    }
+
+    /// Consumes the index, returning the collection and its sorted order.
+    pub fn into_parts(self) -> (A, Vec<usize>) {
+        (self.provider, self.indices)
+    }
+
+    /// Returns the first position whose key is not less than `key`.
+    pub fn lower_bound(&self, key: &[u8]) -> usize {
+        let probe = key_prefix(key, 0);
+        self.partition_point(|position| self.compare_at(position, key, probe) == Ordering::Less)
+    }
+
+    /// Returns the first position whose key is greater than `key`.
+    pub fn upper_bound(&self, key: &[u8]) -> usize {
+        let probe = key_prefix(key, 0);
+        self.partition_point(|position| self.compare_at(position, key, probe) != Ordering::Greater)
+    }
+
+    /// Returns the range of positions whose key equals `key`.
+    pub fn equal_range(&self, key: &[u8]) -> Range<usize> {
+        self.lower_bound(key)..self.upper_bound(key)
+    }
+
+    /// Returns `true` if some item has key `key`.
+    pub fn contains(&self, key: &[u8]) -> bool {
+        let position = self.lower_bound(key);
+        position < self.len() && self.key_at(position) == key
+    }
+
+    /// Returns the range of positions whose key starts with `prefix`.
+    ///
+    /// An empty `prefix` matches every position.
+    pub fn prefix_range(&self, prefix: &[u8]) -> Range<usize> {
+        let probe = key_prefix(prefix, 0);
+        let start = self.lower_bound(prefix);
+        let end = self.partition_point(|position| {
+            self.compare_truncated_at(position, prefix, probe) != Ordering::Greater
+        });
+        start..end
+    }
+
+    /// Returns the cached (or freshly loaded) prefix of the key at `position`.
+    #[inline(always)]
+    fn prefix_at(&self, position: usize) -> u64 {
+        match &self.prefixes {
+            Some(prefixes) => prefixes[position],
+            None => self.provider.get_u64_prefix(self.indices[position], 0),
+        }
+    }
+
+    /// Compares the key at `position` with `key`, whose prefix is `probe`.
+    #[inline(always)]
+    fn compare_at(&self, position: usize, key: &[u8], probe: u64) -> Ordering {
+        let cache = self.prefix_at(position);
+        if cache != probe {
+            return cache.cmp(&probe);
+        }
+        self.key_at(position).cmp(key)
+    }
+
+    /// Compares the key at `position`, cut to `prefix.len()` bytes, with `prefix`.
+    #[inline(always)]
+    fn compare_truncated_at(&self, position: usize, prefix: &[u8], probe: u64) -> Ordering {
+        let mask = match prefix.len() {
+            0 => 0,
+            len if len >= SPLICE_PREFIX_SIZE => u64::MAX,
+            len => !(u64::MAX >> (len * 8)),
+        };
+        let cache = self.prefix_at(position) & mask;
+        if cache != probe & mask {
+            return cache.cmp(&(probe & mask));
+        }
+        let key = self.key_at(position);
+        key[..key.len().min(prefix.len())].cmp(prefix)
+    }
+
+    /// Returns the first position for which `pred` is false; `pred` must be monotone.
+    fn partition_point(&self, mut pred: impl FnMut(usize) -> bool) -> usize {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if pred(mid) {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+}
@@ -0,0 +1,624 @@
+//! Sorting JSON Lines (NDJSON) by field paths.
+//!
+//! [`JsonlIndex`] splits a buffer into lines, and [`JsonlIndex::sort_by`] orders them by the
+//! values at one or more dotted [`JsonPath`]s, e.g. `.user.id` then `.ts`. Each line is
+//! only scanned as far as needed to locate the values: siblings of the path are skipped
+//! without being decoded, and no document tree is built. Sorted lines are written back
+//! unchanged.
+//!
+//! ## Key encoding
+//!
+//! Every value is encoded into an order-preserving byte string, and the encodings for all
+//! paths are concatenated into one key per line (stored in a [`KeyArena`]). Values of
+//! different types order as:
+//!
+//! ```text
+//! missing < null < false < true < numbers < strings < arrays and objects
+//! ```
+//!
+//! - **Numbers** compare by exact numeric value (`1`, `1.0` and `10e-1` are equal, and
+//!   integers beyond 2^53 keep their precision). Exponents saturate at ±32767.
+//! - **Strings** compare by the bytes of their decoded UTF-8 text, so `"\u00e9"` equals `"é"`.
+//! - **Arrays and objects** compare by their raw text.
+
+use crate::algo::orasort;
+use crate::arena::KeyArena;
+use crate::error::JsonlError;
+use crate::permutation::Permutation;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::Range;
+use core::str::FromStr;
+
+const TAG_MISSING: u8 = 0x01;
+const TAG_NULL: u8 = 0x02;
+const TAG_FALSE: u8 = 0x03;
+const TAG_TRUE: u8 = 0x04;
+const TAG_NEGATIVE: u8 = 0x05;
+const TAG_ZERO: u8 = 0x06;
+const TAG_POSITIVE: u8 = 0x07;
+const TAG_STRING: u8 = 0x08;
+const TAG_COMPOSITE: u8 = 0x09;
+
+/// Emitted in place of a `0x00` byte in strings and composite values.
+const ESCAPED_NUL: [u8; 2] = [0x00, 0xFF];
+
+/// Emitted at the end of strings and composite values.
+const BYTES_END: [u8; 2] = [0x00, 0x01];
+
+/// A dotted path to a field, such as `.user.id`.
+///
+/// Segments name object members; a segment of digits also indexes into an array
+/// (`.tags.0`). The leading dot is optional.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct JsonPath {
+    segments: Vec<String>,
+}
+
+impl JsonPath {
+    /// Parses a dotted path.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonlError::InvalidPath`] if the path or one of its segments is empty.
+    pub fn parse(path: &str) -> Result<Self, JsonlError> {
+        let trimmed = path.strip_prefix('.').unwrap_or(path);
+        let segments: Vec<String> = trimmed.split('.').map(ToString::to_string).collect();
+        if segments.iter().any(String::is_empty) {
+            return Err(JsonlError::InvalidPath(path.to_string()));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Returns the segments of the path.
+    pub fn segments(&self) -> impl ExactSizeIterator<Item = &str> {
+        self.segments.iter().map(String::as_str)
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = JsonlError;
+
+    fn from_str(path: &str) -> Result<Self, JsonlError> {
+        Self::parse(path)
+    }
+}
+
+/// The lines of a JSON Lines buffer.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::jsonl::{JsonPath, JsonlIndex};
+///
+/// let data = br#"{"user":{"id":10},"ts":3}
+/// {"user":{"id":9},"ts":5}
+/// {"ts":1,"user":{"id":10}}
+/// "#;
+/// let index = JsonlIndex::new(data);
+/// let paths = [JsonPath::parse(".user.id")?, JsonPath::parse(".ts")?];
+///
+/// let order = index.sort_by(&paths)?;
+/// assert_eq!(
+///     index.to_sorted_vec(&order),
+///     br#"{"user":{"id":9},"ts":5}
+/// {"ts":1,"user":{"id":10}}
+/// {"user":{"id":10},"ts":3}
+/// "#
+/// );
+/// # Ok::<(), orasort::JsonlError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct JsonlIndex<'a> {
+    data: &'a [u8],
+    /// Content (without `\n`) of every line.
+    lines: Vec<Range<usize>>,
+}
+
+impl<'a> JsonlIndex<'a> {
+    /// Splits `data` into `\n`-terminated lines. A line break after the last line is
+    /// optional.
+    pub fn new(data: &'a [u8]) -> Self {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        while start < data.len() {
+            let end = data[start..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(data.len(), |n| start + n);
+            lines.push(start..end);
+            start = end + 1;
+        }
+        Self { data, lines }
+    }
+
+    /// Returns the number of lines.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Returns `true` if there are no lines.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Returns line `index` without its line break.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= self.len()`.
+    pub fn line(&self, index: usize) -> &'a [u8] {
+        &self.data[self.lines[index].clone()]
+    }
+
+    /// Returns the raw JSON text of the value at `path` on line `index`, or `None` if the
+    /// line has no such value.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonlError::Malformed`] if the line is not valid JSON up to the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= self.len()`.
+    pub fn value(&self, index: usize, path: &JsonPath) -> Result<Option<&'a [u8]>, JsonlError> {
+        let line = self.line(index);
+        let malformed = |offset| JsonlError::Malformed {
+            line: index,
+            offset,
+        };
+        let range = Scanner::new(line).find(path).map_err(malformed)?;
+        Ok(range.map(|range| &line[range]))
+    }
+
+    /// Returns the encoded keys of all lines for `paths` (see the module documentation).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonlError::Malformed`] for the first line that is not valid JSON up to
+    /// one of the values.
+    pub fn keys(&self, paths: &[JsonPath]) -> Result<KeyArena<u64>, JsonlError> {
+        let mut arena = KeyArena::with_capacity(self.len(), 0);
+        let mut key = Vec::new();
+
+        for index in 0..self.len() {
+            let line = self.line(index);
+            let malformed = |offset| JsonlError::Malformed {
+                line: index,
+                offset,
+            };
+
+            key.clear();
+            for path in paths {
+                match Scanner::new(line).find(path).map_err(malformed)? {
+                    None => key.push(TAG_MISSING),
+                    Some(value) => encode_value(line, value, &mut key).map_err(malformed)?,
+                }
+            }
+            arena.push(&key);
+        }
+
+        Ok(arena)
+    }
+
+    /// Sorts the lines by the values at `paths`; later paths break ties of earlier ones.
+    ///
+    /// # Errors
+    ///
+    /// See [`keys`](Self::keys).
+    pub fn sort_by(&self, paths: &[JsonPath]) -> Result<Permutation, JsonlError> {
+        Ok(orasort(&self.keys(paths)?))
+    }
+
+    /// Returns the lines in the order given by `permutation`, each unchanged.
+    ///
+    /// Every line is followed by `\n`, except the last one if the buffer's last line had
+    /// no line break.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `permutation.len() != self.len()`.
+    pub fn to_sorted_vec(&self, permutation: &Permutation) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.data.len() + 1);
+        self.write_pieces(permutation, |piece| {
+            out.extend_from_slice(piece);
+            Ok::<(), core::convert::Infallible>(())
+        })
+        .unwrap_or_else(|never| match never {});
+        out
+    }
+
+    /// Writes the lines in the order given by `permutation`, each unchanged.
+    ///
+    /// See [`to_sorted_vec`](Self::to_sorted_vec).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `permutation.len() != self.len()`.
+    #[cfg(feature = "std")]
+    pub fn write_sorted<W: std::io::Write>(
+        &self,
+        permutation: &Permutation,
+        mut out: W,
+    ) -> std::io::Result<()> {
+        self.write_pieces(permutation, |piece| out.write_all(piece))
+    }
+
+    fn write_pieces<E>(
+        &self,
+        permutation: &Permutation,
+        mut write: impl FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        assert_eq!(
+            permutation.len(),
+            self.len(),
+            "JSON Lines index and permutation must have the same length"
+        );
+
+        let terminated = self.data.last() == Some(&b'\n');
+        let last = permutation.len().saturating_sub(1);
+        for (position, &index) in permutation.iter().enumerate() {
+            write(self.line(index))?;
+            if terminated || position != last {
+                write(b"\n")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Locates values in one line of JSON.
+///
+/// Errors are byte offsets into the line.
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    /// Returns the range of the value at `path`, or `None` if there is none.
+    fn find(mut self, path: &JsonPath) -> Result<Option<Range<usize>>, usize> {
+        self.skip_whitespace();
+        if self.pos == self.bytes.len() {
+            return Ok(None);
+        }
+
+        for segment in &path.segments {
+            let found = match self.peek()? {
+                b'{' => self.enter_member(segment.as_bytes())?,
+                b'[' => match segment.parse() {
+                    Ok(position) => self.enter_element(position)?,
+                    Err(_) => false,
+                },
+                _ => false,
+            };
+            if !found {
+                return Ok(None);
+            }
+        }
+
+        let start = self.pos;
+        self.skip_value()?;
+        Ok(Some(start..self.pos))
+    }
+
+    /// Moves from the start of an object to the value of member `name`.
+    fn enter_member(&mut self, name: &[u8]) -> Result<bool, usize> {
+        self.pos += 1;
+        self.skip_whitespace();
+        if self.peek()? == b'}' {
+            return Ok(false);
+        }
+
+        let mut decoded = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let key = self.pos;
+            self.skip_string()?;
+            let raw = &self.bytes[key + 1..self.pos - 1];
+            let matches = match raw.contains(&b'\\') {
+                false => raw == name,
+                true => {
+                    decoded.clear();
+                    decode_string(raw, &mut decoded).map_err(|n| key + 1 + n)?;
+                    decoded == name
+                }
+            };
+
+            self.skip_whitespace();
+            self.expect(b':')?;
+            self.skip_whitespace();
+            if matches {
+                return Ok(true);
+            }
+            self.skip_value()?;
+
+            self.skip_whitespace();
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b'}' => return Ok(false),
+                _ => return Err(self.pos),
+            }
+        }
+    }
+
+    /// Moves from the start of an array to element `position`.
+    fn enter_element(&mut self, position: usize) -> Result<bool, usize> {
+        self.pos += 1;
+        self.skip_whitespace();
+        if self.peek()? == b']' {
+            return Ok(false);
+        }
+
+        for _ in 0..position {
+            self.skip_whitespace();
+            self.skip_value()?;
+            self.skip_whitespace();
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b']' => return Ok(false),
+                _ => return Err(self.pos),
+            }
+        }
+        self.skip_whitespace();
+        Ok(true)
+    }
+
+    /// Skips a value, without validating the contents of arrays and objects.
+    fn skip_value(&mut self) -> Result<(), usize> {
+        match self.peek()? {
+            b'"' => self.skip_string(),
+            b'{' | b'[' => {
+                let mut depth = 0usize;
+                loop {
+                    match self.peek()? {
+                        b'"' => {
+                            self.skip_string()?;
+                            continue;
+                        }
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => depth -= 1,
+                        _ => {}
+                    }
+                    self.pos += 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+            }
+            _ => {
+                let start = self.pos;
+                while self.pos < self.bytes.len()
+                    && !matches!(self.bytes[self.pos], b',' | b'}' | b']')
+                    && !is_whitespace(self.bytes[self.pos])
+                {
+                    self.pos += 1;
+                }
+                match self.pos > start {
+                    true => Ok(()),
+                    false => Err(start),
+                }
+            }
+        }
+    }
+
+    /// Skips a string, including its quotes.
+    fn skip_string(&mut self) -> Result<(), usize> {
+        self.expect(b'"')?;
+        loop {
+            match self.peek()? {
+                b'"' => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                b'\\' => self.pos += 2,
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.bytes.len() && is_whitespace(self.bytes[self.pos]) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Result<u8, usize> {
+        self.bytes.get(self.pos).copied().ok_or(self.pos)
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), usize> {
+        match self.peek()? == byte {
+            true => {
+                self.pos += 1;
+                Ok(())
+            }
+            false => Err(self.pos),
+        }
+    }
+}
+
+#[inline(always)]
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+/// Appends the encoding of the value at `range` of `line` to `key`.
+fn encode_value(line: &[u8], range: Range<usize>, key: &mut Vec<u8>) -> Result<(), usize> {
+    let text = &line[range.clone()];
+    match text[0] {
+        b'"' => {
+            let mut decoded = Vec::new();
+            decode_string(&text[1..text.len() - 1], &mut decoded)
+                .map_err(|n| range.start + 1 + n)?;
+            key.push(TAG_STRING);
+            encode_bytes(&decoded, key);
+        }
+        b'{' | b'[' => {
+            key.push(TAG_COMPOSITE);
+            encode_bytes(text, key);
+        }
+        _ => match text {
+            b"null" => key.push(TAG_NULL),
+            b"false" => key.push(TAG_FALSE),
+            b"true" => key.push(TAG_TRUE),
+            _ => encode_number(text, key).map_err(|n| range.start + n)?,
+        },
+    }
+    Ok(())
+}
+
+/// Appends `bytes` with `0x00` escaped, followed by the terminator.
+fn encode_bytes(bytes: &[u8], key: &mut Vec<u8>) {
+    for &byte in bytes {
+        match byte {
+            0 => key.extend_from_slice(&ESCAPED_NUL),
+            _ => key.push(byte),
+        }
+    }
+    key.extend_from_slice(&BYTES_END);
+}
+
+/// Appends the encoding of a JSON number.
+///
+/// A non-zero number `±0.d1d2...dn × 10^e` (with `d1` and `dn` non-zero) is encoded as its
+/// sign tag, `e` as a biased big-endian `u16`, the digits, and a terminator below every
+/// digit. Negative numbers complement everything after the tag.
+fn encode_number(text: &[u8], key: &mut Vec<u8>) -> Result<(), usize> {
+    let negative = text[0] == b'-';
+    let mut pos = usize::from(negative);
+    let digits_run = |pos: usize| {
+        text[pos..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+
+    let int_len = digits_run(pos);
+    if int_len == 0 {
+        return Err(pos);
+    }
+    let int = &text[pos..pos + int_len];
+    pos += int_len;
+
+    let mut frac: &[u8] = &[];
+    if text.get(pos) == Some(&b'.') {
+        let frac_len = digits_run(pos + 1);
+        if frac_len == 0 {
+            return Err(pos + 1);
+        }
+        frac = &text[pos + 1..pos + 1 + frac_len];
+        pos += 1 + frac_len;
+    }
+
+    let mut exponent: i64 = 0;
+    if matches!(text.get(pos), Some(b'e' | b'E')) {
+        pos += 1;
+        let exp_negative = text.get(pos) == Some(&b'-');
+        if matches!(text.get(pos), Some(b'+' | b'-')) {
+            pos += 1;
+        }
+        let exp_len = digits_run(pos);
+        if exp_len == 0 {
+            return Err(pos);
+        }
+        for &digit in &text[pos..pos + exp_len] {
+            exponent = exponent
+                .saturating_mul(10)
+                .saturating_add(i64::from(digit - b'0'));
+        }
+        if exp_negative {
+            exponent = -exponent;
+        }
+        pos += exp_len;
+    }
+    if pos != text.len() {
+        return Err(pos);
+    }
+
+    // value = 0.(int frac) × 10^(int.len() + exponent), then drop leading and trailing zeros.
+    let leading = int
+        .iter()
+        .chain(frac)
+        .take_while(|&&digit| digit == b'0')
+        .count();
+    if leading == int.len() + frac.len() {
+        key.push(TAG_ZERO);
+        return Ok(());
+    }
+    let exponent = exponent
+        .saturating_add(int.len() as i64)
+        .saturating_sub(leading as i64)
+        .clamp(i64::from(i16::MIN), i64::from(i16::MAX)) as i16;
+
+    let mask = if negative { 0xFF } else { 0x00 };
+    key.push(if negative { TAG_NEGATIVE } else { TAG_POSITIVE });
+    let biased = (exponent as u16) ^ 0x8000;
+    key.extend(biased.to_be_bytes().map(|b| b ^ mask));
+
+    let significant: Vec<u8> = int.iter().chain(frac).skip(leading).copied().collect();
+    let trailing = significant.iter().rev().take_while(|&&d| d == b'0').count();
+    key.extend(
+        significant[..significant.len() - trailing]
+            .iter()
+            .map(|digit| digit ^ mask),
+    );
+    key.push(mask);
+    Ok(())
+}
+
+/// Appends the UTF-8 text of a JSON string body (without quotes) to `out`.
+///
+/// Errors are offsets into `raw`. Unpaired surrogates decode to U+FFFD.
+fn decode_string(raw: &[u8], out: &mut Vec<u8>) -> Result<(), usize> {
+    let mut pos = 0;
+    while let Some(n) = raw[pos..].iter().position(|&b| b == b'\\') {
+        out.extend_from_slice(&raw[pos..pos + n]);
+        pos += n;
+
+        let escaped = *raw.get(pos + 1).ok_or(pos)?;
+        let byte = match escaped {
+            b'"' => b'"',
+            b'\\' => b'\\',
+            b'/' => b'/',
+            b'b' => 0x08,
+            b'f' => 0x0C,
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'u' => {
+                let high = hex4(raw, pos + 2).ok_or(pos)?;
+                pos += 6;
+                let mut code = u32::from(high);
+                if (0xD800..0xDC00).contains(&high)
+                    && raw[pos..].starts_with(b"\\u")
+                    && let Some(low @ 0xDC00..0xE000) = hex4(raw, pos + 2)
+                {
+                    code = 0x10000 + ((code - 0xD800) << 10) + (u32::from(low) - 0xDC00);
+                    pos += 6;
+                }
+                let c = char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER);
+                out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                continue;
+            }
+            _ => return Err(pos),
+        };
+        out.push(byte);
+        pos += 2;
+    }
+    out.extend_from_slice(&raw[pos..]);
+    Ok(())
+}
+
+/// Parses four hex digits at `pos`.
+fn hex4(raw: &[u8], pos: usize) -> Option<u16> {
+    let digits = raw.get(pos..pos + 4)?;
+    digits.iter().try_fold(0u16, |value, &digit| {
+        let nibble = (digit as char).to_digit(16)?;
+        Some(value << 4 | nibble as u16)
+    })
+}
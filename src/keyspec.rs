@@ -0,0 +1,692 @@
+//! Field and key selection for line sorting, with `sort -t` / `sort -k` semantics.
+//!
+//! A [`KeySpec`] describes which parts of a line to compare and how, using the option
+//! syntax of POSIX `sort`:
+//!
+//! | Option | Meaning |
+//! |--------|---------|
+//! | `-t SEP` | Fields are separated by the byte `SEP` (`\t` and `\0` are accepted as escapes). Without it, fields are separated by the transition from a blank to a non-blank, and leading blanks belong to the field. |
+//! | `-k POS1[,POS2]` | Compare the bytes from `POS1` to `POS2` (inclusive, default: end of line). A position is `F[.C][OPTS]`: field `F`, character `C` (1-based; an end character of 0 means the end of the field). May be repeated; later keys break ties of earlier ones. |
+//! | `-b` | Ignore leading blanks when locating a key's start (or its end character). |
+//! | `-f` | Fold lower case to upper case. |
+//! | `-r` | Reverse the result of comparisons. |
+//! | `-s` | Stable: keep input order for lines whose keys are equal. |
+//!
+//! `b`, `f` and `r` may also be attached to a key position (`-k3,3r`). A key with any of
+//! them ignores the global flags; keys without them inherit the global ones. Without any
+//! `-k`, the whole line is the key. Unless `-s` is given, lines whose keys all compare
+//! equal are ordered by their bytes (reversed with `-r`), so the order is fully determined.
+//! Blanks are spaces and tabs; characters are bytes.
+//!
+//! ## Encoding
+//!
+//! A `KeySpec` is a [`Collator`]. Its collation key concatenates one component per key: the
+//! selected bytes (case-folded with `f`), with every `0x00` escaped as `0x00 0xFF`, followed
+//! by the terminator `0x00 0x01`. A reverse key complements all bytes of its component. The
+//! components are prefix-free, so concatenating them compares key by key, and the sort
+//! still caches the first 8 collation bytes. Fields are located while the collation key is
+//! generated, and only as far as the sort asks for it.
+
+use crate::collate::{Collator, Emitter, compare_collation_keys, orasort_collated};
+use crate::core::KeyAccessor;
+use crate::error::KeySpecError;
+use crate::permutation::Permutation;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ops::Range;
+use core::str::FromStr;
+
+/// Emitted in place of a `0x00` key byte.
+const ESCAPED_NUL: [u8; 2] = [0x00, 0xFF];
+
+/// Emitted at the end of every key component.
+const COMPONENT_END: [u8; 2] = [0x00, 0x01];
+
+/// Size of the buffer key components are transformed in.
+const COMPONENT_CHUNK: usize = 64;
+
+/// One `-k` key definition, with its flags resolved against the global ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct KeyField {
+    /// Zero-based field the key starts in.
+    start_field: usize,
+    /// Zero-based byte within the start field.
+    start_char: usize,
+    /// Zero-based field the key ends in; `None` extends the key to the end of the line.
+    end_field: Option<usize>,
+    /// Number of bytes of the end field included; `None` includes the whole field.
+    end_char: Option<usize>,
+    skip_start_blanks: bool,
+    skip_end_blanks: bool,
+    fold_case: bool,
+    reverse: bool,
+}
+
+impl KeyField {
+    /// The whole line, as used when no `-k` is given.
+    const WHOLE_LINE: Self = Self {
+        start_field: 0,
+        start_char: 0,
+        end_field: None,
+        end_char: None,
+        skip_start_blanks: false,
+        skip_end_blanks: false,
+        fold_case: false,
+        reverse: false,
+    };
+}
+
+/// Flags given on the command line or attached to a key position.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Flags {
+    blanks: bool,
+    fold_case: bool,
+    reverse: bool,
+}
+
+impl Flags {
+    fn any(self) -> bool {
+        self.blanks || self.fold_case || self.reverse
+    }
+}
+
+/// Which parts of a line to compare, and how.
+///
+/// See the [module documentation](self) for the syntax.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::keyspec::KeySpec;
+///
+/// // By the third tab-separated column, then by the first one, descending.
+/// let spec = KeySpec::parse(r"-t '\t' -k3,3 -k1,1r").unwrap();
+///
+/// let lines = vec!["a\tx\t2", "b\ty\t1", "c\tz\t2"];
+/// let sorted = spec.sort(&lines).gather(&lines);
+///
+/// assert_eq!(sorted, vec!["b\ty\t1", "c\tz\t2", "a\tx\t2"]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeySpec {
+    separator: Option<u8>,
+    keys: Vec<KeyField>,
+    reverse: bool,
+    stable: bool,
+}
+
+impl Default for KeySpec {
+    /// Compares whole lines bytewise.
+    fn default() -> Self {
+        Self {
+            separator: None,
+            keys: alloc::vec![KeyField::WHOLE_LINE],
+            reverse: false,
+            stable: false,
+        }
+    }
+}
+
+impl KeySpec {
+    /// Parses a specification from a single string of options.
+    ///
+    /// Options are split at whitespace; single and double quotes group words as in a
+    /// shell, but backslashes are kept as they are.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`KeySpecError`] if the string contains a malformed or unknown option.
+    pub fn parse(spec: &str) -> Result<Self, KeySpecError> {
+        Self::from_args(split_words(spec)?)
+    }
+
+    /// Parses a specification from separate arguments, as passed to a command-line tool.
+    ///
+    /// Short options may be combined (`-bf`) and their values attached (`-k2`, `-t,`); the
+    /// long forms `--field-separator`, `--key`, `--ignore-leading-blanks`, `--ignore-case`,
+    /// `--reverse` and `--stable` are accepted as well.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`KeySpecError`] for malformed or unknown options, and for arguments that
+    /// are not options.
+    pub fn from_args<I, S>(args: I) -> Result<Self, KeySpecError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut parser = Parser::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let arg = arg.as_ref();
+            let mut value = |option: &str, inline: &str| -> Result<String, KeySpecError> {
+                if !inline.is_empty() {
+                    return Ok(inline.to_string());
+                }
+                args.next()
+                    .map(|value| value.as_ref().to_string())
+                    .ok_or_else(|| KeySpecError::MissingValue(option.to_string()))
+            };
+
+            if let Some(long) = arg.strip_prefix("--") {
+                let (name, inline) = long.split_once('=').unwrap_or((long, ""));
+                match name {
+                    "field-separator" => parser.separator(&value(arg, inline)?)?,
+                    "key" => parser.key(&value(arg, inline)?)?,
+                    "ignore-leading-blanks" => parser.flags.blanks = true,
+                    "ignore-case" => parser.flags.fold_case = true,
+                    "reverse" => parser.flags.reverse = true,
+                    "stable" => parser.stable = true,
+                    _ => return Err(KeySpecError::UnknownOption(arg.to_string())),
+                }
+                continue;
+            }
+
+            let Some(flags) = arg.strip_prefix('-').filter(|flags| !flags.is_empty()) else {
+                return Err(KeySpecError::UnknownOption(arg.to_string()));
+            };
+            for (at, flag) in flags.char_indices() {
+                let rest = &flags[at + flag.len_utf8()..];
+                match flag {
+                    't' => {
+                        parser.separator(&value("-t", rest)?)?;
+                        break;
+                    }
+                    'k' => {
+                        parser.key(&value("-k", rest)?)?;
+                        break;
+                    }
+                    'b' => parser.flags.blanks = true,
+                    'f' => parser.flags.fold_case = true,
+                    'r' => parser.flags.reverse = true,
+                    's' => parser.stable = true,
+                    _ => return Err(KeySpecError::UnknownOption(alloc::format!("-{}", flag))),
+                }
+            }
+        }
+
+        Ok(parser.finish())
+    }
+
+    /// Returns the field separator, or `None` if fields are separated by blanks.
+    pub fn separator(&self) -> Option<u8> {
+        self.separator
+    }
+
+    /// Returns the number of keys compared; `1` when no `-k` was given.
+    pub fn key_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns `true` if lines with equal keys keep their input order.
+    pub fn is_stable(&self) -> bool {
+        self.stable
+    }
+
+    /// Returns the bytes of `line` selected by each key, before case folding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use orasort::keyspec::KeySpec;
+    ///
+    /// let spec = KeySpec::parse("-k2,2 -k3.2b,3").unwrap();
+    /// let fields: Vec<&[u8]> = spec.fields(b"x  alpha   beta").collect();
+    ///
+    /// assert_eq!(fields, vec![&b"  alpha"[..], b"eta"]);
+    /// ```
+    pub fn fields<'a>(&'a self, line: &'a [u8]) -> impl Iterator<Item = &'a [u8]> + 'a {
+        self.keys
+            .iter()
+            .map(move |key| &line[self.key_range(key, line)])
+    }
+
+    /// Compares two lines.
+    pub fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        compare_collation_keys(self, a, b)
+    }
+
+    /// Returns `true` if all keys of `a` and `b` compare equal, regardless of the
+    /// whole-line comparison that otherwise breaks ties.
+    pub fn keys_equal(&self, a: &[u8], b: &[u8]) -> bool {
+        compare_collation_keys(&KeysOnly(self), a, b) == Ordering::Equal
+    }
+
+    /// Sorts the lines of `lines` by this specification.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use orasort::keyspec::KeySpec;
+    ///
+    /// let lines = vec!["b 2", "a 2", "c 1"];
+    /// let spec = KeySpec::parse("-s -k2").unwrap();
+    ///
+    /// assert_eq!(spec.sort(&lines), vec![2, 0, 1]);
+    /// ```
+    pub fn sort<T: KeyAccessor + ?Sized>(&self, lines: &T) -> Permutation {
+        let order = orasort_collated(lines, self);
+        if !self.stable {
+            return order;
+        }
+
+        // Lines with equal keys tie, and the sort is unstable: restore their input order.
+        let mut indices = order.into_vec();
+        let mut start = 0;
+        while start < indices.len() {
+            let first = lines.get_key(indices[start]);
+            let run = indices[start + 1..]
+                .iter()
+                .take_while(|&&index| self.keys_equal(first, lines.get_key(index)))
+                .count();
+            indices[start..=start + run].sort_unstable();
+            start += run + 1;
+        }
+        Permutation::from_vec_unchecked(indices)
+    }
+
+    /// Writes the next bytes of the collation key of `line` into `out`, continuing where
+    /// `state` says.
+    fn emit(
+        &self,
+        line: &[u8],
+        state: &mut KeySpecState,
+        out: &mut [u8],
+        last_resort: bool,
+    ) -> usize {
+        if out.is_empty() {
+            return 0;
+        }
+        let mut emitter = Emitter::new(state.written, out);
+        state.written = 0;
+        self.generate(line, state, &mut emitter, last_resort);
+        emitter.written()
+    }
+
+    /// Skips the next `count` bytes of the collation key of `line` without writing them.
+    fn skip(
+        &self,
+        line: &[u8],
+        state: &mut KeySpecState,
+        count: usize,
+        last_resort: bool,
+    ) -> usize {
+        let mut emitter = Emitter::new(state.written + count, &mut []);
+        state.written = 0;
+        self.generate(line, state, &mut emitter, last_resort);
+        count - emitter.room()
+    }
+
+    /// Emits the collation key of `line` from where `state` stands until `emitter` is full.
+    fn generate(
+        &self,
+        line: &[u8],
+        state: &mut KeySpecState,
+        emitter: &mut Emitter<'_>,
+        last_resort: bool,
+    ) {
+        while !emitter.is_full() {
+            let key = self.keys.get(state.component);
+            let (fold_case, reverse) = match key {
+                Some(key) => (key.fold_case, key.reverse),
+                None if last_resort && state.component == self.keys.len() => (false, self.reverse),
+                None => break,
+            };
+            let rest = state.rest.get_or_insert_with(|| match key {
+                Some(key) => self.key_range(key, line),
+                None => 0..line.len(),
+            });
+
+            if emit_component(emitter, line, rest, fold_case, reverse, &mut state.written) {
+                state.component += 1;
+                state.rest = None;
+            }
+        }
+    }
+
+    /// Returns the byte range of `line` selected by `key`.
+    fn key_range(&self, key: &KeyField, line: &[u8]) -> Range<usize> {
+        let mut start = self.field_start(line, key.start_field);
+        if key.skip_start_blanks {
+            start = skip_blanks(line, start);
+        }
+        start = line.len().min(start + key.start_char);
+
+        let end = match key.end_field {
+            None => line.len(),
+            Some(field) => {
+                let field_start = self.field_start(line, field);
+                match key.end_char {
+                    None => self.field_end(line, field_start),
+                    Some(chars) => {
+                        let from = match key.skip_end_blanks {
+                            true => skip_blanks(line, field_start),
+                            false => field_start,
+                        };
+                        line.len().min(from + chars)
+                    }
+                }
+            }
+        };
+
+        start..end.max(start)
+    }
+
+    /// Returns the position where zero-based field `field` starts, or the end of the line.
+    fn field_start(&self, line: &[u8], field: usize) -> usize {
+        let mut pos = 0;
+        for _ in 0..field {
+            pos = match self.separator {
+                Some(separator) => match line[pos..].iter().position(|&b| b == separator) {
+                    Some(n) => pos + n + 1,
+                    None => return line.len(),
+                },
+                None => self.field_end(line, pos),
+            };
+        }
+        pos
+    }
+
+    /// Returns the end of the field starting at `start`.
+    fn field_end(&self, line: &[u8], start: usize) -> usize {
+        match self.separator {
+            Some(separator) => line[start..]
+                .iter()
+                .position(|&b| b == separator)
+                .map_or(line.len(), |n| start + n),
+            None => {
+                let word = skip_blanks(line, start);
+                line[word..]
+                    .iter()
+                    .position(|&b| is_blank(b))
+                    .map_or(line.len(), |n| word + n)
+            }
+        }
+    }
+}
+
+impl Collator for KeySpec {
+    type State = KeySpecState;
+
+    fn collation_bytes(&self, key: &[u8], state: &mut KeySpecState, out: &mut [u8]) -> usize {
+        self.emit(key, state, out, !self.stable)
+    }
+
+    fn skip_collation_bytes(&self, key: &[u8], state: &mut KeySpecState, count: usize) -> usize {
+        self.skip(key, state, count, !self.stable)
+    }
+}
+
+/// Where generation of a [`KeySpec`] collation key stopped (see [`Collator::State`]).
+#[derive(Clone, Debug, Default)]
+pub struct KeySpecState {
+    /// Component being generated: a key, or the whole line after the last key.
+    component: usize,
+    /// Bytes of the component not generated yet; `None` until its key is located.
+    rest: Option<Range<usize>>,
+    /// Bytes of the escape or terminator starting `rest` that an earlier call wrote.
+    written: usize,
+}
+
+impl FromStr for KeySpec {
+    type Err = KeySpecError;
+
+    fn from_str(spec: &str) -> Result<Self, KeySpecError> {
+        Self::parse(spec)
+    }
+}
+
+/// The collation of a [`KeySpec`] without the whole-line comparison.
+struct KeysOnly<'a>(&'a KeySpec);
+
+impl Collator for KeysOnly<'_> {
+    type State = KeySpecState;
+
+    fn collation_bytes(&self, key: &[u8], state: &mut KeySpecState, out: &mut [u8]) -> usize {
+        self.0.emit(key, state, out, false)
+    }
+
+    fn skip_collation_bytes(&self, key: &[u8], state: &mut KeySpecState, count: usize) -> usize {
+        self.0.skip(key, state, count, false)
+    }
+}
+
+/// Collects options into a [`KeySpec`].
+#[derive(Default)]
+struct Parser {
+    separator: Option<u8>,
+    /// Keys with the flags attached to them.
+    keys: Vec<(KeyField, Flags)>,
+    flags: Flags,
+    stable: bool,
+}
+
+impl Parser {
+    fn separator(&mut self, value: &str) -> Result<(), KeySpecError> {
+        self.separator = Some(match value.as_bytes() {
+            [byte] => *byte,
+            b"\\t" => b'\t',
+            b"\\0" => b'\0',
+            b"\\\\" => b'\\',
+            _ => return Err(KeySpecError::InvalidSeparator(value.to_string())),
+        });
+        Ok(())
+    }
+
+    fn key(&mut self, value: &str) -> Result<(), KeySpecError> {
+        let invalid = |reason| KeySpecError::InvalidKey {
+            key: value.to_string(),
+            reason,
+        };
+
+        let (start, end) = match value.split_once(',') {
+            Some((start, end)) => (start, Some(end)),
+            None => (value, None),
+        };
+
+        let (start_field, start_char, start_flags) = parse_position(start).map_err(invalid)?;
+        let start_char = match start_char {
+            None => 0,
+            Some(0) => return Err(invalid("character positions start at 1")),
+            Some(chars) => chars - 1,
+        };
+
+        let mut key = KeyField {
+            start_field,
+            start_char,
+            skip_start_blanks: start_flags.blanks,
+            fold_case: start_flags.fold_case,
+            reverse: start_flags.reverse,
+            ..KeyField::WHOLE_LINE
+        };
+        let mut flags = start_flags;
+
+        if let Some(end) = end {
+            let (end_field, end_char, end_flags) = parse_position(end).map_err(invalid)?;
+            key.end_field = Some(end_field);
+            key.end_char = end_char.filter(|&chars| chars > 0);
+            key.skip_end_blanks = end_flags.blanks;
+            key.fold_case |= end_flags.fold_case;
+            key.reverse |= end_flags.reverse;
+            flags.blanks |= end_flags.blanks;
+            flags.fold_case |= end_flags.fold_case;
+            flags.reverse |= end_flags.reverse;
+        }
+
+        self.keys.push((key, flags));
+        Ok(())
+    }
+
+    fn finish(self) -> KeySpec {
+        let global = self.flags;
+        let inherit = |mut key: KeyField| {
+            key.skip_start_blanks = global.blanks;
+            key.skip_end_blanks = global.blanks;
+            key.fold_case = global.fold_case;
+            key.reverse = global.reverse;
+            key
+        };
+
+        let keys = match self.keys.is_empty() {
+            true => alloc::vec![inherit(KeyField::WHOLE_LINE)],
+            false => self
+                .keys
+                .into_iter()
+                .map(|(key, own)| if own.any() { key } else { inherit(key) })
+                .collect(),
+        };
+
+        KeySpec {
+            separator: self.separator,
+            keys,
+            reverse: global.reverse,
+            stable: self.stable,
+        }
+    }
+}
+
+/// Parses `F[.C][OPTS]` into a zero-based field, the character number and the flags.
+fn parse_position(text: &str) -> Result<(usize, Option<usize>, Flags), &'static str> {
+    let digits = |s: &str| s.bytes().take_while(u8::is_ascii_digit).count();
+
+    let field_len = digits(text);
+    let field: usize = text[..field_len]
+        .parse()
+        .map_err(|_| "missing field number")?;
+    if field == 0 {
+        return Err("field numbers start at 1");
+    }
+    let mut rest = &text[field_len..];
+
+    let mut chars = None;
+    if let Some(after_dot) = rest.strip_prefix('.') {
+        let chars_len = digits(after_dot);
+        chars = Some(
+            after_dot[..chars_len]
+                .parse()
+                .map_err(|_| "missing character number")?,
+        );
+        rest = &after_dot[chars_len..];
+    }
+
+    let mut flags = Flags::default();
+    for flag in rest.chars() {
+        match flag {
+            'b' => flags.blanks = true,
+            'f' => flags.fold_case = true,
+            'r' => flags.reverse = true,
+            _ => return Err("unsupported ordering option"),
+        }
+    }
+
+    Ok((field - 1, chars, flags))
+}
+
+/// Splits `text` into words at whitespace, honouring single and double quotes.
+fn split_words(text: &str) -> Result<Vec<String>, KeySpecError> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+
+    for c in text.chars() {
+        match quote {
+            Some(open) if c == open => quote = None,
+            Some(_) => word.get_or_insert_with(String::new).push(c),
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            None if c.is_whitespace() => words.extend(word.take()),
+            None => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err(KeySpecError::UnterminatedQuote);
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Emits the bytes `line[rest]` of a key component and its terminator (see the module
+/// documentation), advancing `rest` past the bytes written.
+///
+/// Returns `true` once the whole component is out. If `out` fills up in the middle of an
+/// escape or the terminator, `written` records how much of it was written.
+fn emit_component(
+    emitter: &mut Emitter<'_>,
+    line: &[u8],
+    rest: &mut Range<usize>,
+    fold_case: bool,
+    reverse: bool,
+    written: &mut usize,
+) -> bool {
+    let mask = if reverse { 0xFF } else { 0x00 };
+    let mut buf = [0u8; COMPONENT_CHUNK];
+
+    while rest.start < rest.end {
+        if emitter.is_full() {
+            return false;
+        }
+
+        if line[rest.start] == 0 {
+            let escape = ESCAPED_NUL.map(|byte| byte ^ mask);
+            let done = emitter.extend(&escape);
+            if done < escape.len() {
+                *written = done;
+                return false;
+            }
+            rest.start += 1;
+            continue;
+        }
+
+        // Pass over the other bytes that are only skipped without transforming them.
+        let skip_end = rest.end.min(rest.start + emitter.skipping());
+        let plain_end = line[rest.start..skip_end]
+            .iter()
+            .position(|&byte| byte == 0)
+            .map_or(skip_end, |n| rest.start + n);
+        if plain_end > rest.start {
+            rest.start += emitter.extend(&line[rest.start..plain_end]);
+            continue;
+        }
+
+        // Transform the run of other bytes, as far as it fits.
+        let end = rest
+            .end
+            .min(rest.start + COMPONENT_CHUNK.min(emitter.room()));
+        let mut len = 0;
+        for &byte in line[rest.start..end].iter().take_while(|&&byte| byte != 0) {
+            let byte = if fold_case {
+                byte.to_ascii_uppercase()
+            } else {
+                byte
+            };
+            buf[len] = byte ^ mask;
+            len += 1;
+        }
+        rest.start += emitter.extend(&buf[..len]);
+    }
+
+    let terminator = COMPONENT_END.map(|byte| byte ^ mask);
+    let done = emitter.extend(&terminator);
+    if done < terminator.len() {
+        *written = done;
+        return false;
+    }
+    true
+}
+
+#[inline(always)]
+fn is_blank(byte: u8) -> bool {
+    byte == b' ' || byte == b'\t'
+}
+
+fn skip_blanks(line: &[u8], pos: usize) -> usize {
+    line[pos..]
+        .iter()
+        .position(|&b| !is_blank(b))
+        .map_or(line.len(), |n| pos + n)
+}
@@ -0,0 +1,98 @@
+//! Longest common prefixes of neighbouring sorted keys.
+//!
+//! Front coding, tries and suffix-array style algorithms need the length of the prefix every
+//! sorted key shares with its predecessor. The sort already establishes most of it: all
+//! keys of a radix bucket share the bytes the bucket was split on. [`orasort_lcp`] records
+//! those lengths while sorting and only compares the bytes after them afterwards.
+
+use crate::algo::{Scratch, SortKeys, sort_pointers};
+use crate::core::{KeyAccessor, SortPtr};
+use crate::permutation::Permutation;
+use alloc::vec::Vec;
+use core::cell::Cell;
+
+/// Sorts `provider` and returns the sorted order together with the length of the longest
+/// common prefix of every sorted key and its predecessor.
+///
+/// `lcp[i]` belongs to the keys at `order[i - 1]` and `order[i]`; `lcp[0]` is 0.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::orasort_lcp;
+///
+/// let words = vec!["carton", "car", "cat", "dog", "cart"];
+/// let (order, lcp) = orasort_lcp(&words);
+///
+/// assert_eq!(order, vec![1, 4, 0, 2, 3]); // car, cart, carton, cat, dog
+/// assert_eq!(lcp, vec![0, 3, 4, 2, 0]);
+/// ```
+pub fn orasort_lcp<T: KeyAccessor + ?Sized>(provider: &T) -> (Permutation, Vec<usize>) {
+    let len = provider.len();
+    let mut pointers: Vec<SortPtr> = (0..len)
+        .map(|index| {
+            let cache = provider.get_u64_prefix(index, 0);
+            SortPtr { index, cache }
+        })
+        .collect();
+
+    let tracker = LcpTracker {
+        inner: provider,
+        base: pointers.as_ptr() as usize,
+        floors: (0..len).map(|_| Cell::new(0)).collect(),
+    };
+    let merged = sort_pointers(&tracker, &mut pointers, 0, &mut Scratch::default());
+
+    let mut lcp: Vec<usize> = tracker.floors.into_iter().map(Cell::into_inner).collect();
+    if merged {
+        // Merging runs moved pointers away from the positions the floors refer to.
+        lcp.fill(0);
+    }
+
+    for position in 1..len {
+        let previous = provider.get_key(pointers[position - 1].index);
+        let current = provider.get_key(pointers[position].index);
+        let floor = lcp[position].min(previous.len()).min(current.len());
+        lcp[position] = floor
+            + previous[floor..]
+                .iter()
+                .zip(&current[floor..])
+                .take_while(|(a, b)| a == b)
+                .count();
+    }
+
+    let order = pointers.into_iter().map(|p| p.index).collect();
+    (Permutation::from_vec_unchecked(order), lcp)
+}
+
+/// Wraps a provider and records, per sorted position, a lower bound of the prefix the key
+/// there shares with its predecessor.
+///
+/// Bounds count zero padding like the sort does, so they are capped at the key lengths
+/// before use.
+struct LcpTracker<'a, T: KeyAccessor + ?Sized> {
+    inner: &'a T,
+    /// Address of the sorted pointer array, to turn sub-slices into positions.
+    base: usize,
+    floors: Vec<Cell<usize>>,
+}
+
+impl<T: KeyAccessor + ?Sized> SortKeys for LcpTracker<'_, T> {
+    #[inline(always)]
+    fn key(&self, index: usize) -> &[u8] {
+        self.inner.get_key(index)
+    }
+
+    #[inline(always)]
+    fn prefix(&self, index: usize, offset: usize) -> u64 {
+        self.inner.get_u64_prefix(index, offset)
+    }
+
+    fn shared_prefix(&self, ptrs: &[SortPtr], cp_len: usize) {
+        let start = (ptrs.as_ptr() as usize - self.base) / size_of::<SortPtr>();
+        // The first key's predecessor lies outside `ptrs`; deeper calls only raise bounds.
+        for floor in self.floors[start..start + ptrs.len()].iter().skip(1) {
+            floor.set(cp_len);
+        }
+    }
+}
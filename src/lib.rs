@@ -15,7 +15,50 @@
 //!   Radix Sort (for large partitions) to maintain optimal performance across various distributions.
 //! - **Zero-Copy abstractions**: The [`KeyAccessor`] trait allows sorting arbitrary data structures
 //!   (e.g., Arrow arrays, `Vec<Vec<u8>>`) without copying the underlying data.
+//! - **Combined Collections**: [`Concat`], [`ConcatMany`] and [`Subset`] sort the union of
+//!   several collections, or selected keys of one, without copying keys, and map sorted
+//!   indices back to their sources.
+//! - **Fallible Keys**: [`TryKeyAccessor`] and [`try_orasort`] sort keys whose fetch can fail,
+//!   returning the first error without leaving data half-permuted.
+//! - **Packed Keys**: [`KeyArena`] stores keys contiguously with zero padding, so every prefix
+//!   load is a single unaligned read.
+//! - **Memory-Mapped Keys**: `mmap::MmapKeys` (feature `mmap`) sorts keys stored as a
+//!   data file plus a 32- or 64-bit offsets file, in place, without loading them.
+//! - **Framed Records**: [`framed::FramedRecords`] sorts buffers of varint- or
+//!   `u32`-length-prefixed records by their bodies and writes the frames back in order.
+//! - **Text Lines**: [`lines::Lines`] indexes the `\n`-, `\r\n`- or NUL-delimited lines of a
+//!   buffer with a word-at-a-time scan and writes them back sorted.
 //! - **In-Place Mutation**: Provides [`orasort_mut`] for sorting `Vec`s in-place with minimal allocation.
+//! - **Permutations**: Sorts return a [`Permutation`] that can reorder, gather, invert and
+//!   compose, and reorder several struct-of-arrays columns in one pass.
+//! - **Saved Permutations**: [`persist::PermutationFile`] saves a sorted order, with
+//!   optional prefixes and LCPs, for other processes to map back in place; files saved for
+//!   different keys are refused.
+//! - **Ranks**: [`orasort_ranks`] computes `ROW_NUMBER`, `RANK` and `DENSE_RANK` style ranks,
+//!   detecting ties during the sort.
+//! - **Searchable Indices**: [`SortedIndex`] keeps the sorted order for prefix-accelerated
+//!   point lookups and prefix range scans.
+//! - **Incremental Sorting**: [`SortedBatches`] sorts each incoming batch once and merges
+//!   sorted levels, LSM-style, instead of re-sorting everything.
+//! - **Buffer Reuse**: [`Sorter`] retains its pointer and scratch buffers, so repeated sorts of
+//!   many small batches run without allocating once warmed up.
+//! - **Streaming Input**: [`StreamSorter`] copies pushed keys into one contiguous buffer
+//!   instead of allocating per key.
+//! - **Pluggable Collation**: The [`Collator`] trait sorts by non-byte orderings while still
+//!   caching the first 8 collation-key bytes in the sort pointer.
+//! - **Natural Order**: [`orasort_natural`] orders digit runs numerically (`file9` < `file10`).
+//! - **Sortedness Checks and Merges**: [`check_sorted`] finds the first out-of-order key from
+//!   the cached prefixes, and [`merge::merge_files`] streams the merge of sorted line files
+//!   with bounded memory.
+//! - **CSV Columns**: [`csv::CsvIndex`] sorts RFC 4180 records by a column, handling quoted
+//!   fields with embedded delimiters and line breaks, and keeps the header row in place.
+//! - **JSON Lines**: [`jsonl::JsonlIndex`] sorts NDJSON lines by dotted field paths with
+//!   type-aware, order-preserving keys, scanning each line only as far as needed.
+//! - **Sorted String Tables**: [`sst::SstWriter`] writes sorted keys and values as
+//!   front-coded, checksummed blocks, taking the shared prefixes from [`orasort_lcp`]
+//!   instead of recomputing them; [`sst::SstReader`] seeks and iterates them.
+//! - **Key Selection**: [`KeySpec`] sorts lines by fields and character ranges with the
+//!   `sort -t`/`-k` syntax, including per-key reverse, blank-skipping and case folding.
 //!
 //! ## Usage
 //!
@@ -75,16 +118,73 @@
 //!
 //! This library is particularly effective for datasets where cache misses are the primary bottleneck,
 //! such as sorting large arrays of data.
+//!
+//! ## `no_std` Support
+//!
+//! The `std` feature is enabled by default. Without it, the crate only depends on `core` and
+//! `alloc`. [`orasort_in`] sorts into caller-provided scratch space and never allocates.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 pub mod algo;
+pub mod arena;
+pub mod batches;
+pub mod btree;
+mod codec;
+pub mod collate;
 pub mod core;
-pub use algo::{orasort, orasort_from_indices, orasort_mut, orasort_slice};
-pub use core::KeyAccessor;
+pub mod csv;
+pub mod error;
+pub mod fallible;
+pub mod framed;
+pub mod index;
+pub mod jsonl;
+pub mod keyspec;
+pub mod lcp;
+pub mod lines;
+#[cfg(feature = "std")]
+pub mod merge;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+pub mod natural;
+pub mod permutation;
+pub mod persist;
+pub mod rank;
+pub mod sorter;
+pub mod sst;
+pub mod stream;
+pub use algo::{
+    check_sorted, orasort, orasort_from_indices, orasort_from_indices_checked, orasort_in,
+    orasort_mut, orasort_slice, orasort_slice_checked, required_scratch,
+};
+pub use arena::KeyArena;
+pub use batches::SortedBatches;
+pub use collate::{Collator, orasort_collated, orasort_collated_mut};
 pub use core::SPLICE_PREFIX_SIZE;
+pub use core::{Concat, ConcatMany, KeyAccessor, Subset, TryKeyAccessor};
+pub use error::{
+    CsvError, FrameError, JsonlError, KeySpecError, OffsetsError, OrasortError, PersistError,
+    SstError,
+};
+pub use fallible::{try_orasort, try_orasort_from_indices, try_orasort_mut, try_orasort_slice};
+pub use index::SortedIndex;
+pub use keyspec::KeySpec;
+pub use lcp::orasort_lcp;
+pub use natural::{orasort_natural, orasort_natural_mut};
+pub use permutation::{Permutation, Permute};
+pub use rank::{RankPolicy, orasort_percent_ranks, orasort_ranks};
+pub use sorter::Sorter;
+pub use stream::StreamSorter;
 
 pub mod prelude {
     //! Prelude for Orasort.
 
     pub use crate::algo::{orasort, orasort_from_indices, orasort_mut};
+    pub use crate::collate::{Collator, orasort_collated, orasort_collated_mut};
     pub use crate::core::{KeyAccessor, SPLICE_PREFIX_SIZE};
+    pub use crate::natural::{orasort_natural, orasort_natural_mut};
+    pub use crate::permutation::Permutation;
+    pub use crate::sorter::Sorter;
 }
@@ -0,0 +1,324 @@
+//! Sorting the lines of a text buffer.
+//!
+//! [`Lines`] finds every line break of a buffer in one word-at-a-time scan, remembers
+//! where each line starts (in 4 bytes per line for buffers under 4 GiB), and serves the
+//! lines as a [`KeyAccessor`] straight from the buffer. Sorted lines are written back
+//! through one contiguous output buffer.
+
+use crate::algo::orasort;
+use crate::core::{KeyAccessor, buffer_prefix};
+use crate::permutation::Permutation;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Size of the buffer [`Lines::write_sorted`] assembles output in.
+#[cfg(feature = "std")]
+const WRITE_CHUNK: usize = 1 << 16;
+
+/// What ends a line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Delimiter {
+    /// `\n`.
+    #[default]
+    Newline,
+    /// `\r\n`. A `\n` without a `\r` before it is part of the line.
+    CrLf,
+    /// `\0`, as written by `find -print0` and `sort -z`.
+    Nul,
+}
+
+impl Delimiter {
+    /// Returns the bytes of the delimiter.
+    pub fn as_bytes(self) -> &'static [u8] {
+        match self {
+            Delimiter::Newline => b"\n",
+            Delimiter::CrLf => b"\r\n",
+            Delimiter::Nul => b"\0",
+        }
+    }
+}
+
+/// How a delimiter at the end of the buffer is read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TrailingDelimiter {
+    /// Delimiters end lines, and one after the last line is optional: `a\nb\n` and `a\nb`
+    /// both hold two lines. Sorted output ends with a delimiter if the input did.
+    #[default]
+    Optional,
+    /// Delimiters separate lines: `a\nb\n` holds three lines, the last one empty. Sorted
+    /// output puts delimiters between lines only.
+    Separator,
+}
+
+/// Line boundaries of a text buffer.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::lines::{Delimiter, Lines, TrailingDelimiter};
+///
+/// let lines = Lines::new(b"pear\napple\nfig\n");
+/// assert_eq!(lines.to_sorted_vec(&lines.sort()), b"apple\nfig\npear\n");
+///
+/// let lines = Lines::with_options(b"b\r\na\r\n", Delimiter::CrLf, TrailingDelimiter::Separator);
+/// assert_eq!(lines.len(), 3);
+/// assert_eq!(lines.to_sorted_vec(&lines.sort()), b"\r\na\r\nb");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Lines<'a> {
+    data: &'a [u8],
+    delimiter: Delimiter,
+    /// Start of every line; line `i` ends a delimiter before line `i + 1` starts.
+    starts: Starts,
+    /// End of the last line.
+    last_end: usize,
+    /// Whether the buffer ends with a delimiter that does not start another line.
+    terminated: bool,
+}
+
+impl<'a> Lines<'a> {
+    /// Splits `data` into `\n`-terminated lines. A line break after the last line is
+    /// optional.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self::with_options(data, Delimiter::Newline, TrailingDelimiter::Optional)
+    }
+
+    /// Splits `data` into lines ending at `delimiter`, reading a delimiter at the end of
+    /// `data` as `trailing` says.
+    pub fn with_options(data: &'a [u8], delimiter: Delimiter, trailing: TrailingDelimiter) -> Self {
+        let (starts, terminated) = if u32::try_from(data.len()).is_ok() {
+            let (starts, terminated) = split(data, delimiter, trailing, |pos| pos as u32);
+            (Starts::Narrow(starts), terminated)
+        } else {
+            let (starts, terminated) = split(data, delimiter, trailing, |pos| pos);
+            (Starts::Wide(starts), terminated)
+        };
+        let last_end = if terminated {
+            data.len() - delimiter.as_bytes().len()
+        } else {
+            data.len()
+        };
+
+        Self {
+            data,
+            delimiter,
+            starts,
+            last_end,
+            terminated,
+        }
+    }
+
+    /// Returns the buffer the lines were split from.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns the number of lines.
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Returns `true` if there are no lines.
+    pub fn is_empty(&self) -> bool {
+        self.starts.len() == 0
+    }
+
+    /// Returns line `index` without its delimiter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= self.len()`.
+    pub fn line(&self, index: usize) -> &'a [u8] {
+        &self.data[self.range(index)]
+    }
+
+    /// Returns an iterator over the lines, without delimiters.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &'a [u8]> + '_ {
+        (0..self.len()).map(|index| self.line(index))
+    }
+
+    /// Sorts the lines bytewise.
+    pub fn sort(&self) -> Permutation {
+        orasort(self)
+    }
+
+    /// Returns the lines in the order given by `permutation`, each followed by the
+    /// delimiter as the [`TrailingDelimiter`] policy says.
+    ///
+    /// The result is exactly as long as the input buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `permutation.len() != self.len()`.
+    pub fn to_sorted_vec(&self, permutation: &Permutation) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.data.len());
+        self.write_pieces(permutation, |piece| {
+            out.extend_from_slice(piece);
+            Ok::<(), core::convert::Infallible>(())
+        })
+        .unwrap_or_else(|never| match never {});
+        out
+    }
+
+    /// Writes the lines in the order given by `permutation`.
+    ///
+    /// Lines are gathered into one contiguous buffer and written in large chunks, so `out`
+    /// needs no buffering of its own. See [`to_sorted_vec`](Self::to_sorted_vec).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `permutation.len() != self.len()`.
+    #[cfg(feature = "std")]
+    pub fn write_sorted<W: std::io::Write>(
+        &self,
+        permutation: &Permutation,
+        mut out: W,
+    ) -> std::io::Result<()> {
+        let mut chunk = Vec::with_capacity(WRITE_CHUNK.min(self.data.len()));
+        self.write_pieces(permutation, |piece| {
+            if chunk.len() + piece.len() > WRITE_CHUNK {
+                out.write_all(&chunk)?;
+                chunk.clear();
+            }
+            if piece.len() > WRITE_CHUNK {
+                out.write_all(piece)
+            } else {
+                chunk.extend_from_slice(piece);
+                Ok(())
+            }
+        })?;
+        out.write_all(&chunk)
+    }
+
+    fn write_pieces<E>(
+        &self,
+        permutation: &Permutation,
+        mut write: impl FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        assert_eq!(
+            permutation.len(),
+            self.len(),
+            "lines and permutation must have the same length"
+        );
+
+        let delimiter = self.delimiter.as_bytes();
+        let last = permutation.len().saturating_sub(1);
+        for (position, &index) in permutation.iter().enumerate() {
+            write(self.line(index))?;
+            if self.terminated || position != last {
+                write(delimiter)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn range(&self, index: usize) -> Range<usize> {
+        let start = self.starts.get(index);
+        let end = if index + 1 == self.starts.len() {
+            self.last_end
+        } else {
+            self.starts.get(index + 1) - self.delimiter.as_bytes().len()
+        };
+        start..end
+    }
+}
+
+impl KeyAccessor for Lines<'_> {
+    #[inline(always)]
+    fn get_key(&self, index: usize) -> &[u8] {
+        &self.data[self.range(index)]
+    }
+
+    fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    #[inline(always)]
+    fn get_u64_prefix(&self, index: usize, offset: usize) -> u64 {
+        buffer_prefix(self.data, self.range(index), offset)
+    }
+}
+
+/// Line starts, 32-bit if the buffer allows.
+#[derive(Clone, Debug)]
+enum Starts {
+    Narrow(Vec<u32>),
+    Wide(Vec<usize>),
+}
+
+impl Starts {
+    #[inline(always)]
+    fn get(&self, index: usize) -> usize {
+        match self {
+            Starts::Narrow(starts) => starts[index] as usize,
+            Starts::Wide(starts) => starts[index],
+        }
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        match self {
+            Starts::Narrow(starts) => starts.len(),
+            Starts::Wide(starts) => starts.len(),
+        }
+    }
+}
+
+/// Returns the start of every line of `data`, converted by `offset`, and whether `data`
+/// ends with a delimiter that does not start another line.
+fn split<O>(
+    data: &[u8],
+    delimiter: Delimiter,
+    trailing: TrailingDelimiter,
+    offset: impl Fn(usize) -> O,
+) -> (Vec<O>, bool) {
+    let mut starts = Vec::new();
+    starts.push(offset(0));
+
+    match delimiter {
+        Delimiter::Newline => find_all(data, b'\n', |pos| starts.push(offset(pos + 1))),
+        Delimiter::Nul => find_all(data, b'\0', |pos| starts.push(offset(pos + 1))),
+        Delimiter::CrLf => find_all(data, b'\n', |pos| {
+            if pos > 0 && data[pos - 1] == b'\r' {
+                starts.push(offset(pos + 1));
+            }
+        }),
+    }
+
+    // The split always yields a line after the last delimiter, empty if the buffer ends
+    // with one.
+    let ends_with_delimiter = data.ends_with(delimiter.as_bytes());
+    if trailing == TrailingDelimiter::Optional && (data.is_empty() || ends_with_delimiter) {
+        starts.pop();
+        return (starts, ends_with_delimiter);
+    }
+    (starts, false)
+}
+
+/// Calls `found` with the position of every `needle` in `haystack`, in order.
+///
+/// Compares eight bytes per step, like the portable fallback of `memchr`.
+fn find_all(haystack: &[u8], needle: u8, mut found: impl FnMut(usize)) {
+    const LOW: u64 = 0x7f7f_7f7f_7f7f_7f7f;
+    let splat = u64::from_le_bytes([needle; 8]);
+
+    let mut chunks = haystack.chunks_exact(8);
+    let mut base = 0;
+    for chunk in &mut chunks {
+        let word = u64::from_le_bytes(chunk.try_into().unwrap()) ^ splat;
+        // The high bit of exactly the zero bytes of `word`, without carries between bytes.
+        let mut matches = !(((word & LOW) + LOW) | word | LOW);
+        while matches != 0 {
+            found(base + matches.trailing_zeros() as usize / 8);
+            matches &= matches - 1;
+        }
+        base += 8;
+    }
+
+    for (pos, &byte) in chunks.remainder().iter().enumerate() {
+        if byte == needle {
+            found(base + pos);
+        }
+    }
+}
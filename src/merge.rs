@@ -0,0 +1,259 @@
+//! Streaming merge of sorted line files.
+//!
+//! Shards that are already sorted do not need to be sorted again: [`merge_files`] reads
+//! them line by line and writes their merge, holding only one buffered line per input in
+//! memory. [`LineMerger`] configures the line delimiter, duplicate removal and the order
+//! the inputs are sorted in.
+
+use core::cmp::Ordering;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::vec::Vec;
+
+/// Size of the read buffer of every input.
+const INPUT_BUFFER: usize = 64 * 1024;
+
+/// Size of the output buffer.
+const OUTPUT_BUFFER: usize = 256 * 1024;
+
+/// Merges line files that are each sorted bytewise into `output`.
+///
+/// Lines are delimited by `\n`; a missing delimiter after the last line of an input is
+/// tolerated. Equal lines are written in input order. Returns the number of lines written.
+///
+/// # Errors
+///
+/// Returns the first I/O error; errors opening or reading an input name its path.
+///
+/// # Examples
+///
+/// ```no_run
+/// use orasort::merge::merge_files;
+/// use std::fs::File;
+///
+/// let output = File::create("merged.txt")?;
+/// merge_files(&["shard-0.txt", "shard-1.txt"], output)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn merge_files<P: AsRef<Path>, W: Write>(inputs: &[P], output: W) -> io::Result<u64> {
+    LineMerger::new().merge_files(inputs, output)
+}
+
+/// Merges sorted line streams with bounded memory.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::merge::LineMerger;
+///
+/// let inputs = vec![&b"c\nb\na\n"[..], &b"d\nb\n"[..]];
+/// let mut output = Vec::new();
+///
+/// // Inputs sorted in descending order, duplicates removed.
+/// let written = LineMerger::new()
+///     .unique(true)
+///     .compare_by(|a: &[u8], b: &[u8]| b.cmp(a))
+///     .merge(inputs, &mut output)?;
+///
+/// assert_eq!(output, b"d\nc\nb\na\n");
+/// assert_eq!(written, 4);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct LineMerger<F = fn(&[u8], &[u8]) -> Ordering> {
+    delimiter: u8,
+    unique: bool,
+    compare: F,
+}
+
+impl Default for LineMerger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineMerger {
+    /// Creates a merger for `\n`-delimited lines in bytewise order.
+    pub fn new() -> Self {
+        Self {
+            delimiter: b'\n',
+            unique: false,
+            compare: <[u8]>::cmp,
+        }
+    }
+}
+
+impl<F: FnMut(&[u8], &[u8]) -> Ordering> LineMerger<F> {
+    /// Sets the byte that terminates lines (`\n` by default).
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Writes only the first of every run of lines that compare equal.
+    pub fn unique(mut self, unique: bool) -> Self {
+        self.unique = unique;
+        self
+    }
+
+    /// Sets the order the inputs are sorted in.
+    pub fn compare_by<G>(self, compare: G) -> LineMerger<G>
+    where
+        G: FnMut(&[u8], &[u8]) -> Ordering,
+    {
+        LineMerger {
+            delimiter: self.delimiter,
+            unique: self.unique,
+            compare,
+        }
+    }
+
+    /// Merges the sorted files at `inputs` into `output`. Returns the number of lines written.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first I/O error; errors opening or reading an input name its path.
+    pub fn merge_files<P: AsRef<Path>, W: Write>(
+        &mut self,
+        inputs: &[P],
+        output: W,
+    ) -> io::Result<u64> {
+        let readers = inputs
+            .iter()
+            .map(|path| {
+                let path = path.as_ref();
+                let file = File::open(path).map_err(|error| annotate(error, path))?;
+                Ok(Named {
+                    reader: BufReader::with_capacity(INPUT_BUFFER, file),
+                    path,
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        self.merge(readers, output)
+    }
+
+    /// Merges the sorted streams `inputs` into `output`. Returns the number of lines written.
+    ///
+    /// Lines that compare equal are written in the order of their inputs.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first I/O error.
+    pub fn merge<R: BufRead, W: Write>(&mut self, inputs: Vec<R>, output: W) -> io::Result<u64> {
+        let mut output = BufWriter::with_capacity(OUTPUT_BUFFER, output);
+        let mut cursors = Vec::with_capacity(inputs.len());
+        for reader in inputs {
+            let mut cursor = Cursor {
+                reader,
+                line: Vec::new(),
+            };
+            if cursor.advance(self.delimiter)? {
+                cursors.push(cursor);
+            }
+        }
+
+        // Binary min-heap of cursor indices, ordered by their lines, then by input.
+        let mut heap: Vec<usize> = (0..cursors.len()).collect();
+        for root in (0..heap.len() / 2).rev() {
+            self.sift_down(&mut heap, &cursors, root);
+        }
+
+        let mut previous = Vec::new();
+        let mut written = 0;
+        while let Some(&top) = heap.first() {
+            let line = &cursors[top].line;
+            let duplicate =
+                self.unique && written > 0 && (self.compare)(&previous, line) == Ordering::Equal;
+            if !duplicate {
+                output.write_all(line)?;
+                output.write_all(&[self.delimiter])?;
+                written += 1;
+                if self.unique {
+                    previous.clone_from(line);
+                }
+            }
+
+            if !cursors[top].advance(self.delimiter)? {
+                heap.swap_remove(0);
+            }
+            self.sift_down(&mut heap, &cursors, 0);
+        }
+
+        output.flush()?;
+        Ok(written)
+    }
+
+    fn sift_down<R>(&mut self, heap: &mut [usize], cursors: &[Cursor<R>], mut node: usize) {
+        let mut less = |a: usize, b: usize| {
+            (self.compare)(&cursors[a].line, &cursors[b].line)
+                .then(a.cmp(&b))
+                .is_lt()
+        };
+
+        loop {
+            let mut smallest = node;
+            for child in [2 * node + 1, 2 * node + 2] {
+                if child < heap.len() && less(heap[child], heap[smallest]) {
+                    smallest = child;
+                }
+            }
+            if smallest == node {
+                return;
+            }
+            heap.swap(node, smallest);
+            node = smallest;
+        }
+    }
+}
+
+/// An input and its current line.
+struct Cursor<R> {
+    reader: R,
+    line: Vec<u8>,
+}
+
+impl<R: BufRead> Cursor<R> {
+    /// Reads the next line, without its delimiter. Returns `false` at the end of the input.
+    fn advance(&mut self, delimiter: u8) -> io::Result<bool> {
+        self.line.clear();
+        if self.reader.read_until(delimiter, &mut self.line)? == 0 {
+            return Ok(false);
+        }
+        if self.line.last() == Some(&delimiter) {
+            self.line.pop();
+        }
+        Ok(true)
+    }
+}
+
+/// A file reader whose errors name the file.
+struct Named<'a, R> {
+    reader: R,
+    path: &'a Path,
+}
+
+impl<R: io::Read> io::Read for Named<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader
+            .read(buf)
+            .map_err(|error| annotate(error, self.path))
+    }
+}
+
+impl<R: BufRead> BufRead for Named<'_, R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let path = self.path;
+        self.reader
+            .fill_buf()
+            .map_err(|error| annotate(error, path))
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.reader.consume(amount);
+    }
+}
+
+fn annotate(error: io::Error, path: &Path) -> io::Error {
+    io::Error::new(error.kind(), format!("{}: {}", path.display(), error))
+}
@@ -0,0 +1,151 @@
+//! Keys read in place from memory-mapped files.
+//!
+//! Datasets are often stored as one blob of concatenated keys plus an offsets file, as in
+//! Arrow's variable-size binary layout. Reading them into a `Vec<Vec<u8>>` only to sort
+//! them doubles the memory needed. [`MmapKeys`] maps both files read-only, validates the
+//! offsets once, and serves keys and prefixes straight from the mappings.
+
+use crate::arena::ArenaOffset;
+use crate::core::{KeyAccessor, buffer_prefix};
+use crate::error::OffsetsError;
+use core::marker::PhantomData;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// Keys of a memory-mapped data file, delimited by a memory-mapped offsets file.
+///
+/// The offsets file holds `n + 1` little-endian offsets of type `O` (`u32` or `u64`) into
+/// the data file; key `i` is `data[offsets[i]..offsets[i + 1]]`. Offsets must not decrease
+/// and the last one must not exceed the data file. The first offset need not be zero.
+///
+/// # Examples
+///
+/// ```no_run
+/// use orasort::mmap::MmapKeys;
+/// use orasort::orasort;
+///
+/// // 64-bit offsets, e.g. Arrow's `LargeBinary`.
+/// let keys: MmapKeys<u64> = MmapKeys::open("names.offsets", "names.data")?;
+/// let order = orasort(&keys);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct MmapKeys<O: ArenaOffset = u32> {
+    offsets: Mmap,
+    data: Mmap,
+    len: usize,
+    width: PhantomData<O>,
+}
+
+impl<O: ArenaOffset> MmapKeys<O> {
+    /// Maps the files at `offsets` and `data` and validates the offsets.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if a file cannot be opened or mapped, and an
+    /// [`InvalidData`](io::ErrorKind::InvalidData) error wrapping an [`OffsetsError`] if the
+    /// offsets are invalid. Errors opening or mapping a file name its path.
+    pub fn open<P: AsRef<Path>, Q: AsRef<Path>>(offsets: P, data: Q) -> io::Result<Self> {
+        let (offsets, data) = (offsets.as_ref(), data.as_ref());
+        let map = |path: &Path| {
+            File::open(path)
+                .and_then(|file| map(&file))
+                .map_err(|error| annotate(error, path))
+        };
+        Self::new(map(offsets)?, map(data)?)
+    }
+
+    /// Maps open `offsets` and `data` files and validates the offsets.
+    ///
+    /// # Errors
+    ///
+    /// See [`open`](Self::open); no error names a file.
+    pub fn from_files(offsets: &File, data: &File) -> io::Result<Self> {
+        Self::new(map(offsets)?, map(data)?)
+    }
+
+    fn new(offsets: Mmap, data: Mmap) -> io::Result<Self> {
+        let len = validate::<O>(&offsets, data.len())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        Ok(Self {
+            offsets,
+            data,
+            len,
+            width: PhantomData,
+        })
+    }
+
+    /// Returns the mapped data file.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the start and end of key `index` in the data file.
+    #[inline(always)]
+    fn bounds(&self, index: usize) -> (usize, usize) {
+        // Validated to fit `usize` when the files were mapped.
+        let start = O::read_le(&self.offsets, index) as usize;
+        let end = O::read_le(&self.offsets, index + 1) as usize;
+        (start, end)
+    }
+}
+
+impl<O: ArenaOffset> KeyAccessor for MmapKeys<O> {
+    #[inline(always)]
+    fn get_key(&self, index: usize) -> &[u8] {
+        let (start, end) = self.bounds(index);
+        &self.data[start..end]
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline(always)]
+    fn get_u64_prefix(&self, index: usize, offset: usize) -> u64 {
+        let (start, end) = self.bounds(index);
+        buffer_prefix(&self.data, start..end, offset)
+    }
+}
+
+/// Checks an offsets table against a data file of `data_len` bytes and returns the number
+/// of keys.
+fn validate<O: ArenaOffset>(offsets: &[u8], data_len: usize) -> Result<usize, OffsetsError> {
+    if offsets.is_empty() || !offsets.len().is_multiple_of(O::WIDTH) {
+        return Err(OffsetsError::InvalidLength {
+            len: offsets.len(),
+            width: O::WIDTH,
+        });
+    }
+    let count = offsets.len() / O::WIDTH;
+
+    let mut previous = O::read_le(offsets, 0);
+    for index in 1..count {
+        let offset = O::read_le(offsets, index);
+        if offset < previous {
+            return Err(OffsetsError::Decreasing { index });
+        }
+        previous = offset;
+    }
+    if previous > data_len as u64 {
+        return Err(OffsetsError::PastEnd {
+            offset: previous,
+            data_len,
+        });
+    }
+
+    Ok(count - 1)
+}
+
+fn map(file: &File) -> io::Result<Mmap> {
+    // SAFETY: The mapping is only read. As with any memory-mapped file, modifying or
+    // truncating the file while it is mapped changes the keys under us or faults.
+    unsafe { Mmap::map(file) }
+}
+
+/// Prefixes an error with the path it concerns.
+fn annotate(error: io::Error, path: &Path) -> io::Error {
+    io::Error::new(error.kind(), format!("{}: {}", path.display(), error))
+}
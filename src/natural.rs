@@ -0,0 +1,390 @@
+//! Natural (version-aware) sort order.
+//!
+//! Natural order compares runs of ASCII digits by their numeric value, so `file9` sorts
+//! before `file10` and `node-2` before `node-10`. It is implemented as a [`Collator`]
+//! that rewrites every digit run into an order-preserving encoding, which means the sort
+//! still caches (and mostly compares) the first 8 bytes of the transformed key.
+//!
+//! ## Digit run encoding
+//!
+//! A run of digits with `L` significant digits is emitted as:
+//!
+//! ```text
+//! '0' | len(L) | L (big-endian) | significant digits
+//! ```
+//!
+//! where `len(x)` is the number of bytes needed to represent `x`. Longer numbers compare
+//! greater and numbers of equal length compare digit by digit. Digit runs may be
+//! arbitrarily long.
+//!
+//! Leading zeros only break ties between keys that are otherwise equal, so `01a` sorts
+//! before `1b`, and `1a` before `01a` before `001a`. If any digit run has leading zeros,
+//! the collation key ends with their counts, one per digit run:
+//!
+//! ```text
+//! 0x00 0x00 | len(z) | z (big-endian) | len(z) | z (big-endian) | ...
+//! ```
+//!
+//! To keep that separator below every byte that can follow the rest of a key, NUL bytes
+//! of the key are emitted as `0x00 0xff`.
+//!
+//! ## Semver mode
+//!
+//! [`NaturalCollator::semver`] additionally follows the [Semantic Versioning](https://semver.org)
+//! precedence rules: a pre-release (`1.0.0-alpha`) orders before its release (`1.0.0`),
+//! pre-release identifiers compare numerically when they are numeric and numeric
+//! identifiers order before alphanumeric ones, and build metadata (`+build.5`) is ignored.
+//!
+//! The pre-release starts with `0x00 0x01`, which orders it before the `0x01` that ends a
+//! release. Every identifier starts with a type marker, `0x01` if it consists of digits
+//! only and `0x02` otherwise, and the dot after it becomes `0x00`, below every byte of an
+//! identifier. Numeric identifiers are encoded like digit runs; alphanumeric identifiers,
+//! digits included, are copied as they are and so compare bytewise.
+
+use crate::collate::{Collator, Emitter, orasort_collated};
+use crate::core::KeyAccessor;
+use crate::permutation::Permutation;
+
+/// Marker byte that starts an encoded digit run (keeps runs ordered like digits).
+const DIGIT_RUN_MARKER: u8 = b'0';
+
+/// Emitted in place of a NUL byte of the key.
+const ESCAPED_NUL: [u8; 2] = [0x00, 0xff];
+
+/// Separates the rest of the key from the leading-zero counts of its digit runs.
+const LEADING_ZEROS: [u8; 2] = [0x00, 0x00];
+
+/// Emitted in place of the pre-release separator in semver mode.
+const SEMVER_PRE_RELEASE: [u8; 2] = [0x00, 0x01];
+
+/// Emitted at the end of a version without pre-release in semver mode.
+const SEMVER_RELEASE: u8 = 0x01;
+
+/// Emitted in place of the dot between pre-release identifiers in semver mode.
+const IDENTIFIER_END: u8 = 0x00;
+
+/// Starts a numeric pre-release identifier in semver mode.
+const NUMERIC_IDENTIFIER: u8 = 0x01;
+
+/// Starts an alphanumeric pre-release identifier in semver mode.
+const ALPHANUMERIC_IDENTIFIER: u8 = 0x02;
+
+/// A [`Collator`] implementing natural (numeric-aware) order.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::collate::orasort_collated_mut;
+/// use orasort::natural::NaturalCollator;
+///
+/// let mut tags = vec!["1.0.0", "1.0.0-rc.1", "1.0.0-alpha", "0.9.12", "1.0.0-rc.10"];
+/// orasort_collated_mut(&mut tags, &NaturalCollator::semver());
+///
+/// assert_eq!(
+///     tags,
+///     vec!["0.9.12", "1.0.0-alpha", "1.0.0-rc.1", "1.0.0-rc.10", "1.0.0"]
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NaturalCollator {
+    semver: bool,
+}
+
+impl NaturalCollator {
+    /// Creates a collator for plain natural order.
+    pub const fn new() -> Self {
+        Self { semver: false }
+    }
+
+    /// Creates a collator for natural order with semantic-versioning precedence.
+    pub const fn semver() -> Self {
+        Self { semver: true }
+    }
+}
+
+impl Collator for NaturalCollator {
+    type State = NaturalState;
+
+    fn collation_bytes(&self, key: &[u8], state: &mut NaturalState, out: &mut [u8]) -> usize {
+        if out.is_empty() {
+            return 0;
+        }
+        let mut emitter = Emitter::new(state.written, out);
+        state.written = 0;
+        self.generate(key, state, &mut emitter);
+        emitter.written()
+    }
+
+    /// Skips without writing, which passes over plain runs and digits without copying them.
+    fn skip_collation_bytes(&self, key: &[u8], state: &mut NaturalState, count: usize) -> usize {
+        let mut emitter = Emitter::new(state.written + count, &mut []);
+        state.written = 0;
+        self.generate(key, state, &mut emitter);
+        count - emitter.room()
+    }
+}
+
+impl NaturalCollator {
+    /// Emits the collation key of `key` from where `state` stands until `emitter` is full.
+    fn generate(&self, key: &[u8], state: &mut NaturalState, emitter: &mut Emitter<'_>) {
+        while !emitter.is_full() {
+            match state.phase {
+                Phase::Key => self.emit_key_piece(key, state, emitter),
+                Phase::Release => {
+                    if emit_piece(emitter, state, &[SEMVER_RELEASE]) {
+                        state.phase = zeros_or_done(state);
+                    }
+                }
+                Phase::Separator => {
+                    if emit_piece(emitter, state, &LEADING_ZEROS) {
+                        state.phase = Phase::Zeros;
+                        state.pos = 0;
+                    }
+                }
+                Phase::Zeros => {
+                    let Some(start) = key[state.pos..state.end]
+                        .iter()
+                        .position(u8::is_ascii_digit)
+                        .map(|n| state.pos + n)
+                    else {
+                        state.phase = Phase::Done;
+                        continue;
+                    };
+                    let zeros = key[start..].iter().take_while(|&&b| b == b'0').count();
+                    let end = digit_run_end(key, start + zeros);
+
+                    let mut buf = [0u8; LENGTH_BYTES];
+                    let len = encode_length(zeros, &mut buf);
+                    state.pos = start;
+                    if emit_piece(emitter, state, &buf[..len]) {
+                        state.pos = end;
+                    }
+                }
+                Phase::Done => break,
+            }
+        }
+    }
+
+    /// Emits the piece of the key at `state.pos`, or ends the key.
+    fn emit_key_piece(&self, key: &[u8], state: &mut NaturalState, emitter: &mut Emitter<'_>) {
+        let pos = state.pos;
+
+        // Significant digits of a run whose header is out.
+        if state.digits > 0 {
+            let n = emitter.extend(&key[pos..pos + state.digits]);
+            state.pos += n;
+            state.digits -= n;
+            return;
+        }
+
+        let Some(&byte) = key.get(pos) else {
+            self.end_key(state);
+            return;
+        };
+
+        if byte.is_ascii_digit() && !state.alphanumeric {
+            let zeros = key[pos..].iter().take_while(|&&b| b == b'0').count();
+            let end = digit_run_end(key, pos + zeros);
+
+            let mut header = [0u8; 1 + LENGTH_BYTES];
+            header[0] = DIGIT_RUN_MARKER;
+            let len = 1 + encode_length(end - pos - zeros, &mut header[1..]);
+            if emit_piece(emitter, state, &header[..len]) {
+                state.leading_zeros |= zeros > 0;
+                state.pos = pos + zeros;
+                state.digits = end - state.pos;
+            }
+            return;
+        }
+
+        if byte == 0 {
+            if emit_piece(emitter, state, &ESCAPED_NUL) {
+                state.pos += 1;
+            }
+            return;
+        }
+
+        if self.semver {
+            match byte {
+                // Build metadata does not take part in precedence.
+                b'+' => {
+                    self.end_key(state);
+                    return;
+                }
+                b'-' if !state.pre_release => {
+                    let (marker, alphanumeric) = identifier_marker(key, pos + 1);
+                    let [first, second] = SEMVER_PRE_RELEASE;
+                    if emit_piece(emitter, state, &[first, second, marker]) {
+                        state.pre_release = true;
+                        state.alphanumeric = alphanumeric;
+                        state.pos += 1;
+                    }
+                    return;
+                }
+                b'.' if state.pre_release => {
+                    let (marker, alphanumeric) = identifier_marker(key, pos + 1);
+                    if emit_piece(emitter, state, &[IDENTIFIER_END, marker]) {
+                        state.alphanumeric = alphanumeric;
+                        state.pos += 1;
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        // Copy the run of plain bytes at once, as far as it fits. The current byte is
+        // known to be plain.
+        let limit = key.len().min(pos + emitter.room());
+        let end = key[pos + 1..limit]
+            .iter()
+            .position(|&b| self.starts_piece(state, b))
+            .map_or(limit, |n| pos + 1 + n);
+        state.pos += emitter.extend(&key[pos..end]);
+    }
+
+    /// Returns `true` if `byte` is not copied as it is.
+    fn starts_piece(&self, state: &NaturalState, byte: u8) -> bool {
+        (byte.is_ascii_digit() && !state.alphanumeric)
+            || byte == 0
+            || (self.semver
+                && (byte == b'+'
+                    || (byte == b'-' && !state.pre_release)
+                    || (byte == b'.' && state.pre_release)))
+    }
+
+    /// Moves on from the key to what follows it in the collation key.
+    fn end_key(&self, state: &mut NaturalState) {
+        state.end = state.pos;
+        state.phase = if self.semver && !state.pre_release {
+            Phase::Release
+        } else {
+            zeros_or_done(state)
+        };
+    }
+}
+
+/// Where generation of a natural collation key stopped (see [`Collator::State`]).
+#[derive(Clone, Debug, Default)]
+pub struct NaturalState {
+    phase: Phase,
+    /// Position in the key.
+    pos: usize,
+    /// Bytes of the piece at `pos` that an earlier call already wrote.
+    written: usize,
+    /// Significant digits at `pos` whose digit run header has been written.
+    digits: usize,
+    /// End of the part of the key that takes part in the order.
+    end: usize,
+    pre_release: bool,
+    /// Whether the pre-release identifier at `pos` is alphanumeric, so its digits are
+    /// copied as they are.
+    alphanumeric: bool,
+    /// Whether some digit run has leading zeros.
+    leading_zeros: bool,
+}
+
+/// The part of a natural collation key being generated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Phase {
+    /// The encoded key.
+    #[default]
+    Key,
+    /// The semver marker of a version without pre-release.
+    Release,
+    /// The separator before the leading-zero counts.
+    Separator,
+    /// The leading-zero count of the next digit run from `pos` on.
+    Zeros,
+    Done,
+}
+
+/// Performs an index-based sort of `provider` in natural order.
+///
+/// Runs of ASCII digits compare by numeric value; everything else compares bytewise.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::orasort_natural;
+///
+/// let data = vec!["file10", "file9", "file1"];
+/// let indices = orasort_natural(&data);
+///
+/// assert_eq!(indices, vec![2, 1, 0]); // file1, file9, file10
+/// ```
+pub fn orasort_natural<T: KeyAccessor + ?Sized>(provider: &T) -> Permutation {
+    orasort_collated(provider, &NaturalCollator::new())
+}
+
+/// Sorts a mutable slice in-place in natural order.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::orasort_natural_mut;
+///
+/// let mut hosts = vec!["node-10", "node-2", "node-1"];
+/// orasort_natural_mut(&mut hosts);
+///
+/// assert_eq!(hosts, vec!["node-1", "node-2", "node-10"]);
+/// ```
+pub fn orasort_natural_mut<T: AsRef<[u8]>>(data: &mut [T]) {
+    orasort_natural(data).apply_to(data);
+}
+
+/// Emits `piece`, which starts at the current position. Returns `false` if `out` filled up
+/// first, recording how much of the piece was written.
+fn emit_piece(emitter: &mut Emitter<'_>, state: &mut NaturalState, piece: &[u8]) -> bool {
+    let done = emitter.extend(piece);
+    if done < piece.len() {
+        state.written = done;
+        return false;
+    }
+    true
+}
+
+/// Returns the marker of the pre-release identifier starting at `start`, and whether it is
+/// alphanumeric. Only identifiers of nothing but digits are numeric.
+fn identifier_marker(key: &[u8], start: usize) -> (u8, bool) {
+    let identifier = key[start..]
+        .split(|&b| b == b'.' || b == b'+')
+        .next()
+        .unwrap_or_default();
+    if !identifier.is_empty() && identifier.iter().all(u8::is_ascii_digit) {
+        (NUMERIC_IDENTIFIER, false)
+    } else {
+        (ALPHANUMERIC_IDENTIFIER, true)
+    }
+}
+
+fn zeros_or_done(state: &NaturalState) -> Phase {
+    if state.leading_zeros {
+        Phase::Separator
+    } else {
+        Phase::Done
+    }
+}
+
+/// Returns the end of the digit run that contains `pos`, or `pos` if it is not a digit.
+fn digit_run_end(key: &[u8], pos: usize) -> usize {
+    key[pos..]
+        .iter()
+        .position(|b| !b.is_ascii_digit())
+        .map_or(key.len(), |n| pos + n)
+}
+
+/// Maximum size of an encoded length.
+const LENGTH_BYTES: usize = 9;
+
+/// Writes `value` as a byte count followed by its minimal big-endian bytes into `buf`,
+/// returning the number of bytes written.
+///
+/// Encodings of larger values compare greater.
+fn encode_length(value: usize, buf: &mut [u8]) -> usize {
+    let bytes = (value as u64).to_be_bytes();
+    let width = 8 - (value as u64).leading_zeros() as usize / 8;
+    buf[0] = width as u8;
+    buf[1..=width].copy_from_slice(&bytes[8 - width..]);
+    1 + width
+}
@@ -0,0 +1,344 @@
+//! Permutations produced by the sort entry points.
+//!
+//! [`orasort`](crate::orasort) and its collated, natural and fallible counterparts return a
+//! [`Permutation`]: position `i` of the sorted order holds the item at `permutation[i]` of
+//! the input. Besides reading the indices directly (it dereferences to `[usize]`), a
+//! permutation can reorder data in-place, gather it into a new vector, be inverted into
+//! per-item ranks, and be composed with another permutation.
+//!
+//! Struct-of-arrays data is reordered with [`Permutation::apply_to_many`], which moves a
+//! whole tuple of columns through the same cycle-following pass.
+
+use crate::algo::check_indices;
+use crate::error::OrasortError;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+/// A permutation of `0..len`, as returned by the sort entry points.
+///
+/// `self[i]` is the input position of the item that belongs at position `i` of the output.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::orasort;
+///
+/// let names = vec!["carol", "alice", "bob"];
+/// let mut ages = vec![35, 30, 25];
+///
+/// let permutation = orasort(&names);
+/// permutation.apply_to(&mut ages);
+///
+/// assert_eq!(permutation.gather(&names), vec!["alice", "bob", "carol"]);
+/// assert_eq!(ages, vec![30, 25, 35]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Permutation {
+    indices: Vec<usize>,
+}
+
+impl Permutation {
+    /// Returns the identity permutation of `len` items.
+    pub fn identity(len: usize) -> Self {
+        Self {
+            indices: (0..len).collect(),
+        }
+    }
+
+    /// Wraps indices that are known to form a permutation of `0..indices.len()`.
+    pub(crate) fn from_vec_unchecked(indices: Vec<usize>) -> Self {
+        debug_assert!(check_indices(&indices, indices.len()).is_ok());
+        Self { indices }
+    }
+
+    /// Returns the indices as a slice.
+    pub fn as_slice(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Consumes the permutation, returning its indices.
+    pub fn into_vec(self) -> Vec<usize> {
+        self.indices
+    }
+
+    /// Returns `true` if every item stays at its position.
+    pub fn is_identity(&self) -> bool {
+        self.indices
+            .iter()
+            .enumerate()
+            .all(|(i, &index)| i == index)
+    }
+
+    /// Returns the inverse permutation.
+    ///
+    /// For a sort result, `inverse()[i]` is the output position (rank) of input item `i`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use orasort::orasort;
+    ///
+    /// let data = vec!["c", "a", "b"];
+    /// let ranks = orasort(&data).inverse();
+    ///
+    /// assert_eq!(ranks, vec![2, 0, 1]);
+    /// ```
+    pub fn inverse(&self) -> Self {
+        let mut inverse = vec![0; self.indices.len()];
+        for (position, &index) in self.indices.iter().enumerate() {
+            inverse[index] = position;
+        }
+        Self { indices: inverse }
+    }
+
+    /// Returns the permutation that applies `self` first and `other` second.
+    ///
+    /// `self.compose(other)[i] == self[other[i]]`, so applying the result once is the same
+    /// as calling `self.apply_to(data)` followed by `other.apply_to(data)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the permutations have different lengths.
+    pub fn compose(&self, other: &Permutation) -> Self {
+        assert_eq!(
+            self.len(),
+            other.len(),
+            "permutations must have the same length"
+        );
+        Self {
+            indices: other.indices.iter().map(|&i| self.indices[i]).collect(),
+        }
+    }
+
+    /// Returns the items of `data` in permuted order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data.len() != self.len()`.
+    pub fn gather<T: Clone>(&self, data: &[T]) -> Vec<T> {
+        assert_eq!(
+            data.len(),
+            self.len(),
+            "data and permutation must have the same length"
+        );
+        self.indices.iter().map(|&i| data[i].clone()).collect()
+    }
+
+    /// Reorders `data` in-place, so that afterwards `data[i]` is the item previously at
+    /// `data[self[i]]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data.len() != self.len()`.
+    pub fn apply_to<T>(&self, data: &mut [T]) {
+        self.apply_to_many(data);
+    }
+
+    /// Reorders several columns in-place in a single cycle-following pass.
+    ///
+    /// `columns` is anything implementing [`Permute`]: a slice, a vector, or a tuple of
+    /// mutable references to them (up to 12 columns, possibly of different types).
+    ///
+    /// # Panics
+    ///
+    /// Panics if any column's length differs from `self.len()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use orasort::orasort;
+    ///
+    /// let mut ids = vec![3, 1, 2];
+    /// let mut names = vec!["c", "a", "b"];
+    /// let mut scores = vec![0.3, 0.1, 0.2];
+    ///
+    /// let permutation = orasort(&names);
+    /// permutation.apply_to_many((&mut ids, &mut names, &mut scores));
+    ///
+    /// assert_eq!(ids, vec![1, 2, 3]);
+    /// assert_eq!(names, vec!["a", "b", "c"]);
+    /// assert_eq!(scores, vec![0.1, 0.2, 0.3]);
+    /// ```
+    pub fn apply_to_many<C: Permute>(&self, columns: C) {
+        permute_in_place(&self.indices, columns);
+    }
+}
+
+/// Reorders `columns` so that position `i` receives the item at `indices[i]`.
+///
+/// `indices` must be a permutation of `0..indices.len()`. Cycles are followed with a
+/// visited bitset, so `indices` itself is left untouched.
+pub(crate) fn permute_in_place<C: Permute>(indices: &[usize], mut columns: C) {
+    let len = indices.len();
+    columns.check_len(len);
+
+    let mut visited = vec![0u64; len.div_ceil(64)];
+    for start in 0..len {
+        if visited[start / 64] & (1 << (start % 64)) != 0 {
+            continue;
+        }
+        // Walk the cycle through `start`, pulling each position's item into place.
+        let mut current = start;
+        loop {
+            visited[current / 64] |= 1 << (current % 64);
+            let next = indices[current];
+            if next == start {
+                break;
+            }
+            columns.swap(current, next);
+            current = next;
+        }
+    }
+}
+
+impl Deref for Permutation {
+    type Target = [usize];
+
+    fn deref(&self) -> &[usize] {
+        &self.indices
+    }
+}
+
+impl AsRef<[usize]> for Permutation {
+    fn as_ref(&self) -> &[usize] {
+        &self.indices
+    }
+}
+
+impl From<Permutation> for Vec<usize> {
+    fn from(permutation: Permutation) -> Self {
+        permutation.indices
+    }
+}
+
+impl TryFrom<Vec<usize>> for Permutation {
+    type Error = OrasortError;
+
+    /// Validates that `indices` is a permutation of `0..indices.len()`.
+    ///
+    /// Fails with [`OrasortError::IndexOutOfRange`] or [`OrasortError::DuplicateIndex`].
+    fn try_from(indices: Vec<usize>) -> Result<Self, OrasortError> {
+        check_indices(&indices, indices.len())?;
+        Ok(Self { indices })
+    }
+}
+
+impl IntoIterator for Permutation {
+    type Item = usize;
+    type IntoIter = alloc::vec::IntoIter<usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.indices.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Permutation {
+    type Item = &'a usize;
+    type IntoIter = core::slice::Iter<'a, usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.indices.iter()
+    }
+}
+
+impl PartialEq<Vec<usize>> for Permutation {
+    fn eq(&self, other: &Vec<usize>) -> bool {
+        self.indices == *other
+    }
+}
+
+impl PartialEq<[usize]> for Permutation {
+    fn eq(&self, other: &[usize]) -> bool {
+        self.indices == other
+    }
+}
+
+impl PartialEq<&[usize]> for Permutation {
+    fn eq(&self, other: &&[usize]) -> bool {
+        self.indices == *other
+    }
+}
+
+impl<const N: usize> PartialEq<[usize; N]> for Permutation {
+    fn eq(&self, other: &[usize; N]) -> bool {
+        self.indices == other
+    }
+}
+
+/// Collections that a [`Permutation`] can reorder in-place.
+///
+/// Implemented for slices, vectors, mutable references to them, and tuples of up to 12
+/// `Permute` columns, which are swapped together.
+pub trait Permute {
+    /// Panics unless every column has length `len`.
+    fn check_len(&self, len: usize);
+
+    /// Swaps the items at positions `a` and `b` in every column.
+    fn swap(&mut self, a: usize, b: usize);
+}
+
+impl<T> Permute for [T] {
+    fn check_len(&self, len: usize) {
+        assert_eq!(
+            self.len(),
+            len,
+            "data and permutation must have the same length"
+        );
+    }
+
+    #[inline(always)]
+    fn swap(&mut self, a: usize, b: usize) {
+        <[T]>::swap(self, a, b);
+    }
+}
+
+impl<T> Permute for Vec<T> {
+    fn check_len(&self, len: usize) {
+        self.as_slice().check_len(len);
+    }
+
+    #[inline(always)]
+    fn swap(&mut self, a: usize, b: usize) {
+        self.as_mut_slice().swap(a, b);
+    }
+}
+
+impl<P: Permute + ?Sized> Permute for &mut P {
+    fn check_len(&self, len: usize) {
+        (**self).check_len(len);
+    }
+
+    #[inline(always)]
+    fn swap(&mut self, a: usize, b: usize) {
+        (**self).swap(a, b);
+    }
+}
+
+macro_rules! impl_permute_tuple {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name: Permute),+> Permute for ($($name,)+) {
+            fn check_len(&self, len: usize) {
+                $(self.$index.check_len(len);)+
+            }
+
+            #[inline(always)]
+            fn swap(&mut self, a: usize, b: usize) {
+                $(self.$index.swap(a, b);)+
+            }
+        }
+    };
+}
+
+impl_permute_tuple!(A 0);
+impl_permute_tuple!(A 0, B 1);
+impl_permute_tuple!(A 0, B 1, C 2);
+impl_permute_tuple!(A 0, B 1, C 2, D 3);
+impl_permute_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_permute_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_permute_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_permute_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+impl_permute_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+impl_permute_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+impl_permute_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+impl_permute_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
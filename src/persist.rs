@@ -0,0 +1,592 @@
+//! Sorted permutations saved to files.
+//!
+//! Sorting the same immutable dataset in every process that needs its order is wasted work.
+//! [`PermutationFile`] saves a [`Permutation`], optionally with the cached 8-byte prefix and
+//! the LCP (see [`orasort_lcp`](crate::orasort_lcp)) of every sorted key, together with a
+//! [`fingerprint`] of the keys it was computed from. [`PermutationView`] reads such a file
+//! from a byte buffer without copying, and `MappedPermutation` (with the `mmap` feature)
+//! maps it from disk. Both refuse files whose fingerprint does not match the keys at hand.
+//!
+//! # Format
+//!
+//! Integers are stored in the byte order of the writing machine; a marker in the header
+//! lets readers on other machines refuse the file instead of misreading it.
+//!
+//! ```text
+//! header   := "ORAPERM\0" version:u32 byte-order:u32 len:u64 fingerprint:u64
+//!             index-width:u8 lcp-width:u8 has-prefixes:u8 zero:u8*5 crc32:u32 zero:u8*20
+//! file     := header index{len} padding prefix:u64{len}? lcp{len}? padding
+//! ```
+//!
+//! Indices are 4 bytes wide if they fit, 8 otherwise, and the same holds for LCPs (width 0
+//! if absent). Padding aligns every section to 8 bytes. The checksum is CRC-32 (IEEE) over
+//! the header, with the checksum field zeroed, and everything after it.
+
+use crate::codec::Crc32;
+use crate::core::KeyAccessor;
+use crate::error::PersistError;
+use crate::permutation::Permutation;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Magic bytes at the start of every file.
+const MAGIC: &[u8; 8] = b"ORAPERM\0";
+
+/// Current format version.
+const VERSION: u32 = 1;
+
+/// Written in native byte order; reads back differently on machines of the other order.
+const BYTE_ORDER_MARK: u32 = 0x0102_0304;
+
+/// Size of the header in bytes.
+const HEADER_LEN: usize = 64;
+
+/// Position of the checksum in the header.
+const CHECKSUM_AT: usize = 40;
+
+/// Number of values encoded at a time while writing.
+const WRITE_CHUNK: usize = 512;
+
+/// Returns a 64-bit fingerprint of the keys of `keys`, in order.
+///
+/// The fingerprint covers the number of keys and every key's length and bytes, so any
+/// change to the dataset changes it with overwhelming probability. It is not a
+/// cryptographic hash and reads every key once.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::persist::fingerprint;
+///
+/// assert_eq!(fingerprint(&vec!["a", "b"]), fingerprint(&vec!["a", "b"]));
+/// assert_ne!(fingerprint(&vec!["a", "b"]), fingerprint(&vec!["b", "a"]));
+/// assert_ne!(fingerprint(&vec!["ab"]), fingerprint(&vec!["a", "b"]));
+/// ```
+pub fn fingerprint<T: KeyAccessor + ?Sized>(keys: &T) -> u64 {
+    let mut hash = mix(0, keys.len() as u64);
+    for index in 0..keys.len() {
+        let len = keys.get_key(index).len();
+        hash = mix(hash, len as u64);
+        for offset in (0..len).step_by(8) {
+            hash = mix(hash, keys.get_u64_prefix(index, offset));
+        }
+    }
+    finalize(hash)
+}
+
+fn mix(hash: u64, value: u64) -> u64 {
+    (hash.rotate_left(5) ^ value).wrapping_mul(0x517C_C1B7_2722_0A95)
+}
+
+/// Spreads every input bit over the whole hash (the MurmurHash3 finalizer).
+fn finalize(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xC4CE_B9FE_1A85_EC53);
+    hash ^ (hash >> 33)
+}
+
+/// A permutation prepared for saving.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::orasort_lcp;
+/// use orasort::persist::{PermutationFile, PermutationView};
+///
+/// let keys = vec!["pear", "apple", "peach"];
+/// let (order, lcp) = orasort_lcp(&keys);
+/// let bytes = PermutationFile::new(&keys, &order)
+///     .with_prefixes()
+///     .with_lcp(&lcp)
+///     .to_vec();
+///
+/// let view = PermutationView::new(&bytes, &keys).unwrap();
+/// assert_eq!(view.to_permutation(), order);
+/// assert_eq!(view.lcp().unwrap().to_vec(), vec![0, 0, 3]);
+///
+/// // The file no longer matches changed keys.
+/// assert!(PermutationView::new(&bytes, &vec!["pear", "apple", "plum"]).is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct PermutationFile<'a, T: KeyAccessor + ?Sized> {
+    keys: &'a T,
+    permutation: &'a Permutation,
+    lcp: Option<&'a [usize]>,
+    prefixes: bool,
+    fingerprint: u64,
+}
+
+impl<'a, T: KeyAccessor + ?Sized> PermutationFile<'a, T> {
+    /// Prepares `permutation`, the sorted order of `keys`, for saving.
+    ///
+    /// Computes the [`fingerprint`] of `keys`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `permutation.len() != keys.len()`.
+    pub fn new(keys: &'a T, permutation: &'a Permutation) -> Self {
+        assert_eq!(
+            keys.len(),
+            permutation.len(),
+            "keys and permutation must have the same length"
+        );
+        Self {
+            keys,
+            permutation,
+            lcp: None,
+            prefixes: false,
+            fingerprint: fingerprint(keys),
+        }
+    }
+
+    /// Also saves the first 8 bytes of every sorted key, as cached by the sort.
+    pub fn with_prefixes(mut self) -> Self {
+        self.prefixes = true;
+        self
+    }
+
+    /// Also saves the LCP of every sorted key with its predecessor, as returned by
+    /// [`orasort_lcp`](crate::orasort_lcp).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lcp.len()` differs from the length of the permutation.
+    pub fn with_lcp(mut self, lcp: &'a [usize]) -> Self {
+        assert_eq!(
+            lcp.len(),
+            self.permutation.len(),
+            "LCPs and permutation must have the same length"
+        );
+        self.lcp = Some(lcp);
+        self
+    }
+
+    /// Returns the file contents.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.layout().total_len());
+        self.write_pieces(|piece| {
+            out.extend_from_slice(piece);
+            Ok::<(), core::convert::Infallible>(())
+        })
+        .unwrap_or_else(|never| match never {});
+        out
+    }
+
+    /// Writes the file contents to `out`.
+    #[cfg(feature = "std")]
+    pub fn write<W: std::io::Write>(&self, mut out: W) -> std::io::Result<()> {
+        self.write_pieces(|piece| out.write_all(piece))
+    }
+
+    fn layout(&self) -> Layout {
+        let len = self.permutation.len();
+        let lcp_width = match self.lcp {
+            None => 0,
+            Some(lcp) => width_for(lcp.iter().copied().max().unwrap_or(0)),
+        };
+        Layout {
+            len,
+            fingerprint: self.fingerprint,
+            index_width: width_for(len.saturating_sub(1)),
+            lcp_width,
+            prefixes: self.prefixes,
+        }
+    }
+
+    fn write_pieces<E>(&self, mut write: impl FnMut(&[u8]) -> Result<(), E>) -> Result<(), E> {
+        let layout = self.layout();
+        let mut header = layout.header();
+
+        let mut crc = Crc32::new();
+        crc.update(&header);
+        self.write_payload(&layout, |piece| {
+            crc.update(piece);
+            Ok::<(), core::convert::Infallible>(())
+        })
+        .unwrap_or_else(|never| match never {});
+        header[CHECKSUM_AT..CHECKSUM_AT + 4].copy_from_slice(&crc.finish().to_ne_bytes());
+
+        write(&header)?;
+        self.write_payload(&layout, write)
+    }
+
+    fn write_payload<E>(
+        &self,
+        layout: &Layout,
+        mut write: impl FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let indices = self.permutation.iter().map(|&index| index as u64);
+        write_values(indices, layout.index_width, &mut write)?;
+        write(&[0; 8][..padding(layout.len * layout.index_width)])?;
+
+        if layout.prefixes {
+            let prefixes = self
+                .permutation
+                .iter()
+                .map(|&index| self.keys.get_u64_prefix(index, 0));
+            write_values(prefixes, 8, &mut write)?;
+        }
+        if let Some(lcp) = self.lcp {
+            write_values(lcp.iter().map(|&n| n as u64), layout.lcp_width, &mut write)?;
+            write(&[0; 8][..padding(layout.len * layout.lcp_width)])?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `values` in native byte order, `width` bytes each.
+fn write_values<E>(
+    values: impl Iterator<Item = u64>,
+    width: usize,
+    write: &mut impl FnMut(&[u8]) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut buf = [0u8; WRITE_CHUNK * 8];
+    let mut filled = 0;
+    for value in values {
+        match width {
+            4 => buf[filled..filled + 4].copy_from_slice(&(value as u32).to_ne_bytes()),
+            _ => buf[filled..filled + 8].copy_from_slice(&value.to_ne_bytes()),
+        }
+        filled += width;
+        if filled == buf.len() {
+            write(&buf)?;
+            filled = 0;
+        }
+    }
+    write(&buf[..filled])
+}
+
+/// Returns the width of a stored value that is at most `max`.
+fn width_for(max: usize) -> usize {
+    if max as u64 <= u64::from(u32::MAX) {
+        4
+    } else {
+        8
+    }
+}
+
+/// Returns the number of bytes that align `len` to 8.
+fn padding(len: usize) -> usize {
+    len.wrapping_neg() % 8
+}
+
+/// Rounds `len` up to a multiple of 8.
+fn align(len: usize) -> usize {
+    len + padding(len)
+}
+
+/// Shape of a file, as described by its header.
+#[derive(Clone, Copy, Debug)]
+struct Layout {
+    len: usize,
+    fingerprint: u64,
+    index_width: usize,
+    lcp_width: usize,
+    prefixes: bool,
+}
+
+impl Layout {
+    /// Returns the header, with a zero checksum.
+    fn header(&self) -> [u8; HEADER_LEN] {
+        let mut header = [0; HEADER_LEN];
+        header[0..8].copy_from_slice(MAGIC);
+        header[8..12].copy_from_slice(&VERSION.to_ne_bytes());
+        header[12..16].copy_from_slice(&BYTE_ORDER_MARK.to_ne_bytes());
+        header[16..24].copy_from_slice(&(self.len as u64).to_ne_bytes());
+        header[24..32].copy_from_slice(&self.fingerprint.to_ne_bytes());
+        header[32] = self.index_width as u8;
+        header[33] = self.lcp_width as u8;
+        header[34] = u8::from(self.prefixes);
+        header
+    }
+
+    /// Reads a header, checking everything but the checksum.
+    fn parse(data: &[u8]) -> Result<Self, PersistError> {
+        if data.len() < HEADER_LEN || !data.starts_with(MAGIC) {
+            return Err(PersistError::NotAPermutationFile);
+        }
+        if read_u32(data, 12) != BYTE_ORDER_MARK {
+            return Err(PersistError::ByteOrderMismatch);
+        }
+        let version = read_u32(data, 8);
+        if version != VERSION {
+            return Err(PersistError::UnsupportedVersion(version));
+        }
+
+        let layout = Self {
+            len: usize::try_from(read_u64(data, 16)).map_err(|_| PersistError::Corrupt)?,
+            fingerprint: read_u64(data, 24),
+            index_width: usize::from(data[32]),
+            lcp_width: usize::from(data[33]),
+            prefixes: data[34] == 1,
+        };
+        let valid = matches!(layout.index_width, 4 | 8)
+            && matches!(layout.lcp_width, 0 | 4 | 8)
+            && data[34] <= 1
+            && layout.len.checked_mul(32).is_some();
+        if !valid {
+            return Err(PersistError::Corrupt);
+        }
+        Ok(layout)
+    }
+
+    fn indices(&self) -> Range<usize> {
+        HEADER_LEN..HEADER_LEN + self.len * self.index_width
+    }
+
+    fn prefixes(&self) -> Option<Range<usize>> {
+        let start = align(self.indices().end);
+        self.prefixes.then(|| start..start + self.len * 8)
+    }
+
+    fn lcp(&self) -> Option<Range<usize>> {
+        let start = self
+            .prefixes()
+            .map_or(align(self.indices().end), |prefixes| prefixes.end);
+        (self.lcp_width > 0).then(|| start..start + self.len * self.lcp_width)
+    }
+
+    fn total_len(&self) -> usize {
+        let end = self
+            .lcp()
+            .or(self.prefixes())
+            .map_or(self.indices().end, |r| r.end);
+        align(end)
+    }
+}
+
+fn read_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_ne_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], at: usize) -> u64 {
+    u64::from_ne_bytes(data[at..at + 8].try_into().unwrap())
+}
+
+/// A saved permutation, read in place from a byte buffer.
+#[derive(Clone, Copy, Debug)]
+pub struct PermutationView<'a> {
+    data: &'a [u8],
+    layout: Layout,
+}
+
+impl<'a> PermutationView<'a> {
+    /// Reads the permutation saved in `data` for `keys`.
+    ///
+    /// `data` must start at an 8-byte aligned address, as memory maps and heap buffers do.
+    /// Reading verifies the checksum, checks that the indices form a permutation and
+    /// recomputes the [`fingerprint`] of `keys`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PersistError::Stale`] if the file was saved for different keys, and other
+    /// [`PersistError`]s if `data` is not a valid file or cannot be read in place.
+    pub fn new<T: KeyAccessor + ?Sized>(data: &'a [u8], keys: &T) -> Result<Self, PersistError> {
+        let layout = Layout::parse(data)?;
+        if data.len() != layout.total_len() {
+            return Err(PersistError::Corrupt);
+        }
+        if !(data.as_ptr() as usize).is_multiple_of(8) {
+            return Err(PersistError::Misaligned);
+        }
+
+        let mut crc = Crc32::new();
+        crc.update(&data[..CHECKSUM_AT]);
+        crc.update(&[0; 4]);
+        crc.update(&data[CHECKSUM_AT + 4..]);
+        if crc.finish() != read_u32(data, CHECKSUM_AT) {
+            return Err(PersistError::ChecksumMismatch);
+        }
+
+        if layout.len != keys.len() || layout.fingerprint != fingerprint(keys) {
+            return Err(PersistError::Stale);
+        }
+
+        let view = Self { data, layout };
+        let indices = view.indices();
+        let mut seen = vec![false; layout.len];
+        for position in 0..indices.len() {
+            // Wide indices may not fit `usize` on 32-bit targets.
+            let index = indices.get(position).ok_or(PersistError::Corrupt)?;
+            if index >= layout.len || core::mem::replace(&mut seen[index], true) {
+                return Err(PersistError::Corrupt);
+            }
+        }
+        if let Some(lcp) = view.lcp()
+            && (0..lcp.len()).any(|position| lcp.get(position).is_none())
+        {
+            return Err(PersistError::Corrupt);
+        }
+        Ok(view)
+    }
+
+    /// Returns the number of sorted items.
+    pub fn len(&self) -> usize {
+        self.layout.len
+    }
+
+    /// Returns `true` if the permutation is empty.
+    pub fn is_empty(&self) -> bool {
+        self.layout.len == 0
+    }
+
+    /// Returns the fingerprint of the keys the permutation was saved for.
+    pub fn fingerprint(&self) -> u64 {
+        self.layout.fingerprint
+    }
+
+    /// Returns the sorted order: the original index of every sorted item.
+    pub fn indices(&self) -> PackedSlice<'a> {
+        self.packed(self.layout.indices(), self.layout.index_width)
+    }
+
+    /// Returns the first 8 bytes of every sorted key, if they were saved.
+    pub fn prefixes(&self) -> Option<&'a [u64]> {
+        let range = self.layout.prefixes()?;
+        Some(cast(&self.data[range]))
+    }
+
+    /// Returns the LCP of every sorted key with its predecessor, if they were saved.
+    pub fn lcp(&self) -> Option<PackedSlice<'a>> {
+        let range = self.layout.lcp()?;
+        Some(self.packed(range, self.layout.lcp_width))
+    }
+
+    /// Copies the sorted order into a [`Permutation`].
+    pub fn to_permutation(&self) -> Permutation {
+        Permutation::from_vec_unchecked(self.indices().iter().collect())
+    }
+
+    fn packed(&self, range: Range<usize>, width: usize) -> PackedSlice<'a> {
+        let bytes = &self.data[range];
+        match width {
+            4 => PackedSlice::U32(cast(bytes)),
+            _ => PackedSlice::U64(cast(bytes)),
+        }
+    }
+}
+
+/// Integer types every bit pattern is valid for.
+trait Word: Copy {}
+
+impl Word for u32 {}
+impl Word for u64 {}
+
+/// Reinterprets aligned bytes as integers.
+fn cast<U: Word>(bytes: &[u8]) -> &[U] {
+    // SAFETY: Every bit pattern is a valid `U`. The data start
+    // is 8-byte aligned and every section starts at a multiple of 8, which `align_to`
+    // confirms by leaving no unaligned prefix.
+    let (head, values, tail) = unsafe { bytes.align_to::<U>() };
+    assert!(head.is_empty() && tail.is_empty());
+    values
+}
+
+/// Integers stored 4 or 8 bytes wide.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PackedSlice<'a> {
+    /// Values stored as `u32`.
+    U32(&'a [u32]),
+    /// Values stored as `u64`.
+    U64(&'a [u64]),
+}
+
+impl PackedSlice<'_> {
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::U32(values) => values.len(),
+            Self::U64(values) => values.len(),
+        }
+    }
+
+    /// Returns `true` if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns value `i`, or `None` if there is none or it does not fit `usize`.
+    ///
+    /// Values of a [`PermutationView`] always fit; it refuses files where they do not.
+    pub fn get(&self, i: usize) -> Option<usize> {
+        match self {
+            Self::U32(values) => values.get(i).and_then(|&v| usize::try_from(v).ok()),
+            Self::U64(values) => values.get(i).and_then(|&v| usize::try_from(v).ok()),
+        }
+    }
+
+    /// Returns an iterator over the values.
+    ///
+    /// # Panics
+    ///
+    /// Panics when reaching a value that does not fit `usize`.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = usize> + '_ {
+        (0..self.len()).map(|i| self.get(i).expect("packed value exceeds usize"))
+    }
+
+    /// Copies the values into a vector.
+    pub fn to_vec(&self) -> Vec<usize> {
+        self.iter().collect()
+    }
+}
+
+/// A saved permutation, memory-mapped from a file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use orasort::orasort;
+/// use orasort::persist::{MappedPermutation, PermutationFile};
+/// use std::fs::File;
+///
+/// let keys = vec!["pear", "apple", "peach"];
+/// let order = orasort(&keys);
+/// PermutationFile::new(&keys, &order).write(File::create("keys.perm")?)?;
+///
+/// // In another process, with the same keys:
+/// let saved = MappedPermutation::open("keys.perm", &keys)?;
+/// assert_eq!(saved.view().to_permutation(), order);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub struct MappedPermutation {
+    map: memmap2::Mmap,
+    layout: Layout,
+}
+
+#[cfg(feature = "mmap")]
+impl MappedPermutation {
+    /// Maps the permutation saved at `path` for `keys`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the file cannot be opened or mapped. A file that
+    /// [`PermutationView::new`] rejects gives an [`InvalidData`](std::io::ErrorKind::InvalidData)
+    /// error wrapping the [`PersistError`].
+    pub fn open<P, T>(path: P, keys: &T) -> std::io::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+        T: KeyAccessor + ?Sized,
+    {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: The mapping is only read. Modifying the file while it is mapped would
+        // change the permutation under us, as with any memory-mapped file.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        let layout = PermutationView::new(&map, keys)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?
+            .layout;
+        Ok(Self { map, layout })
+    }
+
+    /// Returns the saved permutation.
+    pub fn view(&self) -> PermutationView<'_> {
+        PermutationView {
+            data: &self.map,
+            layout: self.layout,
+        }
+    }
+}
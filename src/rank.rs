@@ -0,0 +1,175 @@
+//! Rank vectors with SQL tie policies.
+//!
+//! Window functions need the rank of every input row rather than the sorted order.
+//! [`orasort_ranks`] returns ranks indexed by original position, following the semantics of
+//! SQL's `ROW_NUMBER()`, `RANK()` and `DENSE_RANK()`; [`orasort_percent_ranks`] implements
+//! `PERCENT_RANK()`.
+//!
+//! Ties are detected while sorting: every comparison that finds two keys equal is recorded,
+//! so assigning ranks afterwards never compares keys again.
+
+use crate::algo::{Scratch, SortKeys, sort_pointers};
+use crate::core::{KeyAccessor, SortPtr};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::Cell;
+
+/// How equal keys are ranked by [`orasort_ranks`].
+///
+/// Ranks start at 1, as in SQL. For the rows `a, b, b, c`:
+///
+/// | Policy                         | Ranks      |
+/// |--------------------------------|------------|
+/// | [`RowNumber`](Self::RowNumber) | 1, 2, 3, 4 |
+/// | [`Rank`](Self::Rank)           | 1, 2, 2, 4 |
+/// | [`DenseRank`](Self::DenseRank) | 1, 2, 2, 3 |
+///
+/// For `PERCENT_RANK()`, use [`orasort_percent_ranks`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RankPolicy {
+    /// `ROW_NUMBER()`: the position in the sorted order. Equal keys get distinct numbers
+    /// in an unspecified order.
+    RowNumber,
+    /// `RANK()`: equal keys share the rank of the first of them, leaving gaps after ties.
+    Rank,
+    /// `DENSE_RANK()`: equal keys share a rank, without gaps.
+    DenseRank,
+}
+
+/// Returns the rank of every item of `provider` under `policy`, indexed by original position.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::rank::{RankPolicy, orasort_ranks};
+///
+/// let scores = vec!["b", "a", "c", "b"];
+///
+/// assert_eq!(orasort_ranks(&scores, RankPolicy::Rank), vec![2, 1, 4, 2]);
+/// assert_eq!(orasort_ranks(&scores, RankPolicy::DenseRank), vec![2, 1, 3, 2]);
+/// ```
+pub fn orasort_ranks<T: KeyAccessor + ?Sized>(provider: &T, policy: RankPolicy) -> Vec<usize> {
+    let (order, ties) = sort_with_ties(provider, policy != RankPolicy::RowNumber);
+    let mut ranks = vec![0; order.len()];
+
+    let mut rank = 0;
+    for (position, &index) in order.iter().enumerate() {
+        let tied = position > 0
+            && ties
+                .as_ref()
+                .is_some_and(|t| t.tied(order[position - 1], index));
+        if !tied {
+            rank = match policy {
+                RankPolicy::RowNumber | RankPolicy::Rank => position + 1,
+                RankPolicy::DenseRank => rank + 1,
+            };
+        }
+        ranks[index] = rank;
+    }
+
+    ranks
+}
+
+/// Returns the `PERCENT_RANK()` of every item of `provider`, indexed by original position.
+///
+/// The percent rank is `(rank - 1) / (n - 1)` with the [`RankPolicy::Rank`] rank, so it
+/// ranges from `0.0` to `1.0`. A single item has percent rank `0.0`.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::rank::orasort_percent_ranks;
+///
+/// let scores = vec!["b", "a", "c", "b", "d"];
+///
+/// assert_eq!(orasort_percent_ranks(&scores), vec![0.25, 0.0, 0.75, 0.25, 1.0]);
+/// ```
+pub fn orasort_percent_ranks<T: KeyAccessor + ?Sized>(provider: &T) -> Vec<f64> {
+    let ranks = orasort_ranks(provider, RankPolicy::Rank);
+    let denominator = ranks.len().saturating_sub(1).max(1) as f64;
+
+    ranks
+        .into_iter()
+        .map(|rank| (rank - 1) as f64 / denominator)
+        .collect()
+}
+
+/// Sorts `provider`, recording equal comparisons if `track_ties` is set.
+fn sort_with_ties<T: KeyAccessor + ?Sized>(
+    provider: &T,
+    track_ties: bool,
+) -> (Vec<usize>, Option<TieTracker<'_, T>>) {
+    let len = provider.len();
+    let mut pointers: Vec<SortPtr> = (0..len)
+        .map(|index| {
+            let cache = provider.get_u64_prefix(index, 0);
+            SortPtr { index, cache }
+        })
+        .collect();
+
+    let ties = if track_ties {
+        let tracker = TieTracker::new(provider);
+        sort_pointers(&tracker, &mut pointers, 0, &mut Scratch::default());
+        Some(tracker)
+    } else {
+        sort_pointers(provider, &mut pointers, 0, &mut Scratch::default());
+        None
+    };
+
+    (pointers.into_iter().map(|p| p.index).collect(), ties)
+}
+
+/// Wraps a provider and groups items into classes of equal keys as the sort compares them.
+///
+/// The classes are kept in a union-find forest over item indices. Only comparisons that
+/// end in `Equal` touch it, so inputs without duplicates pay nothing beyond the allocation.
+struct TieTracker<'a, T: KeyAccessor + ?Sized> {
+    inner: &'a T,
+    parents: Vec<Cell<usize>>,
+}
+
+impl<'a, T: KeyAccessor + ?Sized> TieTracker<'a, T> {
+    fn new(inner: &'a T) -> Self {
+        Self {
+            inner,
+            parents: (0..inner.len()).map(Cell::new).collect(),
+        }
+    }
+
+    /// Returns the representative of the class of `index`, halving the path on the way.
+    fn find(&self, mut index: usize) -> usize {
+        loop {
+            let parent = self.parents[index].get();
+            if parent == index {
+                return index;
+            }
+            let grandparent = self.parents[parent].get();
+            self.parents[index].set(grandparent);
+            index = grandparent;
+        }
+    }
+
+    /// Returns `true` if the keys at `a` and `b` were found equal.
+    fn tied(&self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+}
+
+impl<T: KeyAccessor + ?Sized> SortKeys for TieTracker<'_, T> {
+    #[inline(always)]
+    fn key(&self, index: usize) -> &[u8] {
+        self.inner.get_key(index)
+    }
+
+    #[inline(always)]
+    fn prefix(&self, index: usize, offset: usize) -> u64 {
+        self.inner.get_u64_prefix(index, offset)
+    }
+
+    fn keys_equal(&self, a: usize, b: usize) {
+        let (root_a, root_b) = (self.find(a), self.find(b));
+        if root_a != root_b {
+            self.parents[root_a.max(root_b)].set(root_a.min(root_b));
+        }
+    }
+}
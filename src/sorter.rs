@@ -0,0 +1,120 @@
+//! Reusable sorter with retained buffers.
+//!
+//! [`orasort`](crate::orasort) allocates its pointer array, radix buffer and output vector on
+//! every call. For workloads that sort many small batches, [`Sorter`] keeps all of these
+//! buffers between calls so that, once warmed up to the largest batch size, sorting does not
+//! allocate at all.
+
+use crate::algo::{Scratch, sort_pointers};
+use crate::core::{KeyAccessor, SortPtr};
+use alloc::vec::Vec;
+
+/// A reusable sorter holding the pointer, scratch and output buffers of the sort.
+///
+/// Buffers grow to fit the largest input seen so far and are never shrunk implicitly; use
+/// [`Sorter::shrink`] to release them.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::Sorter;
+///
+/// let mut sorter = Sorter::new();
+///
+/// for batch in [vec!["pear", "fig"], vec!["plum", "kiwi", "apple"]] {
+///     let indices = sorter.sort(&batch);
+///     let sorted: Vec<&str> = indices.iter().map(|&i| batch[i]).collect();
+///     assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Sorter {
+    pointers: Vec<SortPtr>,
+    scratch: Scratch,
+    indices: Vec<usize>,
+}
+
+impl Sorter {
+    /// Creates a sorter with empty buffers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a sorter whose buffers can sort `len` items without allocating.
+    pub fn with_capacity(len: usize) -> Self {
+        let mut sorter = Self::new();
+        sorter.reserve(len);
+        sorter
+    }
+
+    /// Grows the buffers so that sorting up to `len` items does not allocate.
+    pub fn reserve(&mut self, len: usize) {
+        self.pointers
+            .reserve(len.saturating_sub(self.pointers.len()));
+        self.indices.reserve(len.saturating_sub(self.indices.len()));
+        self.scratch.reserve(len);
+    }
+
+    /// Releases all buffers held by the sorter.
+    pub fn shrink(&mut self) {
+        self.pointers = Vec::new();
+        self.indices = Vec::new();
+        self.scratch.release();
+    }
+
+    /// Performs an index-based sort of `provider`, like [`orasort`](crate::orasort).
+    ///
+    /// The returned indices borrow the sorter's output buffer and stay valid until the
+    /// next call.
+    pub fn sort<T: KeyAccessor + ?Sized>(&mut self, provider: &T) -> &[usize] {
+        let len = provider.len();
+
+        self.pointers.clear();
+        self.pointers.extend((0..len).map(|index| {
+            let cache = provider.get_u64_prefix(index, 0);
+            SortPtr { index, cache }
+        }));
+
+        sort_pointers(provider, &mut self.pointers, 0, &mut self.scratch);
+
+        self.indices.clear();
+        self.indices.extend(self.pointers.iter().map(|p| p.index));
+        &self.indices
+    }
+
+    /// Returns the result of the last [`sort`](Self::sort).
+    pub(crate) fn output(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Returns the number of heap bytes currently held by the sorter's buffers.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.pointers.capacity() * size_of::<SortPtr>()
+            + self.indices.capacity() * size_of::<usize>()
+            + self.scratch.heap_bytes()
+    }
+
+    /// Sorts `indices` in-place, skipping `offset` bytes, like
+    /// [`orasort_slice`](crate::orasort_slice).
+    ///
+    /// Only the pointer and scratch buffers are used; the output buffer is left untouched.
+    pub fn sort_slice<T: KeyAccessor + ?Sized>(
+        &mut self,
+        provider: &T,
+        indices: &mut [usize],
+        offset: usize,
+    ) {
+        self.pointers.clear();
+        self.pointers.extend(indices.iter().map(|&index| {
+            let cache = provider.get_u64_prefix(index, offset);
+            SortPtr { index, cache }
+        }));
+
+        sort_pointers(provider, &mut self.pointers, offset, &mut self.scratch);
+
+        indices
+            .iter_mut()
+            .zip(self.pointers.iter())
+            .for_each(|(slot, p)| *slot = p.index);
+    }
+}
@@ -0,0 +1,687 @@
+//! Sorted string tables: immutable, block-based files of sorted keys and values.
+//!
+//! [`SstWriter`] sorts a [`KeyAccessor`] and writes its keys (and optionally one value per
+//! key) in sorted order. Keys are front-coded: every key stores only the bytes after the
+//! prefix it shares with its predecessor, taken from [`orasort_lcp`] instead of comparing
+//! the neighbours again. Every `restart_interval`-th key of a block is stored in full, so a
+//! lookup can binary search the block before decoding a few keys.
+//!
+//! [`SstReader`] opens a table from a byte buffer (e.g. a memory-mapped file), verifying
+//! all checksums once, and hands out [`SstCursor`]s for seeks and ordered iteration.
+//!
+//! # Format
+//!
+//! All integers are little-endian unless they are varints (unsigned LEB128).
+//!
+//! ```text
+//! table  := data-block* index-block footer
+//! block  := entry* restart:u32* restart-count:u32 crc32:u32
+//! entry  := shared:varint unshared:varint value-len:varint key-suffix value
+//! footer := index-offset:u64 index-len:u64 entry-count:u64 crc32:u32 "ORASST01"
+//! ```
+//!
+//! Restarts are block-relative offsets of entries with `shared = 0`. The index block has an
+//! entry for every data block, in order: the block's last key and, as value, the block's
+//! offset and length (without checksum) as varints. Checksums are CRC-32 (IEEE) over the
+//! block, or over the first 24 footer bytes.
+
+use crate::codec::{crc32, get_varint, put_varint};
+use crate::core::KeyAccessor;
+use crate::error::SstError;
+use crate::lcp::orasort_lcp;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Magic bytes at the end of every table.
+const MAGIC: &[u8; 8] = b"ORASST01";
+
+/// Size of the footer in bytes.
+const FOOTER_LEN: usize = 36;
+
+/// Size of the checksum after every block.
+const CHECKSUM_LEN: usize = 4;
+
+/// Default target size of a data block.
+const DEFAULT_BLOCK_SIZE: usize = 4 * 1024;
+
+/// Default number of keys between keys stored in full.
+const DEFAULT_RESTART_INTERVAL: usize = 16;
+
+/// Size of the entries at which a data block is closed regardless of the block size, so
+/// that every entry starts at an offset that fits a `u32` restart.
+const MAX_BLOCK_ENTRIES_LEN: usize = u32::MAX as usize;
+
+/// Writes sorted string tables.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::sst::{SstReader, SstWriter};
+///
+/// let keys = vec!["pear", "apple", "peach", "apricot"];
+/// let prices = vec!["3", "1", "4", "2"];
+/// let table = SstWriter::new().build_with_values(&keys, &prices);
+///
+/// let reader = SstReader::new(&table).unwrap();
+/// assert_eq!(reader.get(b"peach"), Some(&b"4"[..]));
+///
+/// let mut cursor = reader.cursor();
+/// cursor.seek(b"b");
+/// assert_eq!(cursor.key(), b"peach");
+/// cursor.advance();
+/// assert_eq!(cursor.key(), b"pear");
+/// cursor.advance();
+/// assert!(!cursor.is_valid());
+/// ```
+#[derive(Clone, Debug)]
+pub struct SstWriter {
+    block_size: usize,
+    restart_interval: usize,
+}
+
+impl Default for SstWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SstWriter {
+    /// Creates a writer with 4 KiB blocks and a key stored in full every 16 keys.
+    pub fn new() -> Self {
+        Self {
+            block_size: DEFAULT_BLOCK_SIZE,
+            restart_interval: DEFAULT_RESTART_INTERVAL,
+        }
+    }
+
+    /// Sets the target size of data blocks. A block is closed as soon as it reaches this
+    /// size, so blocks are slightly larger. Blocks are closed at 4 GiB of entries at the
+    /// latest, since restarts are 32-bit offsets.
+    pub fn block_size(mut self, bytes: usize) -> Self {
+        self.block_size = bytes;
+        self
+    }
+
+    /// Sets how many keys follow a key stored in full before the next one (at least 1).
+    ///
+    /// Longer intervals compress better; shorter ones make seeks decode fewer keys.
+    pub fn restart_interval(mut self, interval: usize) -> Self {
+        self.restart_interval = interval.max(1);
+        self
+    }
+
+    /// Sorts `keys` and returns a table of them with empty values.
+    pub fn build<K: KeyAccessor + ?Sized>(&self, keys: &K) -> Vec<u8> {
+        self.build_with_values(keys, &NoValues(keys.len()))
+    }
+
+    /// Sorts `keys` and returns a table with `values.get_key(i)` as the value of key `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keys` and `values` have different lengths, or if the index block, which
+    /// holds the last key of every data block, grows past 4 GiB.
+    pub fn build_with_values<K, V>(&self, keys: &K, values: &V) -> Vec<u8>
+    where
+        K: KeyAccessor + ?Sized,
+        V: KeyAccessor + ?Sized,
+    {
+        let mut out = Vec::new();
+        self.write_pieces(keys, values, |piece| {
+            out.extend_from_slice(piece);
+            Ok::<(), core::convert::Infallible>(())
+        })
+        .unwrap_or_else(|never| match never {});
+        out
+    }
+
+    /// Sorts `keys` and writes a table of them with empty values.
+    #[cfg(feature = "std")]
+    pub fn write<K, W>(&self, keys: &K, out: W) -> std::io::Result<()>
+    where
+        K: KeyAccessor + ?Sized,
+        W: std::io::Write,
+    {
+        self.write_with_values(keys, &NoValues(keys.len()), out)
+    }
+
+    /// Sorts `keys` and writes a table with `values.get_key(i)` as the value of key `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keys` and `values` have different lengths, or if the index block, which
+    /// holds the last key of every data block, grows past 4 GiB.
+    #[cfg(feature = "std")]
+    pub fn write_with_values<K, V, W>(
+        &self,
+        keys: &K,
+        values: &V,
+        mut out: W,
+    ) -> std::io::Result<()>
+    where
+        K: KeyAccessor + ?Sized,
+        V: KeyAccessor + ?Sized,
+        W: std::io::Write,
+    {
+        self.write_pieces(keys, values, |piece| out.write_all(piece))
+    }
+
+    fn write_pieces<K, V, E>(
+        &self,
+        keys: &K,
+        values: &V,
+        mut write: impl FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E>
+    where
+        K: KeyAccessor + ?Sized,
+        V: KeyAccessor + ?Sized,
+    {
+        assert_eq!(
+            keys.len(),
+            values.len(),
+            "keys and values must have the same length"
+        );
+
+        let (order, lcp) = orasort_lcp(keys);
+        let mut block = BlockBuilder::new(self.restart_interval);
+        let mut index = BlockBuilder::new(1);
+        let mut handle = Vec::new();
+        let mut offset = 0;
+
+        for (position, &item) in order.iter().enumerate() {
+            let key = keys.get_key(item);
+            block.add(key, lcp[position], values.get_key(item));
+
+            if block.len() >= self.block_size.min(MAX_BLOCK_ENTRIES_LEN)
+                || position + 1 == order.len()
+            {
+                let len = block.len_with_trailer();
+                write(block.finish())?;
+                block.reset();
+
+                handle.clear();
+                put_varint(&mut handle, offset as u64);
+                put_varint(&mut handle, len as u64);
+                index.add(key, 0, &handle);
+                offset += len + CHECKSUM_LEN;
+            }
+        }
+
+        let index_len = index.len_with_trailer();
+        write(index.finish())?;
+
+        let mut footer = [0; FOOTER_LEN];
+        footer[0..8].copy_from_slice(&(offset as u64).to_le_bytes());
+        footer[8..16].copy_from_slice(&(index_len as u64).to_le_bytes());
+        footer[16..24].copy_from_slice(&(order.len() as u64).to_le_bytes());
+        let checksum = crc32(&footer[..24]);
+        footer[24..28].copy_from_slice(&checksum.to_le_bytes());
+        footer[28..].copy_from_slice(MAGIC);
+        write(&footer)
+    }
+}
+
+/// Values of a table written without values.
+struct NoValues(usize);
+
+impl KeyAccessor for NoValues {
+    fn get_key(&self, _index: usize) -> &[u8] {
+        &[]
+    }
+
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+/// Accumulates the entries of one block.
+struct BlockBuilder {
+    buf: Vec<u8>,
+    restarts: Vec<u32>,
+    entries: usize,
+    restart_interval: usize,
+}
+
+impl BlockBuilder {
+    fn new(restart_interval: usize) -> Self {
+        Self {
+            buf: Vec::new(),
+            restarts: Vec::new(),
+            entries: 0,
+            restart_interval,
+        }
+    }
+
+    /// Returns the size of the entries added so far.
+    fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Returns the size of the block once finished, without its checksum.
+    fn len_with_trailer(&self) -> usize {
+        self.buf.len() + 4 * (self.restarts.len() + 1)
+    }
+
+    /// Adds an entry whose key shares `shared` bytes with the previous key of the table.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entry is a restart starting past `u32::MAX`. Data blocks are closed
+    /// before that (see [`MAX_BLOCK_ENTRIES_LEN`]); the index block cannot be.
+    fn add(&mut self, key: &[u8], shared: usize, value: &[u8]) {
+        let shared = if self.entries.is_multiple_of(self.restart_interval) {
+            let restart = u32::try_from(self.buf.len()).expect("block exceeds 4 GiB");
+            self.restarts.push(restart);
+            0
+        } else {
+            shared
+        };
+
+        put_varint(&mut self.buf, shared as u64);
+        put_varint(&mut self.buf, (key.len() - shared) as u64);
+        put_varint(&mut self.buf, value.len() as u64);
+        self.buf.extend_from_slice(&key[shared..]);
+        self.buf.extend_from_slice(value);
+        self.entries += 1;
+    }
+
+    /// Appends the restarts and the checksum and returns the finished block.
+    fn finish(&mut self) -> &[u8] {
+        let restarts = self.restarts.len() as u32;
+        for restart in self.restarts.drain(..) {
+            self.buf.extend_from_slice(&restart.to_le_bytes());
+        }
+        self.buf.extend_from_slice(&restarts.to_le_bytes());
+        let checksum = crc32(&self.buf);
+        self.buf.extend_from_slice(&checksum.to_le_bytes());
+        &self.buf
+    }
+
+    /// Starts a new block.
+    fn reset(&mut self) {
+        self.buf.clear();
+        self.restarts.clear();
+        self.entries = 0;
+    }
+}
+
+/// Reads a sorted string table.
+///
+/// Opening a table verifies the footer, every checksum and the structure of every block,
+/// so reading entries afterwards cannot fail.
+#[derive(Clone, Debug)]
+pub struct SstReader<'a> {
+    data: &'a [u8],
+    blocks: Vec<BlockRef>,
+    entries: usize,
+}
+
+/// Location of a validated data block.
+#[derive(Clone, Debug)]
+struct BlockRef {
+    /// Last key of the block.
+    last_key: Range<usize>,
+    /// Entries of the block.
+    entries: Range<usize>,
+    /// Start of the restart array.
+    restarts: usize,
+    /// Number of restarts.
+    restart_count: usize,
+}
+
+impl BlockRef {
+    /// Returns the absolute offset of restart `n`.
+    fn restart(&self, data: &[u8], n: usize) -> usize {
+        let at = self.restarts + 4 * n;
+        self.entries.start + read_u32(data, at) as usize
+    }
+}
+
+/// A decoded entry header.
+struct Entry {
+    shared: usize,
+    suffix: Range<usize>,
+    value: Range<usize>,
+}
+
+impl<'a> SstReader<'a> {
+    /// Opens the table in `data`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`SstError`] if `data` is not a table written by [`SstWriter`] or if any
+    /// part of it is damaged.
+    pub fn new(data: &'a [u8]) -> Result<Self, SstError> {
+        if data.len() < FOOTER_LEN || !data.ends_with(MAGIC) {
+            return Err(SstError::NotATable);
+        }
+        let footer_start = data.len() - FOOTER_LEN;
+        let footer = &data[footer_start..];
+        if crc32(&footer[..24]) != read_u32(footer, 24) {
+            return Err(SstError::ChecksumMismatch {
+                offset: footer_start,
+            });
+        }
+
+        let corrupt = SstError::Corrupt {
+            offset: footer_start,
+        };
+        let index_start = usize::try_from(read_u64(footer, 0)).map_err(|_| corrupt)?;
+        let index_len = usize::try_from(read_u64(footer, 8)).map_err(|_| corrupt)?;
+        let entries = usize::try_from(read_u64(footer, 16)).map_err(|_| corrupt)?;
+        let index_end = index_start
+            .checked_add(index_len)
+            .filter(|&end| end.checked_add(CHECKSUM_LEN) == Some(footer_start))
+            .ok_or(corrupt)?;
+        let index = open_block(data, index_start..index_end)?;
+
+        let corrupt = SstError::Corrupt {
+            offset: index_start,
+        };
+        let mut blocks = Vec::new();
+        let mut key = Vec::new();
+        let mut pos = index.entries.start;
+        let mut block_start = 0;
+        let mut counted = 0;
+        while pos < index.entries.end {
+            let entry = decode_entry(data, &mut pos, index.entries.end).ok_or(corrupt)?;
+            let mut handle = entry.value.start;
+            let start = get_varint(data, &mut handle).and_then(|v| usize::try_from(v).ok());
+            let len = get_varint(data, &mut handle).and_then(|v| usize::try_from(v).ok());
+            let (Some(start), Some(len)) = (start, len) else {
+                return Err(corrupt);
+            };
+            if entry.shared != 0 || handle != entry.value.end || start != block_start {
+                return Err(corrupt);
+            }
+
+            // Data blocks are contiguous and end where the index starts.
+            block_start = len
+                .checked_add(CHECKSUM_LEN)
+                .and_then(|n| start.checked_add(n))
+                .filter(|&next| next <= index_start)
+                .ok_or(corrupt)?;
+            let mut block = open_block(data, start..start + len)?;
+
+            counted += validate_entries(data, &block, &mut key)?;
+            if key != data[entry.suffix.clone()] {
+                return Err(SstError::Corrupt { offset: start });
+            }
+            block.last_key = entry.suffix;
+            blocks.push(block);
+        }
+
+        if counted != entries || block_start != index_start {
+            return Err(corrupt);
+        }
+
+        Ok(Self {
+            data,
+            blocks,
+            entries,
+        })
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries
+    }
+
+    /// Returns `true` if the table has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries == 0
+    }
+
+    /// Returns the value of the first entry with key `key`, if there is one.
+    pub fn get(&self, key: &[u8]) -> Option<&'a [u8]> {
+        let mut cursor = self.cursor();
+        cursor.seek(key);
+        (cursor.is_valid() && cursor.key() == key).then(|| cursor.value())
+    }
+
+    /// Returns a cursor positioned at the first entry.
+    pub fn cursor(&self) -> SstCursor<'_, 'a> {
+        let mut cursor = SstCursor {
+            reader: self,
+            block: 0,
+            next: 0,
+            key: Vec::new(),
+            value: 0..0,
+            valid: false,
+        };
+        cursor.seek_to_first();
+        cursor
+    }
+}
+
+/// A position in an [`SstReader`], moving forward in key order.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::sst::{SstReader, SstWriter};
+///
+/// let keys = vec!["b", "c", "a"];
+/// let table = SstWriter::new().build(&keys);
+/// let reader = SstReader::new(&table).unwrap();
+///
+/// let mut cursor = reader.cursor();
+/// let mut seen = Vec::new();
+/// while cursor.is_valid() {
+///     seen.push(cursor.key().to_vec());
+///     cursor.advance();
+/// }
+/// assert_eq!(seen, vec![b"a", b"b", b"c"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SstCursor<'r, 'a> {
+    reader: &'r SstReader<'a>,
+    /// Block of the current entry.
+    block: usize,
+    /// Offset of the entry after the current one.
+    next: usize,
+    key: Vec<u8>,
+    value: Range<usize>,
+    valid: bool,
+}
+
+impl<'a> SstCursor<'_, 'a> {
+    /// Returns `true` if the cursor is at an entry, `false` once it moved past the last one.
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+
+    /// Returns the key of the current entry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cursor is not [valid](Self::is_valid).
+    pub fn key(&self) -> &[u8] {
+        assert!(self.valid, "cursor is not at an entry");
+        &self.key
+    }
+
+    /// Returns the value of the current entry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cursor is not [valid](Self::is_valid).
+    pub fn value(&self) -> &'a [u8] {
+        assert!(self.valid, "cursor is not at an entry");
+        &self.reader.data[self.value.clone()]
+    }
+
+    /// Moves to the first entry.
+    pub fn seek_to_first(&mut self) {
+        self.enter_block(0, None);
+        self.advance();
+    }
+
+    /// Moves to the first entry whose key is not less than `target`.
+    pub fn seek(&mut self, target: &[u8]) {
+        let data = self.reader.data;
+        let blocks = &self.reader.blocks;
+        let block = blocks.partition_point(|b| &data[b.last_key.clone()] < target);
+        if block == blocks.len() {
+            self.valid = false;
+            return;
+        }
+
+        // Keys at restarts are stored in full: find the last one before `target`.
+        let restarts = &blocks[block];
+        let (mut low, mut high) = (0, restarts.restart_count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let mut pos = restarts.restart(data, mid);
+            let entry = decode_validated(data, &mut pos, restarts.entries.end);
+            if &data[entry.suffix] < target {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        self.enter_block(block, Some(low.saturating_sub(1)));
+        self.advance();
+        while self.valid && self.key.as_slice() < target {
+            self.advance();
+        }
+    }
+
+    /// Moves to the next entry.
+    ///
+    /// Does nothing once the cursor moved past the last entry.
+    pub fn advance(&mut self) {
+        let data = self.reader.data;
+        let blocks = &self.reader.blocks;
+
+        while self.block < blocks.len() && self.next == blocks[self.block].entries.end {
+            if self.block + 1 == blocks.len() {
+                self.block = blocks.len();
+            } else {
+                self.enter_block(self.block + 1, None);
+            }
+        }
+        if self.block == blocks.len() {
+            self.valid = false;
+            return;
+        }
+
+        let entry = decode_validated(data, &mut self.next, blocks[self.block].entries.end);
+        self.key.truncate(entry.shared);
+        self.key.extend_from_slice(&data[entry.suffix]);
+        self.value = entry.value;
+        self.valid = true;
+    }
+
+    /// Positions the cursor before the entry at `restart` of `block` (its first entry by
+    /// default), or past the end if there is no such block.
+    fn enter_block(&mut self, block: usize, restart: Option<usize>) {
+        self.block = block;
+        self.key.clear();
+        self.valid = false;
+        if let Some(current) = self.reader.blocks.get(block) {
+            self.next = match restart {
+                Some(n) => current.restart(self.reader.data, n),
+                None => current.entries.start,
+            };
+        }
+    }
+}
+
+/// Checks the checksum and restart array of the block at `range` (without checksum).
+fn open_block(data: &[u8], range: Range<usize>) -> Result<BlockRef, SstError> {
+    let corrupt = SstError::Corrupt {
+        offset: range.start,
+    };
+    let stored = data
+        .get(range.end..range.end + CHECKSUM_LEN)
+        .ok_or(corrupt)?;
+    let block = &data[range.clone()];
+    if crc32(block) != read_u32(stored, 0) {
+        return Err(SstError::ChecksumMismatch {
+            offset: range.start,
+        });
+    }
+
+    let count_at = block.len().checked_sub(4).ok_or(corrupt)?;
+    let restart_count = read_u32(block, count_at) as usize;
+    let restarts = restart_count
+        .checked_mul(4)
+        .and_then(|len| count_at.checked_sub(len))
+        .ok_or(corrupt)?;
+
+    Ok(BlockRef {
+        last_key: 0..0,
+        entries: range.start..range.start + restarts,
+        restarts: range.start + restarts,
+        restart_count,
+    })
+}
+
+/// Decodes every entry of `block`, checking that entries fit the block, that shared
+/// prefixes exist and that restarts point at keys stored in full.
+///
+/// Leaves the last key of the block in `key` and returns the number of entries.
+fn validate_entries(data: &[u8], block: &BlockRef, key: &mut Vec<u8>) -> Result<usize, SstError> {
+    let corrupt = SstError::Corrupt {
+        offset: block.entries.start,
+    };
+    let mut pos = block.entries.start;
+    let mut restart = 0;
+    let mut entries = 0;
+    key.clear();
+
+    while pos < block.entries.end {
+        let at_restart = restart < block.restart_count && block.restart(data, restart) == pos;
+        let entry = decode_entry(data, &mut pos, block.entries.end).ok_or(corrupt)?;
+        if entry.shared > key.len()
+            || (at_restart && entry.shared != 0)
+            || (entries == 0 && !at_restart)
+        {
+            return Err(corrupt);
+        }
+        restart += usize::from(at_restart);
+        entries += 1;
+        key.truncate(entry.shared);
+        key.extend_from_slice(&data[entry.suffix]);
+    }
+
+    if restart != block.restart_count || entries == 0 {
+        return Err(corrupt);
+    }
+    Ok(entries)
+}
+
+/// Decodes the entry at `pos`, which must end by `end`, and moves `pos` past it.
+fn decode_entry(data: &[u8], pos: &mut usize, end: usize) -> Option<Entry> {
+    let data = &data[..end];
+    let shared = usize::try_from(get_varint(data, pos)?).ok()?;
+    let unshared = usize::try_from(get_varint(data, pos)?).ok()?;
+    let value_len = usize::try_from(get_varint(data, pos)?).ok()?;
+
+    let suffix = *pos..pos.checked_add(unshared)?;
+    let value = suffix.end..suffix.end.checked_add(value_len)?;
+    if value.end > end {
+        return None;
+    }
+    *pos = value.end;
+    Some(Entry {
+        shared,
+        suffix,
+        value,
+    })
+}
+
+/// Decodes an entry of a block checked by [`validate_entries`].
+fn decode_validated(data: &[u8], pos: &mut usize, end: usize) -> Entry {
+    decode_entry(data, pos, end).expect("entries are validated when the table is opened")
+}
+
+fn read_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(data[at..at + 8].try_into().unwrap())
+}
@@ -0,0 +1,242 @@
+//! Streaming sorter with owned key storage.
+//!
+//! Sorting keys that arrive one by one (e.g. from an iterator or a decoder) with
+//! [`orasort`](crate::orasort) means collecting them into a `Vec<Vec<u8>>` first, which
+//! costs one allocation per key. [`StreamSorter`] instead copies every pushed key into a
+//! single contiguous byte buffer and records where it ends, so pushing amortizes to no
+//! allocations at all. Each key can carry a payload that is returned alongside it.
+//!
+//! All buffers, including the sort's own pointer and scratch buffers (see
+//! [`Sorter`]), are retained between rounds, and the peak heap usage is
+//! reported by [`StreamSorter::high_water_bytes`].
+
+use crate::core::KeyAccessor;
+use crate::permutation::permute_in_place;
+use crate::sorter::Sorter;
+use alloc::vec::{self, Vec};
+use core::iter::FusedIterator;
+use core::slice;
+
+/// Sorts keys pushed one at a time, each with an optional payload.
+///
+/// # Examples
+///
+/// ```
+/// use orasort::StreamSorter;
+///
+/// let mut sorter = StreamSorter::new();
+/// for (line, text) in ["pear", "apple", "fig"].into_iter().enumerate() {
+///     sorter.push_with_payload(text.as_bytes(), line);
+/// }
+///
+/// let sorted: Vec<(&[u8], usize)> = sorter.finish().collect();
+/// assert_eq!(sorted, vec![(&b"apple"[..], 1), (&b"fig"[..], 2), (&b"pear"[..], 0)]);
+///
+/// // Buffers are reused for the next round.
+/// sorter.push_with_payload(b"kiwi", 0);
+/// assert_eq!(sorter.len(), 1);
+/// ```
+#[derive(Debug)]
+pub struct StreamSorter<P = ()> {
+    /// All keys, back to back.
+    bytes: Vec<u8>,
+    /// Exclusive end of every key in `bytes`.
+    ends: Vec<usize>,
+    payloads: Vec<P>,
+    sorter: Sorter,
+    high_water: usize,
+}
+
+impl<P> Default for StreamSorter<P> {
+    fn default() -> Self {
+        Self {
+            bytes: Vec::new(),
+            ends: Vec::new(),
+            payloads: Vec::new(),
+            sorter: Sorter::new(),
+            high_water: 0,
+        }
+    }
+}
+
+impl<P> StreamSorter<P> {
+    /// Creates an empty stream sorter.
+    ///
+    /// Without payloads, name the type as `StreamSorter` (i.e. `StreamSorter<()>`) and use
+    /// [`push`](StreamSorter::push).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a stream sorter with room for `keys` keys of `bytes` total length.
+    pub fn with_capacity(keys: usize, bytes: usize) -> Self {
+        let mut sorter = Self::new();
+        sorter.bytes.reserve(bytes);
+        sorter.ends.reserve(keys);
+        sorter.payloads.reserve(keys);
+        sorter.sorter.reserve(keys);
+        sorter.record_memory();
+        sorter
+    }
+
+    /// Returns the number of keys pushed since the last [`finish`](Self::finish).
+    pub fn len(&self) -> usize {
+        self.payloads.len()
+    }
+
+    /// Returns `true` if no keys were pushed since the last [`finish`](Self::finish).
+    pub fn is_empty(&self) -> bool {
+        self.payloads.is_empty()
+    }
+
+    /// Copies `key` into the sorter's buffer, together with its payload.
+    pub fn push_with_payload(&mut self, key: &[u8], payload: P) {
+        // Keys of the previous round stay in place until the first push of the next one,
+        // so that the iterator returned by `finish` can borrow them.
+        if self.ends.len() != self.payloads.len() {
+            self.bytes.clear();
+            self.ends.clear();
+        }
+
+        let grows = self.bytes.capacity() - self.bytes.len() < key.len()
+            || self.ends.len() == self.ends.capacity()
+            || self.payloads.len() == self.payloads.capacity();
+
+        self.bytes.extend_from_slice(key);
+        self.ends.push(self.bytes.len());
+        self.payloads.push(payload);
+
+        if grows {
+            self.record_memory();
+        }
+    }
+
+    /// Sorts the pushed keys and returns them in order, each with its payload.
+    ///
+    /// The sorter is empty afterwards and can be reused; its buffers keep their capacity.
+    /// Payloads not consumed from the iterator are dropped with it.
+    pub fn finish(&mut self) -> Sorted<'_, P> {
+        if self.ends.len() != self.payloads.len() {
+            self.bytes.clear();
+            self.ends.clear();
+        }
+
+        let keys = StoredKeys {
+            bytes: &self.bytes,
+            ends: &self.ends,
+        };
+        self.sorter.sort(&keys);
+        self.record_memory();
+
+        let order = self.sorter.output();
+        permute_in_place(order, self.payloads.as_mut_slice());
+
+        Sorted {
+            keys: StoredKeys {
+                bytes: &self.bytes,
+                ends: &self.ends,
+            },
+            order: order.iter(),
+            payloads: self.payloads.drain(..),
+        }
+    }
+
+    /// Returns the number of heap bytes currently held by the sorter.
+    pub fn memory_bytes(&self) -> usize {
+        self.bytes.capacity()
+            + self.ends.capacity() * size_of::<usize>()
+            + self.payloads.capacity() * size_of::<P>()
+            + self.sorter.heap_bytes()
+    }
+
+    /// Returns the largest [`memory_bytes`](Self::memory_bytes) observed so far, including
+    /// the sort buffers allocated by [`finish`](Self::finish).
+    pub fn high_water_bytes(&self) -> usize {
+        self.high_water
+    }
+
+    /// Releases all buffers. The high-water mark is kept.
+    pub fn shrink(&mut self) {
+        self.bytes = Vec::new();
+        self.ends = Vec::new();
+        self.payloads = Vec::new();
+        self.sorter.shrink();
+    }
+
+    fn record_memory(&mut self) {
+        self.high_water = self.high_water.max(self.memory_bytes());
+    }
+}
+
+impl StreamSorter<()> {
+    /// Copies `key` into the sorter's buffer.
+    pub fn push(&mut self, key: &[u8]) {
+        self.push_with_payload(key, ());
+    }
+}
+
+impl<'k, P> Extend<&'k [u8]> for StreamSorter<P>
+where
+    P: Default,
+{
+    /// Pushes every key with a default payload.
+    fn extend<I: IntoIterator<Item = &'k [u8]>>(&mut self, iter: I) {
+        iter.into_iter()
+            .for_each(|key| self.push_with_payload(key, P::default()));
+    }
+}
+
+/// Sorted keys and payloads, returned by [`StreamSorter::finish`].
+#[derive(Debug)]
+pub struct Sorted<'a, P> {
+    keys: StoredKeys<'a>,
+    order: slice::Iter<'a, usize>,
+    payloads: vec::Drain<'a, P>,
+}
+
+impl<'a, P> Iterator for Sorted<'a, P> {
+    type Item = (&'a [u8], P);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = *self.order.next()?;
+        let payload = self.payloads.next()?;
+        Some((self.keys.key(index), payload))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.order.size_hint()
+    }
+}
+
+impl<P> ExactSizeIterator for Sorted<'_, P> {}
+
+impl<P> FusedIterator for Sorted<'_, P> {}
+
+/// Keys stored back to back in one buffer.
+#[derive(Clone, Copy, Debug)]
+struct StoredKeys<'a> {
+    bytes: &'a [u8],
+    ends: &'a [usize],
+}
+
+impl<'a> StoredKeys<'a> {
+    #[inline(always)]
+    fn key(&self, index: usize) -> &'a [u8] {
+        let start = match index {
+            0 => 0,
+            _ => self.ends[index - 1],
+        };
+        &self.bytes[start..self.ends[index]]
+    }
+}
+
+impl KeyAccessor for StoredKeys<'_> {
+    #[inline(always)]
+    fn get_key(&self, index: usize) -> &[u8] {
+        self.key(index)
+    }
+
+    fn len(&self) -> usize {
+        self.ends.len()
+    }
+}
@@ -0,0 +1,107 @@
+use orasort::core::KeyAccessor;
+use orasort::{KeyArena, Permutation, orasort};
+use rand::prelude::*;
+
+mod common;
+use common::random_keys;
+
+const ALPHABET: &[u8] = b"\x00\x01\x02\x03";
+const MAX_LEN: usize = 20;
+
+/// Reference prefix load: copy through a zeroed buffer.
+fn reference_prefix(key: &[u8], offset: usize) -> u64 {
+    let mut buf = [0u8; 8];
+    if offset < key.len() {
+        let n = (key.len() - offset).min(8);
+        buf[..n].copy_from_slice(&key[offset..offset + n]);
+    }
+    u64::from_be_bytes(buf)
+}
+
+#[test]
+fn test_prefix_loads_match_reference() {
+    let mut rng = StdRng::seed_from_u64(39);
+    let keys = random_keys(&mut rng, 300, MAX_LEN, ALPHABET);
+    // Nonzero neighbours make sure bytes of the next key are masked off.
+    let keys: Vec<Vec<u8>> = keys
+        .into_iter()
+        .map(|k| k.into_iter().map(|b| b + 0xF0).collect())
+        .collect();
+
+    let narrow: KeyArena = keys.iter().collect();
+    let wide: KeyArena<u64> = keys.iter().collect();
+
+    assert_eq!(narrow.len(), keys.len());
+    assert_eq!(
+        narrow.key_bytes(),
+        keys.iter().map(|k| k.len()).sum::<usize>()
+    );
+
+    for (index, key) in keys.iter().enumerate() {
+        assert_eq!(narrow.get_key(index), key.as_slice());
+        assert_eq!(wide.get(index), Some(key.as_slice()));
+        for offset in 0..key.len() + 10 {
+            let expected = reference_prefix(key, offset);
+            assert_eq!(narrow.get_u64_prefix(index, offset), expected);
+            assert_eq!(wide.get_u64_prefix(index, offset), expected);
+        }
+    }
+    assert_eq!(narrow.get(keys.len()), None);
+    assert_eq!(narrow.get_u64_prefix(0, usize::MAX), 0);
+}
+
+#[test]
+fn test_sorted_rewrite() {
+    let mut rng = StdRng::seed_from_u64(390);
+
+    for count in [0, 1, 40, 5000] {
+        let keys = random_keys(&mut rng, count, MAX_LEN, ALPHABET);
+        let arena: KeyArena = keys.iter().collect();
+
+        assert_eq!(orasort(&arena), orasort(&keys));
+
+        let sorted = arena.sorted();
+        let mut expected = keys.clone();
+        expected.sort();
+        assert_eq!(sorted.iter().collect::<Vec<_>>(), expected);
+        assert!(orasort(&sorted).is_identity());
+        assert_eq!(sorted.key_bytes(), arena.key_bytes());
+    }
+}
+
+#[test]
+fn test_permuted_and_extend() {
+    let mut arena = KeyArena::<u32>::new();
+    assert!(arena.is_empty());
+    arena.push(b"");
+    arena.extend(["bb", "a"]);
+
+    let reversed = arena.permuted(&Permutation::try_from(vec![2, 1, 0]).unwrap());
+    assert_eq!(
+        reversed.iter().collect::<Vec<_>>(),
+        vec![&b"a"[..], b"bb", b""]
+    );
+    assert_eq!(reversed.get_u64_prefix(0, 0), u64::from(b'a') << 56);
+    assert_eq!(reversed.get_u64_prefix(2, 0), 0);
+}
+
+#[test]
+fn test_offset_conversions() {
+    use orasort::arena::ArenaOffset;
+
+    for value in [0, 1, u32::MAX as usize] {
+        assert_eq!(u32::from_usize(value).unwrap().to_usize(), value);
+        assert_eq!(u64::from_usize(value).unwrap().to_usize(), value);
+    }
+    #[cfg(target_pointer_width = "64")]
+    assert_eq!(u32::from_usize(u32::MAX as usize + 1), None);
+}
+
+#[test]
+#[cfg(not(target_pointer_width = "64"))]
+#[should_panic(expected = "exceeds usize")]
+fn test_wide_offset_is_not_truncated() {
+    use orasort::arena::ArenaOffset;
+
+    (1u64 << 32).to_usize();
+}
@@ -0,0 +1,226 @@
+use orasort::SortedBatches;
+use orasort::btree::{btree_map_from_unsorted, btree_set_from_unsorted};
+use rand::prelude::*;
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet};
+
+fn random_batch(rng: &mut StdRng, len: usize) -> Vec<String> {
+    (0..len)
+        .map(|_| format!("key-{:04}", rng.random_range(0..2000)))
+        .collect()
+}
+
+#[test]
+fn test_batches_stay_sorted_with_few_levels() {
+    let mut rng = StdRng::seed_from_u64(37);
+    let mut batches = SortedBatches::new();
+    let mut model = Vec::new();
+
+    for round in 0..200 {
+        let len = rng.random_range(0..50);
+        let batch = random_batch(&mut rng, len);
+        model.extend(batch.iter().cloned());
+        batches.insert_batch(batch);
+        if round % 17 == 0 {
+            batches.insert(format!("key-{:04}", round));
+            model.push(format!("key-{:04}", round));
+        }
+
+        // Level sizes roughly double from newest to oldest.
+        assert!(batches.level_count() <= (model.len() + 1).ilog2() as usize + 1);
+    }
+    model.sort();
+
+    assert_eq!(batches.len(), model.len());
+    let iterated: Vec<&String> = batches.iter().collect();
+    assert_eq!(iterated, model.iter().collect::<Vec<_>>());
+    assert_eq!(batches.iter().len(), model.len());
+
+    for probe in ["key-0000", "key-0123", "key-1999", "key-2000", "key-"] {
+        let expected = model.iter().filter(|k| k.as_str() == probe).count();
+        assert_eq!(batches.count(probe.as_bytes()), expected, "{}", probe);
+        assert_eq!(batches.contains(probe.as_bytes()), expected > 0);
+        assert_eq!(batches.get(probe.as_bytes()).is_some(), expected > 0);
+    }
+
+    let prefixed: Vec<&String> = batches.prefix_iter(b"key-01").collect();
+    let expected: Vec<&String> = model.iter().filter(|k| k.starts_with("key-01")).collect();
+    assert_eq!(prefixed, expected);
+
+    assert_eq!(batches.into_sorted_vec(), model);
+}
+
+#[test]
+fn test_lookups_cover_merges_in_progress() {
+    let mut rng = StdRng::seed_from_u64(3701);
+    let mut batches = SortedBatches::new();
+    let mut model = Vec::new();
+
+    for round in 0..300 {
+        let len = if round % 50 == 49 {
+            400
+        } else {
+            rng.random_range(1..8)
+        };
+        let batch = random_batch(&mut rng, len);
+        model.extend(batch.iter().cloned());
+        batches.insert_batch(batch);
+        model.sort();
+
+        let iterated: Vec<&String> = batches.iter().collect();
+        assert_eq!(
+            iterated,
+            model.iter().collect::<Vec<_>>(),
+            "round {}",
+            round
+        );
+        let probe = &model[rng.random_range(0..model.len())];
+        let expected = model.iter().filter(|k| *k == probe).count();
+        assert_eq!(batches.count(probe.as_bytes()), expected, "round {}", round);
+        assert!(batches.contains(probe.as_bytes()));
+        let prefix = &probe.as_bytes()[..7];
+        let prefixed: Vec<&String> = batches.prefix_iter(prefix).collect();
+        let expected: Vec<&String> = model
+            .iter()
+            .filter(|k| k.as_bytes().starts_with(prefix))
+            .collect();
+        assert_eq!(prefixed, expected, "round {}", round);
+    }
+}
+
+thread_local! {
+    static KEY_LOADS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts how often its bytes are loaded, which the collection does for every full key
+/// comparison.
+struct Counted(String);
+
+impl AsRef<[u8]> for Counted {
+    fn as_ref(&self) -> &[u8] {
+        KEY_LOADS.with(|loads| loads.set(loads.get() + 1));
+        self.0.as_bytes()
+    }
+}
+
+#[test]
+fn test_inserts_merge_a_bounded_number_of_keys() {
+    // The shared prefix makes every comparison load both keys.
+    let mut rng = StdRng::seed_from_u64(3702);
+    let mut batches = SortedBatches::new();
+    let mut most_loads = 0;
+    for _ in 0..4096 {
+        let key = Counted(format!(
+            "shared prefix {:08}",
+            rng.random_range(0..100_000_000)
+        ));
+        KEY_LOADS.with(|loads| loads.set(0));
+        batches.insert(key);
+        most_loads = most_loads.max(KEY_LOADS.with(Cell::get));
+    }
+    // Merging the largest levels synchronously loads thousands of keys in one insert.
+    assert!(most_loads < 400, "{} key loads in one insert", most_loads);
+
+    batches.compact();
+    assert_eq!(batches.level_count(), 1);
+    let keys: Vec<&str> = batches.iter().map(|key| key.0.as_str()).collect();
+    assert!(keys.is_sorted());
+    assert_eq!(keys.len(), 4096);
+}
+
+#[test]
+fn test_short_keys_and_zero_bytes() {
+    // Keys around the 8 cached prefix bytes, with zero bytes that look like padding.
+    let mut rng = StdRng::seed_from_u64(3700);
+    let mut batches = SortedBatches::new();
+    let mut model: Vec<Vec<u8>> = Vec::new();
+    for _ in 0..100 {
+        let batch: Vec<Vec<u8>> = (0..rng.random_range(1..30))
+            .map(|_| {
+                let len = rng.random_range(0..12);
+                (0..len)
+                    .map(|_| *[0, b'a', b'b'].choose(&mut rng).unwrap())
+                    .collect()
+            })
+            .collect();
+        model.extend(batch.iter().cloned());
+        batches.insert_batch(batch);
+    }
+    model.sort();
+
+    assert_eq!(
+        batches.iter().collect::<Vec<_>>(),
+        model.iter().collect::<Vec<_>>()
+    );
+    for probe in [
+        &b""[..],
+        b"\0",
+        b"a",
+        b"a\0",
+        b"ab\0\0\0\0\0\0",
+        b"ab\0\0\0\0\0\0\0",
+    ] {
+        let equal = model.iter().filter(|k| k.as_slice() == probe).count();
+        assert_eq!(batches.count(probe), equal, "{:?}", probe);
+
+        let prefixed: Vec<&Vec<u8>> = batches.prefix_iter(probe).collect();
+        let expected: Vec<&Vec<u8>> = model.iter().filter(|k| k.starts_with(probe)).collect();
+        assert_eq!(prefixed, expected, "{:?}", probe);
+    }
+}
+
+#[test]
+fn test_equal_keys_iterate_in_batch_order() {
+    // Payload after the key bytes distinguishes batches but does not take part in ordering.
+    struct Tagged(&'static str, usize);
+    impl AsRef<[u8]> for Tagged {
+        fn as_ref(&self) -> &[u8] {
+            self.0.as_bytes()
+        }
+    }
+
+    let mut batches = SortedBatches::new();
+    for batch in 0..10 {
+        batches.insert_batch(vec![Tagged("b", batch), Tagged("a", batch)]);
+    }
+    batches.compact();
+    assert_eq!(batches.level_count(), 1);
+
+    let tags: Vec<(&str, usize)> = batches.iter().map(|t| (t.0, t.1)).collect();
+    let expected: Vec<(&str, usize)> = (0..10)
+        .map(|b| ("a", b))
+        .chain((0..10).map(|b| ("b", b)))
+        .collect();
+    assert_eq!(tags, expected);
+    assert_eq!(batches.get(b"a").map(|t| t.1), Some(9));
+}
+
+#[test]
+fn test_collect_and_empty() {
+    let batches: SortedBatches<&str> = ["c", "a", "b"].into_iter().collect();
+    assert_eq!(
+        batches.iter().copied().collect::<Vec<_>>(),
+        vec!["a", "b", "c"]
+    );
+
+    let mut empty = SortedBatches::<Vec<u8>>::new();
+    empty.insert_batch(Vec::new());
+    assert!(empty.is_empty());
+    assert_eq!(empty.level_count(), 0);
+    assert_eq!(empty.iter().next(), None);
+    assert!(!empty.contains(b""));
+}
+
+#[test]
+fn test_btree_bulk_build() {
+    let mut rng = StdRng::seed_from_u64(370);
+    let keys = random_batch(&mut rng, 5000);
+
+    let set = btree_set_from_unsorted(keys.clone());
+    assert_eq!(set, keys.iter().cloned().collect::<BTreeSet<_>>());
+
+    let entries: Vec<(String, usize)> = keys.into_iter().enumerate().map(|(i, k)| (k, i)).collect();
+    let map = btree_map_from_unsorted(entries.clone());
+    // `BTreeMap::from_iter` keeps the last value of duplicate keys.
+    assert_eq!(map, entries.into_iter().collect::<BTreeMap<_, _>>());
+}
@@ -0,0 +1,117 @@
+use orasort::prelude::*;
+use orasort::{OrasortError, orasort_from_indices_checked, orasort_slice, orasort_slice_checked};
+use rand::prelude::*;
+
+mod common;
+use common::random_keys;
+
+/// Reference order for sorting with `offset` skipped bytes: suffix bytes, then total length.
+fn model_key(key: &[u8], offset: usize) -> (Vec<u8>, usize) {
+    (key.get(offset..).unwrap_or(&[]).to_vec(), key.len())
+}
+
+// A small alphabet including 0 to exercise ties and zero padding.
+const ALPHABET: &[u8] = b"\x00\x01\x02";
+const MAX_LEN: usize = 14;
+
+fn assert_model_order(keys: &[Vec<u8>], sorted: &[usize], offset: usize) {
+    let actual: Vec<_> = sorted
+        .iter()
+        .map(|&i| model_key(&keys[i], offset))
+        .collect();
+    let mut expected = actual.clone();
+    expected.sort();
+    assert_eq!(
+        actual,
+        expected,
+        "offset {} with {} items",
+        offset,
+        sorted.len()
+    );
+}
+
+#[test]
+fn test_small_and_pointer_paths_agree_on_unequal_prefixes() {
+    let mut rng = StdRng::seed_from_u64(33);
+
+    // Sizes on both sides of the small-input threshold and past the radix threshold.
+    for &count in &[2, 17, 32, 33, 200, 3000] {
+        let keys = random_keys(&mut rng, count, MAX_LEN, ALPHABET);
+        for offset in 0..6 {
+            let mut indices: Vec<usize> = (0..count).collect();
+            indices.shuffle(&mut rng);
+
+            let sorted = orasort_from_indices(&keys, indices.clone(), offset);
+            assert_model_order(&keys, &sorted, offset);
+
+            orasort_slice(&keys, &mut indices, offset);
+            assert_model_order(&keys, &indices, offset);
+        }
+    }
+}
+
+#[test]
+fn test_checked_matches_unchecked() {
+    let data = vec!["k:pear", "k:fig", "k:apple", "k:plum", "k:fig"];
+    let indices = vec![4, 0, 3, 1, 2];
+
+    let checked = orasort_from_indices_checked(&data, indices.clone(), 2).unwrap();
+    assert_eq!(checked, orasort_from_indices(&data, indices.clone(), 2));
+
+    let mut slice = indices.clone();
+    orasort_slice_checked(&data, &mut slice, 2).unwrap();
+    assert_eq!(slice, checked);
+}
+
+#[test]
+fn test_checked_rejects_out_of_range() {
+    let data = vec!["a", "b", "c"];
+    assert_eq!(
+        orasort_from_indices_checked(&data, vec![0, 7, 1], 0),
+        Err(OrasortError::IndexOutOfRange { index: 7, len: 3 })
+    );
+
+    let mut indices = vec![2, 3];
+    assert_eq!(
+        orasort_slice_checked(&data, &mut indices, 0),
+        Err(OrasortError::IndexOutOfRange { index: 3, len: 3 })
+    );
+    assert_eq!(indices, vec![2, 3]);
+}
+
+#[test]
+fn test_checked_rejects_duplicates() {
+    let data: Vec<String> = (0..200).map(|i| format!("{:05}", i)).collect();
+    let mut indices: Vec<usize> = (0..200).rev().collect();
+    indices.push(130);
+
+    assert_eq!(
+        orasort_slice_checked(&data, &mut indices, 0),
+        Err(OrasortError::DuplicateIndex { index: 130 })
+    );
+    assert_eq!(indices[0], 199);
+}
+
+#[test]
+fn test_checked_rejects_offset_beyond_keys() {
+    let data = vec!["abc", "de", "fghi"];
+    assert_eq!(
+        orasort_from_indices_checked(&data, vec![0, 1], 3),
+        Err(OrasortError::OffsetBeyondKeys {
+            offset: 3,
+            max_key_len: 3
+        })
+    );
+
+    // One selected key reaching past the offset is enough.
+    assert_eq!(
+        orasort_from_indices_checked(&data, vec![0, 1, 2], 3),
+        Ok(vec![1, 0, 2])
+    );
+    // Offset 0 never fails, even for empty keys.
+    assert_eq!(
+        orasort_from_indices_checked(&vec!["b", ""], vec![0, 1], 0),
+        Ok(vec![1, 0])
+    );
+    assert_eq!(orasort_from_indices_checked(&data, vec![], 9), Ok(vec![]));
+}
@@ -0,0 +1,194 @@
+#![cfg(feature = "cli")]
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+fn orasort(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_orasort"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start orasort");
+    // Some invocations fail before reading standard input and close it.
+    if let Err(err) = child.stdin.take().unwrap().write_all(stdin) {
+        assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+    }
+    child.wait_with_output().unwrap()
+}
+
+fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("orasort-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn test_sorts_stdin_bytewise() {
+    let out = orasort(&[], b"banana\nApple\napple\n\xc3\xa9clair\nBanana");
+    assert!(out.status.success());
+    assert_eq!(out.stdout, b"Apple\nBanana\napple\nbanana\n\xc3\xa9clair\n");
+}
+
+#[test]
+fn test_reverse_unique_and_zero_terminated() {
+    let out = orasort(&["-ru"], b"b\na\nb\nc\na\n");
+    assert_eq!(out.stdout, b"c\nb\na\n");
+
+    let out = orasort(&["-z", "--unique"], b"b\0a\nx\0b\0");
+    assert_eq!(out.stdout, b"a\nx\0b\0");
+}
+
+#[test]
+fn test_key_selection() {
+    let input = b"b\t2\tx\na\t10\ty\nc\t2\tX\na\t2\tz\n";
+
+    let out = orasort(&["-t", "\t", "-k2,2", "-k1,1r"], input);
+    assert!(out.status.success());
+    assert_eq!(out.stdout, b"a\t10\ty\nc\t2\tX\nb\t2\tx\na\t2\tz\n");
+
+    // -u keeps the first line of every run of equal keys.
+    let out = orasort(&["-u", "-fk3,3", "-t\t"], input);
+    assert_eq!(out.stdout, b"b\t2\tx\na\t10\ty\na\t2\tz\n");
+
+    let out = orasort(&["-k0"], input);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn test_check_and_merge() {
+    let out = orasort(&["-c"], b"a\nb\nb\n");
+    assert!(out.status.success());
+
+    let out = orasort(&["-cu"], b"a\nb\nb\n");
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(out.stderr, b"orasort: -:3: disorder: b\n");
+
+    let first = temp_file("merge-1.txt", b"a\nc\ne\n");
+    let second = temp_file("merge-2.txt", b"b\nc\nd");
+    let args = ["-m", first.to_str().unwrap(), second.to_str().unwrap()];
+    let out = orasort(&args, b"");
+    assert!(out.status.success());
+    assert_eq!(out.stdout, b"a\nb\nc\nc\nd\ne\n");
+
+    let out = orasort(&["-mu", first.to_str().unwrap(), "-"], b"b\nc\n");
+    assert_eq!(out.stdout, b"a\nb\nc\ne\n");
+
+    let out = orasort(&["-cm"], b"");
+    assert_eq!(out.status.code(), Some(2));
+
+    // Standard input cannot be merged with itself.
+    let out = orasort(&["-m", "-", "-"], b"a\n");
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("more than once"));
+}
+
+#[test]
+fn test_files_parallel_and_output() {
+    let lines: Vec<String> = (0..200_000)
+        .map(|i| format!("{}", (i * 7919) % 200_000))
+        .collect();
+    let first = temp_file("first.txt", lines[..150_000].join("\n").as_bytes());
+    let second = temp_file("second.txt", lines[150_000..].join("\n").as_bytes());
+
+    let mut expected: Vec<&String> = lines.iter().collect();
+    expected.sort();
+    let expected: String = expected.iter().map(|l| format!("{}\n", l)).collect();
+
+    let out = orasort(
+        &[
+            first.to_str().unwrap(),
+            "--parallel=4",
+            "-",
+            second.to_str().unwrap(),
+        ],
+        b"",
+    );
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8(out.stdout).unwrap(), expected);
+
+    // Sorting a file onto itself reads it before truncating it.
+    let out = orasort(
+        &["-o", first.to_str().unwrap(), first.to_str().unwrap()],
+        b"",
+    );
+    assert!(out.status.success());
+    let sorted = std::fs::read_to_string(&first).unwrap();
+    assert!(
+        sorted
+            .lines()
+            .collect::<Vec<_>>()
+            .windows(2)
+            .all(|w| w[0] <= w[1])
+    );
+    assert_eq!(sorted.lines().count(), 150_000);
+}
+
+#[test]
+fn test_errors_exit_with_status_2() {
+    let out = orasort(&["--no-such-option"], b"");
+    assert_eq!(out.status.code(), Some(2));
+
+    let out = orasort(&["/nonexistent/orasort-input"], b"");
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("/nonexistent/orasort-input"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_output_is_written_in_place() {
+    use std::os::unix::fs::{PermissionsExt, symlink};
+
+    let input = temp_file("in-place-input.txt", b"b\na\n");
+    let target = temp_file("in-place-target.txt", b"old contents that are longer\n");
+    std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o640)).unwrap();
+    let link = target.with_file_name("in-place-link.txt");
+    let _ = std::fs::remove_file(&link);
+    symlink(&target, &link).unwrap();
+
+    // Writing through a symlink updates its target and keeps the link and the mode.
+    let out = orasort(
+        &["-o", link.to_str().unwrap(), input.to_str().unwrap()],
+        b"",
+    );
+    assert!(out.status.success());
+    assert!(
+        std::fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink()
+    );
+    assert_eq!(std::fs::read(&target).unwrap(), b"a\nb\n");
+    let mode = std::fs::metadata(&target).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o640);
+
+    // Devices stay devices.
+    let out = orasort(&["-o", "/dev/null", input.to_str().unwrap()], b"");
+    assert!(out.status.success());
+    assert!(!std::fs::metadata("/dev/null").unwrap().is_file());
+
+    // Merging a file onto itself, through a symlink, reads it before truncating it.
+    let other = temp_file("in-place-other.txt", b"a0\nc\n");
+    let args = [
+        "-m",
+        "-o",
+        target.to_str().unwrap(),
+        link.to_str().unwrap(),
+        other.to_str().unwrap(),
+    ];
+    let out = orasort(&args, b"");
+    assert!(out.status.success());
+    assert_eq!(std::fs::read(&target).unwrap(), b"a\na0\nb\nc\n");
+
+    // Sorting onto a mapped input through a symlink, too.
+    let out = orasort(
+        &["-r", "-o", target.to_str().unwrap(), link.to_str().unwrap()],
+        b"",
+    );
+    assert!(out.status.success());
+    assert_eq!(std::fs::read(&target).unwrap(), b"c\nb\na0\na\n");
+}
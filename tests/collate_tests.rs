@@ -0,0 +1,134 @@
+use orasort::collate::{AsciiCaseInsensitive, ByteCollator, Collator, orasort_collated};
+use orasort::prelude::*;
+use rand::Rng;
+use std::cell::Cell;
+
+#[test]
+fn test_byte_collator_matches_orasort() {
+    let mut rng = rand::rng();
+    let input: Vec<Vec<u8>> = (0..5_000)
+        .map(|_| {
+            let len = rng.random_range(0..40);
+            // Small alphabet including 0 to exercise padding ambiguity and long ties.
+            (0..len).map(|_| rng.random_range(0..3u8)).collect()
+        })
+        .collect();
+
+    let indices = orasort_collated(&input, &ByteCollator);
+
+    let mut expected = input.clone();
+    expected.sort();
+
+    let actual: Vec<Vec<u8>> = indices.iter().map(|&i| input[i].clone()).collect();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_ascii_case_insensitive() {
+    let mut data = vec!["delta", "Bravo", "alpha", "Charlie", "ALPHA-2"];
+    orasort_collated_mut(&mut data, &AsciiCaseInsensitive);
+    assert_eq!(data, vec!["alpha", "ALPHA-2", "Bravo", "Charlie", "delta"]);
+}
+
+#[test]
+fn test_long_collation_keys_tie_beyond_cache() {
+    // Keys identical for far longer than one collation chunk.
+    let prefix = "x".repeat(100);
+    let input = vec![
+        format!("{}C", prefix),
+        format!("{}a", prefix),
+        prefix.clone(),
+        format!("{}B", prefix),
+    ];
+
+    let indices = orasort_collated(&input, &AsciiCaseInsensitive);
+    assert_eq!(indices, vec![2, 1, 3, 0]);
+}
+
+/// Reverses byte order and counts how many times collation bytes were requested
+/// beyond the cached prefix, and how many were generated in total.
+struct CountingReverse {
+    tail_calls: Cell<usize>,
+    generated: Cell<usize>,
+}
+
+impl CountingReverse {
+    fn new() -> Self {
+        Self {
+            tail_calls: Cell::new(0),
+            generated: Cell::new(0),
+        }
+    }
+}
+
+impl Collator for CountingReverse {
+    type State = usize;
+
+    fn collation_bytes(&self, key: &[u8], pos: &mut usize, out: &mut [u8]) -> usize {
+        if out.len() > 8 {
+            self.tail_calls.set(self.tail_calls.get() + 1);
+        }
+        let tail = key.get(*pos..).unwrap_or(&[]);
+        let n = tail.len().min(out.len());
+        out.iter_mut()
+            .zip(&tail[..n])
+            .for_each(|(dst, src)| *dst = !src);
+        *pos += n;
+        self.generated.set(self.generated.get() + n);
+        n
+    }
+}
+
+#[test]
+fn test_distinct_prefixes_only_use_cache() {
+    // All keys differ within the first 8 bytes, so no further collation bytes are needed.
+    let input: Vec<String> = (0..2_000).map(|i| format!("{:08}", i)).collect();
+    let collator = CountingReverse::new();
+
+    let indices = orasort_collated(&input, &collator);
+
+    let expected: Vec<usize> = (0..2_000).rev().collect();
+    assert_eq!(indices, expected);
+    assert_eq!(collator.tail_calls.get(), 0);
+}
+
+#[test]
+fn test_long_ties_generate_collation_keys_once() {
+    // Ties far beyond the cache resume where they stopped instead of starting over.
+    let shared = vec![b'x'; 100_000];
+    let input: Vec<Vec<u8>> = (0..4u8)
+        .rev()
+        .map(|last| [&shared[..], &[last]].concat())
+        .collect();
+    let collator = CountingReverse::new();
+
+    let indices = orasort_collated(&input, &collator);
+
+    assert_eq!(indices, vec![0, 1, 2, 3]);
+    let total: usize = input.iter().map(|key| key.len()).sum();
+    assert!(collator.generated.get() < 20 * total);
+}
+
+#[test]
+fn test_long_shared_prefix_generates_each_key_a_bounded_number_of_times() {
+    // Enough keys for radix passes, which walk the shared prefix eight bytes at a time.
+    let mut rng = rand::rng();
+    let shared = vec![b'x'; 2_000];
+    let input: Vec<Vec<u8>> = (0..5_000)
+        .map(|_| {
+            let suffix: [u8; 8] = rng.random();
+            [&shared[..], &suffix].concat()
+        })
+        .collect();
+    let collator = CountingReverse::new();
+
+    let indices = orasort_collated(&input, &collator);
+
+    let mut expected: Vec<Vec<u8>> = input.clone();
+    expected.sort_by(|a, b| b.cmp(a));
+    let actual: Vec<Vec<u8>> = indices.iter().map(|&i| input[i].clone()).collect();
+    assert_eq!(actual, expected);
+
+    let total: usize = input.iter().map(|key| key.len()).sum();
+    assert!(collator.generated.get() < 4 * total);
+}
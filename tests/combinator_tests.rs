@@ -0,0 +1,152 @@
+use orasort::{Concat, ConcatMany, KeyAccessor, KeyArena, Subset, orasort};
+use rand::prelude::*;
+use std::cell::Cell;
+
+mod common;
+use common::random_keys;
+
+const ALPHABET: &[u8] = b"abc";
+const MAX_LEN: usize = 20;
+
+fn arena(keys: &[Vec<u8>]) -> KeyArena {
+    let mut arena = KeyArena::new();
+    for key in keys {
+        arena.push(key);
+    }
+    arena
+}
+
+/// Counts prefix loads, to see that adaptors forward them instead of going through
+/// `get_key`.
+struct Counting<'a> {
+    keys: &'a [Vec<u8>],
+    prefixes: Cell<usize>,
+}
+
+impl KeyAccessor for Counting<'_> {
+    fn get_key(&self, index: usize) -> &[u8] {
+        &self.keys[index]
+    }
+
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn get_u64_prefix(&self, index: usize, offset: usize) -> u64 {
+        self.prefixes.set(self.prefixes.get() + 1);
+        let mut bytes = [0; 8];
+        let key = self.keys[index].get(offset..).unwrap_or_default();
+        let len = key.len().min(8);
+        bytes[..len].copy_from_slice(&key[..len]);
+        u64::from_be_bytes(bytes)
+    }
+}
+
+#[test]
+fn test_concat_matches_copied_keys() {
+    let mut rng = StdRng::seed_from_u64(50);
+    let first = random_keys(&mut rng, 700, MAX_LEN, ALPHABET);
+    let second = random_keys(&mut rng, 1300, MAX_LEN, ALPHABET);
+    let (first_arena, second_arena) = (arena(&first), arena(&second));
+    let both = Concat::new(&first_arena, &second_arena);
+
+    let copied: Vec<Vec<u8>> = first.iter().chain(&second).cloned().collect();
+    assert_eq!(both.len(), copied.len());
+    for index in 0..copied.len() {
+        assert_eq!(both.get_key(index), &copied[index][..]);
+        for offset in [0, 5, 8, 19] {
+            assert_eq!(
+                both.get_u64_prefix(index, offset),
+                copied.get_u64_prefix(index, offset)
+            );
+        }
+    }
+
+    let order = orasort(&both);
+    assert_eq!(order, orasort(&copied));
+
+    let provenance = both.provenance(&order);
+    for (&index, &(source, local)) in order.iter().zip(&provenance) {
+        let key = if source == 0 {
+            &first[local]
+        } else {
+            &second[local]
+        };
+        assert_eq!(key, &copied[index]);
+    }
+}
+
+#[test]
+fn test_concat_many_with_empty_sources() {
+    let mut rng = StdRng::seed_from_u64(500);
+    let sources: Vec<Vec<Vec<u8>>> = [0, 300, 0, 0, 1, 900, 0]
+        .iter()
+        .map(|&count| random_keys(&mut rng, count, MAX_LEN, ALPHABET))
+        .collect();
+    let all = ConcatMany::new(sources.iter().collect());
+
+    let copied: Vec<Vec<u8>> = sources.concat();
+    assert_eq!(all.len(), copied.len());
+    for (index, key) in copied.iter().enumerate() {
+        assert_eq!(all.get_key(index), &key[..]);
+        let (source, local) = all.locate(index);
+        assert_eq!(&sources[source][local], key);
+    }
+
+    let order = orasort(&all);
+    assert_eq!(order, orasort(&copied));
+    for (&index, (source, local)) in order.iter().zip(all.provenance(&order)) {
+        assert_eq!(sources[source][local], copied[index]);
+    }
+
+    let none: ConcatMany<&Vec<Vec<u8>>> = ConcatMany::new(Vec::new());
+    assert!(none.is_empty());
+    assert!(orasort(&none).is_empty());
+}
+
+#[test]
+fn test_subset() {
+    let mut rng = StdRng::seed_from_u64(5000);
+    let keys = random_keys(&mut rng, 2000, MAX_LEN, ALPHABET);
+    let indices: Vec<usize> = (0..1500).map(|_| rng.random_range(0..keys.len())).collect();
+    let subset = Subset::new(&keys, indices.clone());
+
+    let selected: Vec<&Vec<u8>> = indices.iter().map(|&i| &keys[i]).collect();
+    assert_eq!(subset.len(), selected.len());
+    assert_eq!(orasort(&subset), orasort(&selected));
+
+    let short = Subset::filter(&keys, |key| key.len() < 5);
+    assert!(short.indices().iter().all(|&i| keys[i].len() < 5));
+    assert_eq!(
+        short.indices().len(),
+        keys.iter().filter(|key| key.len() < 5).count()
+    );
+}
+
+#[test]
+#[should_panic(expected = "index 3 out of range for length 3")]
+fn test_subset_index_out_of_range() {
+    Subset::new(vec!["a", "b", "c"], vec![0, 3]);
+}
+
+#[test]
+fn test_prefix_loads_are_forwarded() {
+    let mut rng = StdRng::seed_from_u64(50000);
+    let keys = random_keys(&mut rng, 500, MAX_LEN, ALPHABET);
+    let counting = |keys| Counting {
+        keys,
+        prefixes: Cell::new(0),
+    };
+
+    let (a, b) = (counting(&keys[..200]), counting(&keys[200..]));
+    orasort(&Concat::new(&a, &b));
+    assert!(a.prefixes.get() >= 200 && b.prefixes.get() >= 300);
+
+    let (a, b) = (counting(&keys[..200]), counting(&keys[200..]));
+    orasort(&ConcatMany::new(vec![&a, &b]));
+    assert!(a.prefixes.get() >= 200 && b.prefixes.get() >= 300);
+
+    let a = counting(&keys);
+    orasort(&Subset::new(&a, (0..500).step_by(2).collect()));
+    assert!(a.prefixes.get() >= 250);
+}
@@ -0,0 +1,74 @@
+//! Helpers shared by the integration tests. Every test binary uses its own subset of them.
+#![allow(dead_code)]
+
+use rand::Rng;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Counts allocations made by the current thread.
+///
+/// Install it in a test binary with
+/// `#[global_allocator] static GLOBAL: common::CountingAlloc = common::CountingAlloc;`
+/// and read the count with [`allocations`].
+pub struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+/// Returns the number of allocations the current thread made through [`CountingAlloc`].
+pub fn allocations() -> usize {
+    ALLOCATIONS.with(|count| count.get())
+}
+
+/// Every byte value, for keys of random bytes.
+pub const ANY_BYTE: [u8; 256] = {
+    let mut bytes = [0; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        bytes[byte] = byte as u8;
+        byte += 1;
+    }
+    bytes
+};
+
+/// Returns `count` keys shorter than `max_len` bytes, drawn from `alphabet`.
+pub fn random_keys(
+    rng: &mut impl Rng,
+    count: usize,
+    max_len: usize,
+    alphabet: &[u8],
+) -> Vec<Vec<u8>> {
+    (0..count)
+        .map(|_| {
+            let len = rng.random_range(0..max_len);
+            (0..len)
+                .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+                .collect()
+        })
+        .collect()
+}
+
+/// Asserts that `indices` visits `input` in sorted order.
+pub fn assert_sorted(input: &[Vec<u8>], indices: &[usize]) {
+    let mut expected = input.to_vec();
+    expected.sort();
+    let actual: Vec<Vec<u8>> = indices.iter().map(|&i| input[i].clone()).collect();
+    assert_eq!(actual, expected);
+}
@@ -0,0 +1,163 @@
+use orasort::csv::CsvIndex;
+use orasort::{CsvError, KeyAccessor, Permutation, orasort};
+use rand::Rng;
+
+/// Quotes a field if it needs it, per RFC 4180.
+fn encode_field(field: &[u8]) -> Vec<u8> {
+    if !field.iter().any(|b| b",\"\r\n".contains(b)) {
+        return field.to_vec();
+    }
+    let mut out = vec![b'"'];
+    for &b in field {
+        if b == b'"' {
+            out.push(b'"');
+        }
+        out.push(b);
+    }
+    out.push(b'"');
+    out
+}
+
+fn encode_record(fields: &[Vec<u8>]) -> Vec<u8> {
+    let encoded: Vec<Vec<u8>> = fields.iter().map(|f| encode_field(f)).collect();
+    encoded.join(&b',')
+}
+
+#[test]
+fn test_quoted_fields() {
+    let data = b"id,name,note\n\
+        3,\"Doe, Jane\",\"said \"\"hi\"\"\"\n\
+        1,Zed,\"two\nlines\"\n\
+        2,\"\"\"Quoted\"\"\",plain\n";
+    let index = CsvIndex::new(data).unwrap();
+
+    assert_eq!(index.len(), 3);
+    assert_eq!(index.header(), Some(&b"id,name,note"[..]));
+    assert_eq!(index.record(1), b"1,Zed,\"two\nlines\"");
+
+    let names = index.column(1);
+    assert_eq!(names.get_key(0), b"Doe, Jane");
+    assert_eq!(names.get_key(2), b"\"Quoted\"");
+    assert_eq!(index.column(2).get_key(0), b"said \"hi\"");
+
+    assert_eq!(
+        index.to_sorted_vec(&index.sort_by(1)),
+        b"id,name,note\n\
+        2,\"\"\"Quoted\"\"\",plain\n\
+        3,\"Doe, Jane\",\"said \"\"hi\"\"\"\n\
+        1,Zed,\"two\nlines\"\n"
+    );
+    assert_eq!(
+        index.to_sorted_vec(&index.sort_by(0)),
+        b"id,name,note\n\
+        1,Zed,\"two\nlines\"\n\
+        2,\"\"\"Quoted\"\"\",plain\n\
+        3,\"Doe, Jane\",\"said \"\"hi\"\"\"\n"
+    );
+}
+
+#[test]
+fn test_crlf_and_missing_final_line_break() {
+    let data = b"k,v\r\nb,2\r\n\"a\r\nx\",1\r\nc,0";
+    let index = CsvIndex::new(data).unwrap();
+    assert_eq!(index.column(0).get_key(1), b"a\r\nx");
+    assert_eq!(index.column(1).get_key(0), b"2");
+
+    // The last record moves up and gets the file's line break.
+    let sorted = index.to_sorted_vec(&index.sort_by(1));
+    assert_eq!(sorted, b"k,v\r\nc,0\r\n\"a\r\nx\",1\r\nb,2\r\n");
+
+    // Unchanged order reproduces the input exactly.
+    let identity = index.to_sorted_vec(&Permutation::identity(index.len()));
+    assert_eq!(identity, data);
+}
+
+#[test]
+fn test_options_and_columns() {
+    let data = b"b;1\n;\na;\"x;y\";extra\n\n";
+    let index = CsvIndex::with_options(data, b';', false).unwrap();
+    assert_eq!(index.len(), 4);
+    assert_eq!(index.header(), None);
+
+    let second = index.column(1);
+    let keys: Vec<&[u8]> = (0..second.len()).map(|i| second.get_key(i)).collect();
+    // The empty line is one record with one empty field.
+    assert_eq!(keys, vec![&b"1"[..], b"", b"x;y", b""]);
+    assert_eq!(index.column(2).get_key(2), b"extra");
+
+    let index = CsvIndex::new(b"\"first name\",age\nbo,30\nal,4\n").unwrap();
+    let ages = index.column_named(b"age").unwrap();
+    assert_eq!(orasort(&ages), vec![0, 1]);
+    let names = index.column_named(b"first name").unwrap();
+    assert_eq!(orasort(&names), vec![1, 0]);
+    assert!(index.column_named(b"city").is_none());
+
+    let header_only = CsvIndex::new(b"a,b").unwrap();
+    assert!(header_only.is_empty());
+    assert_eq!(header_only.to_sorted_vec(&header_only.sort_by(0)), b"a,b");
+}
+
+#[test]
+fn test_malformed_quotes() {
+    assert_eq!(
+        CsvIndex::new(b"h\n\"open,1\n").unwrap_err(),
+        CsvError::UnterminatedQuote { offset: 2 }
+    );
+    assert_eq!(
+        CsvIndex::new(b"h\n\"a\"b,1\n").unwrap_err(),
+        CsvError::UnexpectedByte { offset: 5 }
+    );
+    assert_eq!(
+        CsvIndex::new(b"\"a\"\r").unwrap_err(),
+        CsvError::UnexpectedByte { offset: 3 }
+    );
+}
+
+#[test]
+fn test_random_records() {
+    let mut rng = rand::rng();
+    let alphabet = b"ab,\"\n\r x";
+
+    let records: Vec<Vec<Vec<u8>>> = (0..2_000)
+        .map(|_| {
+            (0..3)
+                .map(|_| {
+                    let len = rng.random_range(0..8);
+                    (0..len)
+                        .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+                        .collect()
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut data = b"c0,c1,c2\n".to_vec();
+    for record in &records {
+        data.extend(encode_record(record));
+        data.push(b'\n');
+    }
+
+    let index = CsvIndex::new(&data).unwrap();
+    assert_eq!(index.len(), records.len());
+
+    let column = index.column(1);
+    for (i, record) in records.iter().enumerate() {
+        assert_eq!(column.get_key(i), &record[1][..]);
+    }
+
+    let sorted = index.to_sorted_vec(&index.sort_by(1));
+    let resorted = CsvIndex::new(&sorted).unwrap();
+    let column = resorted.column(1);
+
+    let mut expected: Vec<&Vec<Vec<u8>>> = records.iter().collect();
+    expected.sort_by(|a, b| a[1].cmp(&b[1]));
+    for (i, record) in expected.iter().enumerate() {
+        assert_eq!(column.get_key(i), &record[1][..]);
+    }
+
+    let mut before: Vec<&[u8]> = (0..index.len()).map(|i| index.record(i)).collect();
+    let mut after: Vec<&[u8]> = (0..resorted.len()).map(|i| resorted.record(i)).collect();
+    before.sort();
+    after.sort();
+    assert_eq!(before, after);
+}
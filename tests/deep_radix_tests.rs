@@ -0,0 +1,72 @@
+use orasort::core::KeyAccessor;
+use orasort::prelude::*;
+use std::thread;
+
+const KEY_LEN: usize = 64 * 1024;
+const PEELED: usize = 2_000;
+const ALL_ZERO: usize = 1_100;
+const COUNT: usize = PEELED + ALL_ZERO;
+/// Co-prime with `COUNT`; scrambles the input order so it is not presorted.
+const SCRAMBLE: usize = 7_919;
+
+/// Maps an input index to the key it holds.
+fn key_id(index: usize) -> usize {
+    (index * SCRAMBLE) % COUNT
+}
+
+/// 64 KiB keys made of zero bytes, without materializing every key.
+///
+/// Key id `i < PEELED` has a single `1` byte at position `i`, all other keys are entirely zero.
+/// Every radix pass over such keys splits off exactly one key, which used to add one level
+/// of recursion per key byte.
+struct ZeroKeys {
+    peeled: Vec<u8>,
+    zeros: Vec<u8>,
+}
+
+impl ZeroKeys {
+    fn new() -> Self {
+        // Windows into one buffer with a single 1 byte at position PEELED - 1.
+        let mut peeled = vec![0u8; KEY_LEN + PEELED];
+        peeled[PEELED - 1] = 1;
+        Self {
+            peeled,
+            zeros: vec![0u8; KEY_LEN],
+        }
+    }
+}
+
+impl KeyAccessor for ZeroKeys {
+    fn get_key(&self, index: usize) -> &[u8] {
+        let id = key_id(index);
+        if id < PEELED {
+            let start = PEELED - 1 - id;
+            &self.peeled[start..start + KEY_LEN]
+        } else {
+            &self.zeros
+        }
+    }
+
+    fn len(&self) -> usize {
+        COUNT
+    }
+}
+
+#[test]
+fn test_64k_zero_keys_on_small_stack() {
+    let indices = thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(|| {
+            let keys = ZeroKeys::new();
+            orasort(&keys)
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    // All-zero keys first (in any order), then the key whose 1 byte comes last, and so on.
+    let ids: Vec<usize> = indices.iter().map(|&i| key_id(i)).collect();
+    let (zeros, peeled) = ids.split_at(ALL_ZERO);
+    assert!(zeros.iter().all(|&id| id >= PEELED));
+    assert_eq!(peeled, (0..PEELED).rev().collect::<Vec<_>>());
+}
@@ -0,0 +1,195 @@
+use orasort::core::TryKeyAccessor;
+use orasort::fallible::{
+    try_orasort, try_orasort_from_indices, try_orasort_mut, try_orasort_slice,
+};
+use orasort::prelude::*;
+use rand::Rng;
+use std::cell::Cell;
+
+#[derive(Debug, PartialEq, Eq)]
+struct FetchError(usize);
+
+/// Keys with long shared prefixes; fetching the full key of `poisoned` fails.
+///
+/// Prefix loads (`try_get_u64_prefix`) fail only if `poison_prefix` is set.
+struct FlakyKeys {
+    keys: Vec<Vec<u8>>,
+    poisoned: usize,
+    poison_prefix: bool,
+    calls: Cell<usize>,
+    calls_after_failure: Cell<usize>,
+    failed: Cell<bool>,
+}
+
+impl FlakyKeys {
+    fn new(count: usize, poisoned: usize, poison_prefix: bool) -> Self {
+        let mut rng = rand::rng();
+        let mut keys: Vec<Vec<u8>> = (0..count)
+            .map(|_| {
+                // Identical first 8 bytes force full-key fetches while sorting.
+                let mut key = b"segment/".to_vec();
+                key.extend((0..4).map(|_| rng.random_range(b'a'..=b'z')));
+                key
+            })
+            .collect();
+        // A duplicate guarantees the poisoned key's full bytes are needed to break the tie.
+        if poisoned < count - 1 {
+            keys[poisoned] = keys[poisoned + 1].clone();
+        }
+        Self {
+            keys,
+            poisoned,
+            poison_prefix,
+            calls: Cell::new(0),
+            calls_after_failure: Cell::new(0),
+            failed: Cell::new(false),
+        }
+    }
+
+    fn track(&self, index: usize, fails: bool) -> Result<(), FetchError> {
+        self.calls.set(self.calls.get() + 1);
+        if self.failed.get() {
+            self.calls_after_failure
+                .set(self.calls_after_failure.get() + 1);
+        }
+        if fails && index == self.poisoned {
+            self.failed.set(true);
+            return Err(FetchError(index));
+        }
+        Ok(())
+    }
+}
+
+impl TryKeyAccessor for FlakyKeys {
+    type Error = FetchError;
+
+    fn try_get_key(&self, index: usize) -> Result<&[u8], FetchError> {
+        self.track(index, true)?;
+        Ok(&self.keys[index])
+    }
+
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn try_get_u64_prefix(&self, index: usize, offset: usize) -> Result<u64, FetchError> {
+        self.track(index, self.poison_prefix)?;
+        let key = &self.keys[index];
+        let mut buf = [0u8; 8];
+        if offset < key.len() {
+            let n = (key.len() - offset).min(8);
+            buf[..n].copy_from_slice(&key[offset..offset + n]);
+        }
+        Ok(u64::from_be_bytes(buf))
+    }
+}
+
+#[test]
+fn test_infallible_accessors_are_try_accessors() {
+    let data = vec!["pear", "apple", "fig"];
+    assert_eq!(try_orasort(&data).unwrap(), orasort(&data));
+    assert_eq!(
+        try_orasort_from_indices(&data, vec![2, 0], 0).unwrap(),
+        vec![2, 0]
+    );
+}
+
+#[test]
+fn test_error_while_loading_prefixes_stops_immediately() {
+    let keys = FlakyKeys::new(5_000, 10, true);
+
+    assert_eq!(try_orasort(&keys), Err(FetchError(10)));
+    assert_eq!(keys.calls.get(), 11);
+}
+
+#[test]
+fn test_error_while_resolving_ties() {
+    let keys = FlakyKeys::new(5_000, 1_234, false);
+
+    assert_eq!(try_orasort(&keys), Err(FetchError(1_234)));
+    assert!(keys.failed.get());
+    assert_eq!(keys.calls_after_failure.get(), 0);
+}
+
+#[test]
+fn test_error_leaves_data_untouched() {
+    let keys = FlakyKeys::new(3_000, 7, false);
+    let mut rows: Vec<usize> = (0..3_000).collect();
+
+    assert_eq!(try_orasort_mut(&keys, &mut rows), Err(FetchError(7)));
+    assert_eq!(rows, (0..3_000).collect::<Vec<_>>());
+
+    let keys = FlakyKeys::new(3_000, 7, false);
+    let mut indices: Vec<usize> = (0..3_000).rev().collect();
+    assert_eq!(
+        try_orasort_slice(&keys, &mut indices, 0),
+        Err(FetchError(7))
+    );
+    assert_eq!(indices, (0..3_000).rev().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_success_applies_permutation() {
+    // Poisoned index out of range: never fails.
+    let keys = FlakyKeys::new(2_000, usize::MAX, true);
+    let mut rows: Vec<Vec<u8>> = keys.keys.clone();
+
+    try_orasort_mut(&keys, &mut rows).unwrap();
+
+    let mut expected = keys.keys.clone();
+    expected.sort();
+    assert_eq!(rows, expected);
+}
+
+/// Keys sharing their first 8 bytes; the `fail_at`-th full-key fetch fails.
+struct FailingFetch {
+    keys: Vec<Vec<u8>>,
+    fail_at: usize,
+    fetches: Cell<usize>,
+}
+
+impl TryKeyAccessor for FailingFetch {
+    type Error = FetchError;
+
+    fn try_get_key(&self, index: usize) -> Result<&[u8], FetchError> {
+        let fetches = self.fetches.get() + 1;
+        self.fetches.set(fetches);
+        assert!(fetches <= self.fail_at, "fetch after the failure");
+        if fetches == self.fail_at {
+            return Err(FetchError(index));
+        }
+        Ok(&self.keys[index])
+    }
+
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+}
+
+#[test]
+fn test_error_in_the_middle_of_a_leaf() {
+    let mut rng = rand::rng();
+    for count in [20, 200, 1_000] {
+        let keys: Vec<Vec<u8>> = (0..count)
+            .map(|_| {
+                let mut key = b"segment/".to_vec();
+                key.extend((0..6).map(|_| rng.random_range(b'a'..=b'c')));
+                key
+            })
+            .collect();
+
+        // Without a failure, sorting the leaf takes more fetches than any `fail_at` below.
+        for fail_at in (1..3 * count).step_by(7) {
+            let keys = FailingFetch {
+                keys: keys.clone(),
+                fail_at,
+                fetches: Cell::new(0),
+            };
+            assert!(
+                try_orasort(&keys).is_err(),
+                "{count} keys, failing at {fail_at}"
+            );
+            assert_eq!(keys.fetches.get(), fail_at);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use orasort::framed::{FramedRecords, Framing};
+use orasort::{FrameError, KeyAccessor, orasort};
+use rand::prelude::*;
+use std::ops::Bound;
+
+fn frame(framing: Framing, body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    match framing {
+        Framing::Varint => {
+            let mut len = body.len() as u64;
+            while len >= 0x80 {
+                out.push(len as u8 | 0x80);
+                len >>= 7;
+            }
+            out.push(len as u8);
+        }
+        Framing::U32Be => out.extend_from_slice(&(body.len() as u32).to_be_bytes()),
+        Framing::U32Le => out.extend_from_slice(&(body.len() as u32).to_le_bytes()),
+    }
+    out.extend_from_slice(body);
+    out
+}
+
+fn random_bodies(rng: &mut StdRng, count: usize) -> Vec<Vec<u8>> {
+    (0..count)
+        .map(|_| {
+            // Long enough bodies to need multi-byte varints now and then.
+            let len = if rng.random_bool(0.05) {
+                rng.random_range(128..400)
+            } else {
+                rng.random_range(0..12)
+            };
+            (0..len).map(|_| rng.random_range(b'a'..=b'd')).collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_sort_matches_owned_sort() {
+    let mut rng = StdRng::seed_from_u64(48);
+    for framing in [Framing::Varint, Framing::U32Be, Framing::U32Le] {
+        let bodies = random_bodies(&mut rng, 2000);
+        let data: Vec<u8> = bodies
+            .iter()
+            .flat_map(|body| frame(framing, body))
+            .collect();
+
+        let records = FramedRecords::new(&data, framing).unwrap();
+        assert_eq!(records.len(), bodies.len());
+        for (index, body) in bodies.iter().enumerate() {
+            assert_eq!(records.body(index), &body[..]);
+            assert_eq!(records.frame(index), &frame(framing, body)[..]);
+        }
+
+        let order = records.sort();
+        assert_eq!(order, orasort(&bodies));
+
+        let mut sorted = bodies.clone();
+        sorted.sort();
+        let expected: Vec<u8> = sorted
+            .iter()
+            .flat_map(|body| frame(framing, body))
+            .collect();
+        assert_eq!(records.to_sorted_vec(&order), expected);
+
+        let mut written = Vec::new();
+        records.write_sorted(&order, &mut written).unwrap();
+        assert_eq!(written, expected);
+    }
+}
+
+#[test]
+fn test_key_range() {
+    let mut rng = StdRng::seed_from_u64(480);
+    let bodies = random_bodies(&mut rng, 1000);
+    let data: Vec<u8> = bodies
+        .iter()
+        .flat_map(|body| frame(Framing::Varint, body))
+        .collect();
+    let records = FramedRecords::new(&data, Framing::Varint).unwrap();
+
+    let clamp = |body: &[u8], start: usize, end: usize| -> Vec<u8> {
+        let end = end.min(body.len());
+        body[start.min(end)..end].to_vec()
+    };
+
+    for (start, end) in [(0, 3), (2, 6), (4, usize::MAX), (20, 30)] {
+        let keyed = if end == usize::MAX {
+            records.clone().with_key_range(start..)
+        } else {
+            records.clone().with_key_range(start..end)
+        };
+        let keys: Vec<Vec<u8>> = bodies.iter().map(|body| clamp(body, start, end)).collect();
+        for (index, key) in keys.iter().enumerate() {
+            assert_eq!(keyed.get_key(index), &key[..]);
+            for offset in [0, 1, 5, 8, 9] {
+                assert_eq!(
+                    keyed.get_u64_prefix(index, offset),
+                    keys.get_u64_prefix(index, offset)
+                );
+            }
+        }
+        assert_eq!(keyed.sort(), orasort(&keys));
+    }
+
+    let inclusive = records.clone().with_key_range(1..=2);
+    assert_eq!(inclusive.get_key(0), &clamp(&bodies[0], 1, 3)[..]);
+
+    // Bounds at `usize::MAX` saturate instead of overflowing.
+    let to_end = records.clone().with_key_range(1..=usize::MAX);
+    for (index, body) in bodies.iter().enumerate() {
+        assert_eq!(to_end.get_key(index), &clamp(body, 1, usize::MAX)[..]);
+    }
+    let past_end = records
+        .clone()
+        .with_key_range((Bound::Excluded(usize::MAX), Bound::Unbounded));
+    assert!((0..past_end.len()).all(|index| past_end.get_key(index).is_empty()));
+}
+
+#[test]
+fn test_empty_buffer_and_bodies() {
+    let records = FramedRecords::new(b"", Framing::U32Le).unwrap();
+    assert!(records.is_empty());
+    assert!(records.to_sorted_vec(&records.sort()).is_empty());
+
+    let data = b"\x00\x01b\x00\x01a";
+    let records = FramedRecords::new(data, Framing::Varint).unwrap();
+    assert_eq!(records.len(), 4);
+    assert_eq!(records.body(0), b"");
+    assert_eq!(
+        records.to_sorted_vec(&records.sort()),
+        b"\x00\x00\x01a\x01b"
+    );
+}
+
+#[test]
+fn test_malformed_buffers() {
+    // Body shorter than its length.
+    assert_eq!(
+        FramedRecords::new(b"\x01a\x05abc", Framing::Varint).unwrap_err(),
+        FrameError::Truncated { offset: 2 }
+    );
+    // Buffer ends inside a varint.
+    assert_eq!(
+        FramedRecords::new(b"\x01a\x80", Framing::Varint).unwrap_err(),
+        FrameError::Truncated { offset: 2 }
+    );
+    // Varint longer than 64 bits.
+    assert_eq!(
+        FramedRecords::new(&[0xff; 11], Framing::Varint).unwrap_err(),
+        FrameError::InvalidLength { offset: 0 }
+    );
+    // Buffer ends inside a fixed-width length.
+    assert_eq!(
+        FramedRecords::new(b"\x00\x00\x00\x01a\x00\x00", Framing::U32Be).unwrap_err(),
+        FrameError::Truncated { offset: 5 }
+    );
+    assert_eq!(
+        FramedRecords::new(b"\xff\xff\xff\xffabc", Framing::U32Le).unwrap_err(),
+        FrameError::Truncated { offset: 0 }
+    );
+}
+
+#[test]
+#[should_panic(expected = "same length")]
+fn test_permutation_length_mismatch() {
+    let records = FramedRecords::new(b"\x01a\x01b", Framing::Varint).unwrap();
+    let other = FramedRecords::new(b"\x01a", Framing::Varint).unwrap();
+    records.to_sorted_vec(&other.sort());
+}
@@ -0,0 +1,126 @@
+use orasort::SortedIndex;
+use orasort::core::KeyAccessor;
+use rand::prelude::*;
+use std::cell::Cell;
+
+mod common;
+use common::random_keys;
+
+/// Keys that count full key fetches.
+struct CountingKeys {
+    keys: Vec<Vec<u8>>,
+    key_fetches: Cell<usize>,
+}
+
+impl KeyAccessor for CountingKeys {
+    fn get_key(&self, index: usize) -> &[u8] {
+        self.key_fetches.set(self.key_fetches.get() + 1);
+        &self.keys[index]
+    }
+
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+}
+
+// Small alphabet with zero bytes: many ties, shared prefixes and padding lookalikes.
+const ALPHABET: &[u8] = b"\x00\x01\x02";
+const MAX_LEN: usize = 12;
+
+/// Checks every search against binary searches over `sorted`, a sorted copy of the keys.
+fn check_against_model<A: KeyAccessor>(index: &SortedIndex<A>, sorted: &[Vec<u8>], probe: &[u8]) {
+    let lower = sorted.partition_point(|k| k.as_slice() < probe);
+    let upper = sorted.partition_point(|k| k.as_slice() <= probe);
+    let prefix_end = sorted.partition_point(|k| &k[..k.len().min(probe.len())] <= probe);
+
+    assert_eq!(index.lower_bound(probe), lower, "lower_bound {:?}", probe);
+    assert_eq!(index.upper_bound(probe), upper, "upper_bound {:?}", probe);
+    assert_eq!(index.equal_range(probe), lower..upper);
+    assert_eq!(index.contains(probe), lower < upper);
+    assert_eq!(
+        index.prefix_range(probe),
+        lower..prefix_end,
+        "prefix {:?}",
+        probe
+    );
+}
+
+#[test]
+fn test_searches_match_model() {
+    let mut rng = StdRng::seed_from_u64(36);
+
+    for &count in &[0, 1, 5, 100, 3000] {
+        let keys = random_keys(&mut rng, count, MAX_LEN, ALPHABET);
+        let cached = SortedIndex::new(&keys);
+        let uncached = SortedIndex::without_prefixes(&keys);
+
+        for position in 0..cached.len() {
+            assert_eq!(cached.key_at(position), uncached.key_at(position));
+        }
+
+        let mut sorted = keys.clone();
+        sorted.sort();
+
+        let probes = random_keys(&mut rng, 200, MAX_LEN, ALPHABET);
+        for probe in probes.iter().chain(keys.iter().take(50)) {
+            check_against_model(&cached, &sorted, probe);
+            check_against_model(&uncached, &sorted, probe);
+        }
+    }
+}
+
+#[test]
+fn test_prefix_range_scans_long_prefixes() {
+    let keys: Vec<String> = (0..500)
+        .map(|i| format!("tenant-{:02}/object-{:04}", i % 7, i))
+        .collect();
+    let index = SortedIndex::new(&keys);
+
+    let range = index.prefix_range(b"tenant-03/");
+    let found: Vec<&String> = range.map(|p| &keys[index.indices()[p]]).collect();
+    assert_eq!(
+        found.len(),
+        keys.iter().filter(|k| k.starts_with("tenant-03/")).count()
+    );
+    assert!(found.windows(2).all(|w| w[0] < w[1]));
+    assert!(found.iter().all(|k| k.starts_with("tenant-03/")));
+
+    assert_eq!(index.prefix_range(b""), 0..500);
+    assert!(index.prefix_range(b"tenant-9").is_empty());
+}
+
+#[test]
+fn test_cached_searches_only_load_keys_on_ties() {
+    let mut rng = StdRng::seed_from_u64(360);
+    let keys: Vec<Vec<u8>> = (0..10_000)
+        .map(|_| rng.random::<u64>().to_be_bytes().to_vec())
+        .collect();
+    let probes: Vec<Vec<u8>> = keys.iter().step_by(97).cloned().collect();
+
+    let counting = CountingKeys {
+        keys,
+        key_fetches: Cell::new(0),
+    };
+    let index = SortedIndex::new(&counting);
+    counting.key_fetches.set(0);
+
+    for probe in &probes {
+        assert!(index.contains(probe));
+    }
+    // One tie in `lower_bound` plus the final equality check of `contains`.
+    assert!(counting.key_fetches.get() <= 2 * probes.len());
+}
+
+#[test]
+fn test_from_permutation_and_into_parts() {
+    let data = vec!["pear", "apple", "fig"];
+    let permutation = orasort::orasort(&data);
+    let index = SortedIndex::from_permutation(data.clone(), permutation.clone());
+
+    assert_eq!(index.key_at(0), b"apple");
+    assert_eq!(index.lower_bound(b"banana"), 1);
+
+    let (provider, indices) = index.into_parts();
+    assert_eq!(provider, data);
+    assert_eq!(permutation, indices);
+}
@@ -0,0 +1,232 @@
+use orasort::JsonlError;
+use orasort::jsonl::{JsonPath, JsonlIndex};
+use rand::Rng;
+
+fn paths(paths: &[&str]) -> Vec<JsonPath> {
+    paths.iter().map(|p| JsonPath::parse(p).unwrap()).collect()
+}
+
+fn sorted_lines(data: &str, by: &[&str]) -> Vec<String> {
+    let index = JsonlIndex::new(data.as_bytes());
+    let order = index.sort_by(&paths(by)).unwrap();
+    String::from_utf8(index.to_sorted_vec(&order))
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn test_sort_by_nested_paths() {
+    let data = r#"{"ts": 20, "user": {"name": "b", "id": 7}}
+{"user":{"id":12,"tags":["x",{"id":0}]},"ts":5}
+  {"ts":10,"user":{"id":7}}
+{"ts":1}
+{"user":{"id":"7"},"ts":0}
+"#;
+    assert_eq!(
+        sorted_lines(data, &[".user.id", ".ts"]),
+        vec![
+            r#"{"ts":1}"#,
+            r#"  {"ts":10,"user":{"id":7}}"#,
+            r#"{"ts": 20, "user": {"name": "b", "id": 7}}"#,
+            r#"{"user":{"id":12,"tags":["x",{"id":0}]},"ts":5}"#,
+            r#"{"user":{"id":"7"},"ts":0}"#,
+        ]
+    );
+}
+
+#[test]
+fn test_type_order() {
+    let data = r#"{"v":"a"}
+{"v":[1]}
+{"v":-1}
+{"v":true}
+{"v":null}
+{}
+{"v":0}
+{"v":false}
+{"v":1}
+{"v":""}
+"#;
+    let values: Vec<String> = sorted_lines(data, &["v"]);
+    assert_eq!(
+        values,
+        vec![
+            "{}",
+            r#"{"v":null}"#,
+            r#"{"v":false}"#,
+            r#"{"v":true}"#,
+            r#"{"v":-1}"#,
+            r#"{"v":0}"#,
+            r#"{"v":1}"#,
+            r#"{"v":""}"#,
+            r#"{"v":"a"}"#,
+            r#"{"v":[1]}"#,
+        ]
+    );
+}
+
+/// Formats `mantissa × 10^exponent` in one of several equivalent JSON spellings.
+fn spell(mantissa: i64, exponent: i32, style: u32) -> String {
+    let sign = if mantissa < 0 { "-" } else { "" };
+    let digits = mantissa.unsigned_abs().to_string();
+    match style {
+        0 => format!("{}{}e{}", sign, digits, exponent),
+        1 => format!("{}{}.000E{:+}", sign, digits, exponent),
+        _ => {
+            // Plain decimal notation.
+            let exponent = exponent as i64;
+            if exponent >= 0 {
+                format!("{}{}{}", sign, digits, "0".repeat(exponent as usize))
+            } else {
+                let shift = (-exponent) as usize;
+                let padded = format!("{:0>width$}", digits, width = shift + 1);
+                let (int, frac) = padded.split_at(padded.len() - shift);
+                format!("{}{}.{}", sign, int, frac)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_numbers_compare_by_value() {
+    let mut rng = rand::rng();
+    let numbers: Vec<(i64, i32)> = (0..3_000)
+        .map(|_| (rng.random_range(-2_000..2_000), rng.random_range(-4..4)))
+        .collect();
+
+    let data: String = numbers
+        .iter()
+        .map(|&(m, e)| format!("{{\"n\":{}}}\n", spell(m, e, rng.random_range(0..3))))
+        .collect();
+
+    let index = JsonlIndex::new(data.as_bytes());
+    let order = index.sort_by(&paths(&["n"])).unwrap();
+
+    // Compare exactly by scaling both values to 10^-4.
+    let scaled = |(m, e): (i64, i32)| i128::from(m) * 10i128.pow((e + 4) as u32);
+    for pair in order.windows(2) {
+        assert!(scaled(numbers[pair[0]]) <= scaled(numbers[pair[1]]));
+    }
+
+    let keys = index.keys(&paths(&["n"])).unwrap();
+    for pair in order.windows(2) {
+        let (a, b) = (numbers[pair[0]], numbers[pair[1]]);
+        let equal_keys = keys.get(pair[0]) == keys.get(pair[1]);
+        assert_eq!(equal_keys, scaled(a) == scaled(b));
+    }
+}
+
+#[test]
+fn test_large_integers_keep_precision() {
+    let data =
+        "{\"id\":9007199254740993}\n{\"id\":9007199254740992}\n{\"id\":1e400}\n{\"id\":-0.0}\n";
+    assert_eq!(
+        sorted_lines(data, &["id"]),
+        vec![
+            "{\"id\":-0.0}",
+            "{\"id\":9007199254740992}",
+            "{\"id\":9007199254740993}",
+            "{\"id\":1e400}",
+        ]
+    );
+}
+
+#[test]
+fn test_strings_compare_decoded() {
+    let data = r#"{"s":"\u00e9"}
+{"s":"é"}
+{"s":"\ud83d\ude00"}
+{"s":"a\"b"}
+{"s":"a\u0000"}
+{"s":"a"}
+"#;
+    let index = JsonlIndex::new(data.as_bytes());
+    let keys = index.keys(&paths(&["s"])).unwrap();
+    assert_eq!(keys.get(0), keys.get(1));
+
+    assert_eq!(
+        sorted_lines(data, &["s"])[..4],
+        [
+            r#"{"s":"a"}"#,
+            r#"{"s":"a\u0000"}"#,
+            r#"{"s":"a\"b"}"#,
+            r#"{"s":"\u00e9"}"#,
+        ]
+    );
+    // U+1F600 encodes as F0 9F 98 80 and sorts after U+00E9 (C3 A9).
+    assert_eq!(sorted_lines(data, &["s"])[5], r#"{"s":"\ud83d\ude00"}"#);
+
+    // Member names are decoded too.
+    let index = JsonlIndex::new(br#"{"\u0069d": 5}"#);
+    assert_eq!(index.value(0, &paths(&["id"])[0]).unwrap(), Some(&b"5"[..]));
+}
+
+#[test]
+fn test_values_and_array_paths() {
+    let index = JsonlIndex::new(br#"{"a": {"b": [10, {"c": "x,y"}, 30]}, "d": true}"#);
+    let value = |path: &str| index.value(0, &JsonPath::parse(path).unwrap()).unwrap();
+
+    assert_eq!(value(".a.b.0"), Some(&b"10"[..]));
+    assert_eq!(value(".a.b.1.c"), Some(&br#""x,y""#[..]));
+    assert_eq!(value(".a.b.1"), Some(&br#"{"c": "x,y"}"#[..]));
+    assert_eq!(value(".a.b.3"), None);
+    assert_eq!(value(".a.b.x"), None);
+    assert_eq!(value("d"), Some(&b"true"[..]));
+    assert_eq!(value("d.e"), None);
+    assert_eq!(value("missing"), None);
+}
+
+#[test]
+fn test_lines_are_written_unchanged() {
+    let data = b"{\"k\":2}\n\n{\"k\":1} \r\n{\"k\":0}";
+    let index = JsonlIndex::new(data);
+    assert_eq!(index.len(), 4);
+
+    let order = index.sort_by(&paths(&["k"])).unwrap();
+    // The blank line has no value and sorts first; the unterminated last line gets a break.
+    assert_eq!(
+        index.to_sorted_vec(&order),
+        b"\n{\"k\":0}\n{\"k\":1} \r\n{\"k\":2}"
+    );
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(
+        JsonPath::parse("a..b"),
+        Err(JsonlError::InvalidPath("a..b".to_string()))
+    );
+    assert!(JsonPath::parse("").is_err());
+    assert_eq!(
+        JsonPath::parse(".user.id")
+            .unwrap()
+            .segments()
+            .collect::<Vec<_>>(),
+        vec!["user", "id"]
+    );
+
+    let index = JsonlIndex::new(b"{\"a\":1}\n{\"a\" 1}\n");
+    assert_eq!(
+        index.sort_by(&paths(&["a"])),
+        Err(JsonlError::Malformed { line: 1, offset: 5 })
+    );
+
+    let index = JsonlIndex::new(b"{\"a\":01x}\n");
+    assert!(matches!(
+        index.sort_by(&paths(&["a"])),
+        Err(JsonlError::Malformed { line: 0, .. })
+    ));
+    let index = JsonlIndex::new(b"{\"a\":\"\\q\"}\n");
+    assert!(index.sort_by(&paths(&["a"])).is_err());
+
+    // Only the scanned part of a line must be valid.
+    let index = JsonlIndex::new(b"{\"a\":1, garbage\n");
+    assert!(index.sort_by(&paths(&["a"])).is_ok());
+
+    assert_eq!(
+        JsonlError::Malformed { line: 1, offset: 5 }.to_string(),
+        "malformed JSON on line 2 at byte 5"
+    );
+}
@@ -0,0 +1,326 @@
+use orasort::collate::Collator;
+use orasort::keyspec::KeySpec;
+use orasort::{KeySpecError, orasort_collated};
+use rand::Rng;
+use std::cmp::Ordering;
+
+fn sorted<'a>(spec: &str, lines: &[&'a str]) -> Vec<&'a str> {
+    KeySpec::parse(spec).unwrap().sort(lines).gather(lines)
+}
+
+fn fields<'a>(spec: &str, line: &'a str) -> Vec<&'a str> {
+    let spec = KeySpec::parse(spec).unwrap();
+    spec.fields(line.as_bytes())
+        .map(|field| {
+            let start = field.as_ptr() as usize - line.as_ptr() as usize;
+            &line[start..start + field.len()]
+        })
+        .collect()
+}
+
+#[test]
+fn test_tab_separated_columns() {
+    let lines = [
+        "web-2\t200\t2024-03-01",
+        "web-1\t500\t2024-03-01",
+        "web-1\t200\t2024-02-28",
+        "web-3\t404\t2024-03-01",
+    ];
+    assert_eq!(
+        sorted(r"-t '\t' -k3,3 -k1,1r", &lines),
+        vec![
+            "web-1\t200\t2024-02-28",
+            "web-3\t404\t2024-03-01",
+            "web-2\t200\t2024-03-01",
+            "web-1\t500\t2024-03-01",
+        ]
+    );
+    assert_eq!(
+        sorted("-t '\t' -k2,2 -k3", &lines),
+        vec![
+            "web-1\t200\t2024-02-28",
+            "web-2\t200\t2024-03-01",
+            "web-3\t404\t2024-03-01",
+            "web-1\t500\t2024-03-01",
+        ]
+    );
+}
+
+#[test]
+fn test_field_extraction() {
+    // Without -t, fields keep their leading blanks.
+    assert_eq!(fields("-k2,2", "a  bb\tcc"), vec!["  bb"]);
+    assert_eq!(fields("-k2b,2", "a  bb\tcc"), vec!["bb"]);
+    // `b` on the end position only affects where the end character is counted from.
+    assert_eq!(fields("-k2,2b", "a  bb\tcc"), vec!["  bb"]);
+    assert_eq!(fields("-k2,2.1b", "a  bb\tcc"), vec!["  b"]);
+    assert_eq!(fields("-b -k2,2", "a  bb\tcc"), vec!["bb"]);
+    assert_eq!(fields("-k2", "a  bb\tcc"), vec!["  bb\tcc"]);
+    assert_eq!(fields("-k1", "  leading"), vec!["  leading"]);
+
+    // Character positions, including ones past the field or line.
+    assert_eq!(fields("-t: -k2.2,2.3", "ab:cdef:gh"), vec!["de"]);
+    assert_eq!(fields("-t: -k2.3,3.1", "ab:cdef:gh"), vec!["ef:g"]);
+    assert_eq!(fields("-t: -k1.5,1", "ab:cdef:gh"), vec![""]);
+    assert_eq!(fields("-t: -k2,2.0", "ab:cdef:gh"), vec!["cdef"]);
+    assert_eq!(fields("-t: -k4", "ab:cdef:gh"), vec![""]);
+    assert_eq!(fields("-t: -k3,1", "ab:cdef:gh"), vec![""]);
+
+    // Empty fields between adjacent separators.
+    assert_eq!(fields("-t, -k2,2 -k3,3", "a,,c"), vec!["", "c"]);
+
+    // Without -k, the key is the whole line.
+    assert_eq!(fields("-f", "Whole Line"), vec!["Whole Line"]);
+}
+
+#[test]
+fn test_global_flags_apply_to_keys_without_their_own() {
+    let lines = ["b A", "a b", "c a", "a B"];
+
+    // -f is inherited by the first key only; the second has its own flags.
+    let spec = "-f -k1,1 -k2,2r";
+    assert_eq!(sorted(spec, &lines), vec!["a b", "a B", "b A", "c a"]);
+
+    // Global -r also reverses the whole-line comparison of lines with equal keys.
+    assert_eq!(
+        sorted("-r -f -k2,2", &lines),
+        vec!["a b", "a B", "c a", "b A"]
+    );
+    assert_eq!(sorted("-f -k2,2", &lines), vec!["b A", "c a", "a B", "a b"]);
+}
+
+#[test]
+fn test_folds_lower_to_upper_case() {
+    // Folding to upper case puts `_` (0x5F) after letters.
+    let lines = ["_x", "b", "A"];
+    assert_eq!(sorted("-f", &lines), vec!["A", "b", "_x"]);
+    assert_eq!(sorted("", &lines), vec!["A", "_x", "b"]);
+}
+
+#[test]
+fn test_prefixes_and_nul_bytes() {
+    let lines = ["a", "a\0", "a\0\0", "a\x01", "", "ab"];
+    assert_eq!(
+        sorted("-k1", &lines),
+        vec!["", "a", "a\0", "a\0\0", "a\x01", "ab"]
+    );
+    assert_eq!(
+        sorted("-k1r", &lines),
+        vec!["ab", "a\x01", "a\0\0", "a\0", "a", ""]
+    );
+}
+
+/// Reference comparison for `-t , -k2,2r -k3,3f -k1,1`, followed by the whole line.
+fn reference_cmp(a: &[u8], b: &[u8]) -> Ordering {
+    let field = |line: &[u8], i: usize| -> Vec<u8> {
+        line.split(|&c| c == b',').nth(i).unwrap_or(&[]).to_vec()
+    };
+    let upper = |bytes: Vec<u8>| -> Vec<u8> { bytes.to_ascii_uppercase() };
+
+    field(b, 1)
+        .cmp(&field(a, 1))
+        .then_with(|| upper(field(a, 2)).cmp(&upper(field(b, 2))))
+        .then_with(|| field(a, 0).cmp(&field(b, 0)))
+        .then_with(|| a.cmp(b))
+}
+
+#[test]
+fn test_random_lines_match_reference() {
+    let spec = KeySpec::parse("-t , -k2,2r -k3,3f -k1,1").unwrap();
+    let mut rng = rand::rng();
+    let alphabet = b"aAbB,\0 ";
+
+    let lines: Vec<Vec<u8>> = (0..4_000)
+        .map(|_| {
+            let len = rng.random_range(0..24);
+            (0..len)
+                .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+                .collect()
+        })
+        .collect();
+
+    let actual = spec.sort(&lines).gather(&lines);
+
+    let mut expected = lines.clone();
+    expected.sort_by(|a, b| reference_cmp(a, b));
+    assert_eq!(actual, expected);
+
+    for pair in lines.windows(2) {
+        assert_eq!(
+            spec.compare(&pair[0], &pair[1]),
+            reference_cmp(&pair[0], &pair[1])
+        );
+    }
+}
+
+#[test]
+fn test_stable_keeps_input_order_of_equal_keys() {
+    let mut rng = rand::rng();
+    let lines: Vec<String> = (0..3_000)
+        .map(|i| format!("{} {}", rng.random_range(0..20), i))
+        .collect();
+
+    for spec in ["-s -k1,1", "-s -r -k1,1"] {
+        let order = KeySpec::parse(spec).unwrap().sort(&lines);
+        let reverse = spec.contains("-r");
+
+        for pair in order.windows(2) {
+            let key = |i: usize| lines[i].split(' ').next().unwrap().to_string();
+            let (a, b) = (key(pair[0]), key(pair[1]));
+            match a.cmp(&b) {
+                Ordering::Equal => assert!(pair[0] < pair[1]),
+                ord => assert_eq!(ord == Ordering::Less, !reverse),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_keys_equal_ignores_last_resort() {
+    let spec = KeySpec::parse("-k2,2f").unwrap();
+    assert!(spec.keys_equal(b"x Same", b"y sAME"));
+    assert_eq!(spec.compare(b"x Same", b"y sAME"), Ordering::Less);
+    assert!(!spec.keys_equal(b"x a", b"x b"));
+    assert!(!spec.is_stable());
+}
+
+#[test]
+fn test_usable_as_collator() {
+    let lines = vec!["3 c", "1 a", "2 b"];
+    let spec: KeySpec = "-k2r".parse().unwrap();
+    assert_eq!(orasort_collated(&lines, &spec), vec![0, 2, 1]);
+    assert_eq!(spec.key_count(), 1);
+    assert_eq!(KeySpec::default().sort(&lines), vec![1, 2, 0]);
+}
+
+#[test]
+fn test_from_args() {
+    let spec = KeySpec::from_args(["-t", "\t", "-k", "2,2", "-bfk3", "--reverse"]).unwrap();
+    assert_eq!(spec.separator(), Some(b'\t'));
+    assert_eq!(spec.key_count(), 2);
+
+    let long = KeySpec::from_args(["--field-separator=,", "--key=2,2", "--stable"]).unwrap();
+    assert_eq!(long, KeySpec::parse("-t, -k2,2 -s").unwrap());
+    assert!(long.is_stable());
+
+    assert_eq!(KeySpec::parse(r"-t '\0'").unwrap().separator(), Some(0));
+    assert_eq!(KeySpec::parse(r#"-t " ""#).unwrap().separator(), Some(b' '));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(
+        KeySpec::parse("-n"),
+        Err(KeySpecError::UnknownOption("-n".to_string()))
+    );
+    assert_eq!(
+        KeySpec::parse("-k"),
+        Err(KeySpecError::MissingValue("-k".to_string()))
+    );
+    assert_eq!(
+        KeySpec::parse("-t ab"),
+        Err(KeySpecError::InvalidSeparator("ab".to_string()))
+    );
+    assert_eq!(
+        KeySpec::parse("-t 'x"),
+        Err(KeySpecError::UnterminatedQuote)
+    );
+    assert!(matches!(
+        KeySpec::parse("-k0"),
+        Err(KeySpecError::InvalidKey { .. })
+    ));
+    assert!(matches!(
+        KeySpec::parse("-k2.0"),
+        Err(KeySpecError::InvalidKey { .. })
+    ));
+    assert!(matches!(
+        KeySpec::parse("-k2n"),
+        Err(KeySpecError::InvalidKey { .. })
+    ));
+    assert!(matches!(
+        KeySpec::parse("-k,3"),
+        Err(KeySpecError::InvalidKey { .. })
+    ));
+    assert_eq!(
+        KeySpec::parse("input.txt"),
+        Err(KeySpecError::UnknownOption("input.txt".to_string()))
+    );
+    assert_eq!(
+        KeySpec::parse("-k 1x").unwrap_err().to_string(),
+        "invalid key '1x': unsupported ordering option"
+    );
+}
+
+/// Generates the whole collation key of `line`, `chunk` bytes per call.
+fn collation_key(spec: &KeySpec, line: &[u8], chunk: usize) -> Vec<u8> {
+    let mut state = Default::default();
+    let mut buf = vec![0; chunk];
+    let mut out = Vec::new();
+    loop {
+        let n = spec.collation_bytes(line, &mut state, &mut buf);
+        out.extend_from_slice(&buf[..n]);
+        if n < chunk {
+            return out;
+        }
+    }
+}
+
+#[test]
+fn test_generation_resumes_at_any_split() {
+    let mut rng = rand::rng();
+    let alphabet = b"aAbB,\0 ";
+    for spec in ["-t , -k2,2r -k3,3f -k1,1", "-k2b -r", "-s -k1.2,1.3"] {
+        let spec = KeySpec::parse(spec).unwrap();
+        for _ in 0..300 {
+            let len = rng.random_range(0..100);
+            let line: Vec<u8> = (0..len)
+                .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+                .collect();
+
+            let whole = collation_key(&spec, &line, 4096);
+            for chunk in (1..=5).chain([31, 32, 64, 65]) {
+                assert_eq!(collation_key(&spec, &line, chunk), whole, "{:?}", line);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_skipping_matches_generation() {
+    let mut rng = rand::rng();
+    let alphabet = b"aAbB,\0 ";
+    for spec in ["-t , -k2,2r -k3,3f -k1,1", "-k2b -r", "-s -k1.2,1.3"] {
+        let spec = KeySpec::parse(spec).unwrap();
+        for _ in 0..100 {
+            let len = rng.random_range(0..100);
+            let line: Vec<u8> = (0..len)
+                .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+                .collect();
+
+            let whole = collation_key(&spec, &line, 4096);
+            for split in 0..=whole.len() + 2 {
+                // Skip in two steps, to also resume a skip that stopped inside an escape.
+                let mut state = Default::default();
+                let first = spec.skip_collation_bytes(&line, &mut state, split / 2);
+                let second = spec.skip_collation_bytes(&line, &mut state, split - split / 2);
+                assert_eq!(first + second, split.min(whole.len()), "{:?}", line);
+
+                let mut rest = vec![0; whole.len() + 1];
+                let n = spec.collation_bytes(&line, &mut state, &mut rest);
+                assert_eq!(rest[..n], whole[split.min(whole.len())..], "{:?}", line);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_long_ties() {
+    let field = "x".repeat(200_000);
+    let lines = [
+        format!("b,{field}\0a"),
+        format!("a,{field}\0a"),
+        format!("c,{field}"),
+    ];
+    let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+    assert!(sorted("-t , -k2", &lines) == vec![lines[2], lines[1], lines[0]]);
+}
@@ -0,0 +1,91 @@
+use orasort::orasort_lcp;
+use rand::prelude::*;
+
+mod common;
+use common::random_keys;
+
+/// Reference LCPs of the sorted keys.
+fn model_lcp(sorted: &[&Vec<u8>]) -> Vec<usize> {
+    (0..sorted.len())
+        .map(|i| match i {
+            0 => 0,
+            _ => sorted[i - 1]
+                .iter()
+                .zip(sorted[i])
+                .take_while(|(a, b)| a == b)
+                .count(),
+        })
+        .collect()
+}
+
+fn check(keys: &[Vec<u8>]) {
+    let (order, lcp) = orasort_lcp(keys);
+    let sorted: Vec<&Vec<u8>> = order.iter().map(|&i| &keys[i]).collect();
+    assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+    assert_eq!(lcp, model_lcp(&sorted));
+}
+
+/// Returns keys made of a random part of `prefix` followed by bytes from `alphabet`.
+fn prefixed_keys(rng: &mut StdRng, count: usize, prefix: &[u8], alphabet: &[u8]) -> Vec<Vec<u8>> {
+    random_keys(rng, count, 12, alphabet)
+        .into_iter()
+        .map(|tail| {
+            let mut key = prefix[..rng.random_range(0..=prefix.len())].to_vec();
+            key.extend(tail);
+            key
+        })
+        .collect()
+}
+
+#[test]
+fn test_lcp_matches_model() {
+    let mut rng = StdRng::seed_from_u64(45);
+
+    for &count in &[0, 1, 2, 31, 500, 3000, 40_000] {
+        for prefix in [&b""[..], b"a/shared/prefix/longer/than/the/cache/"] {
+            // Zero bytes make padded and real prefixes differ.
+            for alphabet in [&b"ab"[..], b"\0\x01a", b"abcdefghijklmnopqrstuvwxyz"] {
+                check(&prefixed_keys(&mut rng, count, prefix, alphabet));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_lcp_of_presorted_input() {
+    let mut rng = StdRng::seed_from_u64(46);
+    let mut keys = prefixed_keys(&mut rng, 10_000, b"log/2024-", b"0123456789");
+    keys.sort();
+    check(&keys);
+
+    keys.reverse();
+    check(&keys);
+
+    // A sorted log with a short unsorted tail appended.
+    keys.reverse();
+    keys.extend(prefixed_keys(&mut rng, 100, b"log/2024-", b"0123456789"));
+    check(&keys);
+}
+
+#[test]
+fn test_lcp_of_duplicates_and_prefixes() {
+    let keys: Vec<Vec<u8>> = ["abc", "ab", "abc", "", "abcdefghijkl", "abcdefghijk", "b"]
+        .iter()
+        .map(|k| k.as_bytes().to_vec())
+        .collect();
+    let (order, lcp) = orasort_lcp(&keys);
+    let sorted: Vec<&[u8]> = order.iter().map(|&i| &keys[i][..]).collect();
+    assert_eq!(
+        sorted,
+        vec![
+            &b""[..],
+            b"ab",
+            b"abc",
+            b"abc",
+            b"abcdefghijk",
+            b"abcdefghijkl",
+            b"b"
+        ]
+    );
+    assert_eq!(lcp, vec![0, 0, 2, 3, 3, 11, 0]);
+}
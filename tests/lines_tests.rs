@@ -0,0 +1,137 @@
+use orasort::lines::{Delimiter, Lines, TrailingDelimiter};
+use orasort::{KeyAccessor, orasort};
+use rand::prelude::*;
+
+const DELIMITERS: [Delimiter; 3] = [Delimiter::Newline, Delimiter::CrLf, Delimiter::Nul];
+const POLICIES: [TrailingDelimiter; 2] =
+    [TrailingDelimiter::Optional, TrailingDelimiter::Separator];
+
+/// Splits `data` the slow way.
+fn model<'a>(data: &'a [u8], delimiter: &[u8], trailing: TrailingDelimiter) -> Vec<&'a [u8]> {
+    let mut lines = Vec::new();
+    let mut rest = data;
+    while let Some(pos) = rest.windows(delimiter.len()).position(|w| w == delimiter) {
+        lines.push(&rest[..pos]);
+        rest = &rest[pos + delimiter.len()..];
+    }
+    if trailing == TrailingDelimiter::Separator || !rest.is_empty() {
+        lines.push(rest);
+    }
+    lines
+}
+
+fn random_buffer(rng: &mut StdRng, len: usize) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            *[b'a', b'b', b'\n', b'\r', b'\0', 0x80, 0x0a ^ 0x80]
+                .choose(rng)
+                .unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn test_split_matches_model() {
+    let mut rng = StdRng::seed_from_u64(49);
+    for len in (0..40).chain([1000, 5000]) {
+        for _ in 0..20 {
+            let data = random_buffer(&mut rng, len);
+            for delimiter in DELIMITERS {
+                for trailing in POLICIES {
+                    let expected = model(&data, delimiter.as_bytes(), trailing);
+                    let lines = Lines::with_options(&data, delimiter, trailing);
+                    assert_eq!(lines.iter().collect::<Vec<_>>(), expected, "{:?}", data);
+
+                    for (index, line) in expected.iter().enumerate() {
+                        assert_eq!(lines.get_key(index), *line);
+                        for offset in [0, 3, 8] {
+                            assert_eq!(
+                                lines.get_u64_prefix(index, offset),
+                                expected.get_u64_prefix(index, offset)
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_sorted_output() {
+    let mut rng = StdRng::seed_from_u64(490);
+    for delimiter in DELIMITERS {
+        for trailing in POLICIES {
+            for terminated in [false, true] {
+                let words: Vec<Vec<u8>> = (0..3000)
+                    .map(|_| {
+                        let len = rng.random_range(0..8);
+                        (0..len).map(|_| rng.random_range(b'a'..=b'f')).collect()
+                    })
+                    .collect();
+                let mut data = words.join(delimiter.as_bytes());
+                if terminated {
+                    data.extend_from_slice(delimiter.as_bytes());
+                }
+
+                let lines = Lines::with_options(&data, delimiter, trailing);
+                let order = lines.sort();
+                let split = model(&data, delimiter.as_bytes(), trailing);
+                assert_eq!(order, orasort(&split));
+
+                let mut sorted = split.clone();
+                sorted.sort();
+                let mut expected = sorted.join(delimiter.as_bytes());
+                if terminated && trailing == TrailingDelimiter::Optional {
+                    expected.extend_from_slice(delimiter.as_bytes());
+                }
+
+                let output = lines.to_sorted_vec(&order);
+                assert_eq!(output.len(), data.len());
+                assert_eq!(output, expected);
+
+                let mut written = Vec::new();
+                lines.write_sorted(&order, &mut written).unwrap();
+                assert_eq!(written, expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_write_sorted_long_lines() {
+    // Lines longer than the internal output buffer are written straight through.
+    let long = vec![b'x'; 200_000];
+    let mut data = long.clone();
+    data.extend_from_slice(b"\na\n");
+    data.extend_from_slice(&long[..70_000]);
+
+    let lines = Lines::new(&data);
+    let order = lines.sort();
+    let mut written = Vec::new();
+    lines.write_sorted(&order, &mut written).unwrap();
+    assert_eq!(written, lines.to_sorted_vec(&order));
+    assert_eq!(&written[..2], b"a\n");
+}
+
+#[test]
+fn test_edge_cases() {
+    let lines = Lines::new(b"");
+    assert!(lines.is_empty());
+    assert!(lines.to_sorted_vec(&lines.sort()).is_empty());
+
+    let lines = Lines::with_options(b"", Delimiter::Newline, TrailingDelimiter::Separator);
+    assert_eq!(lines.len(), 1);
+    assert!(lines.to_sorted_vec(&lines.sort()).is_empty());
+
+    let lines = Lines::new(b"\n\n");
+    assert_eq!(lines.iter().collect::<Vec<_>>(), vec![b"", b""]);
+
+    // A bare `\n` is not a `\r\n` line break.
+    let lines = Lines::with_options(b"b\na\r\nc\r", Delimiter::CrLf, TrailingDelimiter::Optional);
+    assert_eq!(lines.iter().collect::<Vec<_>>(), vec![&b"b\na"[..], b"c\r"]);
+    assert_eq!(lines.to_sorted_vec(&lines.sort()), b"b\na\r\nc\r");
+
+    let lines = Lines::with_options(b"b\0a\0", Delimiter::Nul, TrailingDelimiter::Optional);
+    assert_eq!(lines.to_sorted_vec(&lines.sort()), b"a\0b\0");
+}
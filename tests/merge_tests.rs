@@ -0,0 +1,122 @@
+use orasort::check_sorted;
+use orasort::merge::{LineMerger, merge_files};
+use rand::Rng;
+use std::io;
+use std::path::PathBuf;
+
+fn random_lines(count: usize) -> Vec<Vec<u8>> {
+    let mut rng = rand::rng();
+    (0..count)
+        .map(|_| {
+            let len = rng.random_range(0..20);
+            (0..len).map(|_| rng.random_range(b'a'..b'e')).collect()
+        })
+        .collect()
+}
+
+fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("orasort-merge-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn test_check_sorted() {
+    let empty: Vec<&str> = Vec::new();
+    assert_eq!(check_sorted(&empty), None);
+    assert_eq!(check_sorted(&vec!["only"]), None);
+
+    // Ties within the cached prefix are resolved by the remaining bytes and lengths.
+    assert_eq!(
+        check_sorted(&vec!["prefix00", "prefix00a", "prefix00a"]),
+        None
+    );
+    assert_eq!(check_sorted(&vec!["prefix00a", "prefix00"]), Some(1));
+    assert_eq!(check_sorted(&vec!["a", "a\0"]), None);
+    assert_eq!(check_sorted(&vec!["a\0", "a"]), Some(1));
+
+    let mut lines = random_lines(5_000);
+    lines.sort();
+    assert_eq!(check_sorted(&lines), None);
+
+    for _ in 0..20 {
+        let at = rand::rng().random_range(1..lines.len());
+        let mut shuffled = lines.clone();
+        shuffled[at] = b"a".to_vec();
+        shuffled[at - 1] = b"e".to_vec();
+
+        let expected = (1..shuffled.len()).find(|&i| shuffled[i - 1] > shuffled[i]);
+        assert_eq!(check_sorted(&shuffled), expected);
+    }
+}
+
+#[test]
+fn test_merge_files_matches_sort() {
+    let mut all = Vec::new();
+    let mut paths = Vec::new();
+    for shard in 0..5 {
+        let mut lines = random_lines(2_000 + shard * 100);
+        lines.sort();
+        let mut contents = lines.join(&b'\n');
+        // The last shard has no final newline.
+        if shard != 4 {
+            contents.push(b'\n');
+        }
+        paths.push(temp_file(&format!("shard-{}.txt", shard), &contents));
+        all.extend(lines);
+    }
+    all.sort();
+
+    let mut output = Vec::new();
+    let written = merge_files(&paths, &mut output).unwrap();
+
+    let expected: Vec<u8> = all
+        .iter()
+        .flat_map(|line| [&line[..], b"\n"].concat())
+        .collect();
+    assert_eq!(written, all.len() as u64);
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_line_merger_options() {
+    let inputs = vec![&b"b\0b\0d\0"[..], &b""[..], &b"a\0b\0c"[..]];
+    let mut output = Vec::new();
+    let written = LineMerger::new()
+        .delimiter(b'\0')
+        .unique(true)
+        .merge(inputs, &mut output)
+        .unwrap();
+    assert_eq!(output, b"a\0b\0c\0d\0");
+    assert_eq!(written, 4);
+
+    // Equal lines are taken from earlier inputs first.
+    let inputs = vec![&b"1 x\n2 x\n"[..], &b"1 y\n3 y\n"[..]];
+    let mut output = Vec::new();
+    LineMerger::new()
+        .compare_by(|a: &[u8], b: &[u8]| a[0].cmp(&b[0]))
+        .merge(inputs, &mut output)
+        .unwrap();
+    assert_eq!(output, b"1 x\n1 y\n2 x\n3 y\n");
+
+    let mut output = Vec::new();
+    assert_eq!(
+        LineMerger::new()
+            .merge(Vec::<&[u8]>::new(), &mut output)
+            .unwrap(),
+        0
+    );
+    assert!(output.is_empty());
+}
+
+#[test]
+fn test_merge_files_names_missing_input() {
+    let present = temp_file("present.txt", b"a\n");
+    let missing = present.with_file_name("missing.txt");
+
+    let error = merge_files(&[&present, &missing], io::sink()).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    assert!(error.to_string().contains("missing.txt"));
+}
@@ -0,0 +1,154 @@
+#![cfg(feature = "mmap")]
+
+use orasort::mmap::MmapKeys;
+use orasort::{KeyAccessor, OffsetsError, orasort};
+use rand::prelude::*;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// A scratch directory removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("orasort-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    fn write(&self, name: &str, bytes: &[u8]) -> PathBuf {
+        let path = self.0.join(name);
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Concatenates `keys` after `skip` unused bytes and returns the data and its offsets.
+fn layout(keys: &[Vec<u8>], skip: usize) -> (Vec<u8>, Vec<u64>) {
+    let mut data = vec![b'#'; skip];
+    let mut offsets = vec![skip as u64];
+    for key in keys {
+        data.extend_from_slice(key);
+        offsets.push(data.len() as u64);
+    }
+    (data, offsets)
+}
+
+fn encode32(offsets: &[u64]) -> Vec<u8> {
+    offsets
+        .iter()
+        .flat_map(|&o| (o as u32).to_le_bytes())
+        .collect()
+}
+
+fn encode64(offsets: &[u64]) -> Vec<u8> {
+    offsets.iter().flat_map(|&o| o.to_le_bytes()).collect()
+}
+
+fn check<T: KeyAccessor>(mapped: &T, keys: &[Vec<u8>]) {
+    assert_eq!(mapped.len(), keys.len());
+    for (index, key) in keys.iter().enumerate() {
+        assert_eq!(mapped.get_key(index), &key[..]);
+        for offset in 0..key.len() + 2 {
+            assert_eq!(
+                mapped.get_u64_prefix(index, offset),
+                keys.get_u64_prefix(index, offset),
+                "key {} offset {}",
+                index,
+                offset
+            );
+        }
+    }
+    assert_eq!(orasort(mapped), orasort(keys));
+}
+
+#[test]
+fn test_both_offset_widths() {
+    let dir = TempDir::new("mmap-widths");
+    let mut rng = StdRng::seed_from_u64(47);
+    let keys: Vec<Vec<u8>> = (0..3000)
+        .map(|_| {
+            let len = rng.random_range(0..14);
+            (0..len).map(|_| rng.random_range(b'a'..=b'd')).collect()
+        })
+        .collect();
+
+    for skip in [0, 5] {
+        let (data, offsets) = layout(&keys, skip);
+        let data_path = dir.write("keys.data", &data);
+
+        let offsets32 = dir.write("keys.offsets32", &encode32(&offsets));
+        let mapped: MmapKeys = MmapKeys::open(&offsets32, &data_path).unwrap();
+        check(&mapped, &keys);
+        assert_eq!(mapped.data(), &data[..]);
+
+        let offsets64 = dir.write("keys.offsets64", &encode64(&offsets));
+        let mapped: MmapKeys<u64> = MmapKeys::open(&offsets64, &data_path).unwrap();
+        check(&mapped, &keys);
+    }
+
+    // Keys ending right at the end of the data, shorter than a prefix.
+    let keys = vec![b"b".to_vec(), b"".to_vec(), b"abc".to_vec()];
+    let (data, offsets) = layout(&keys, 0);
+    let mapped: MmapKeys = MmapKeys::from_files(
+        &std::fs::File::open(dir.write("short.offsets", &encode32(&offsets))).unwrap(),
+        &std::fs::File::open(dir.write("short.data", &data)).unwrap(),
+    )
+    .unwrap();
+    check(&mapped, &keys);
+
+    // No keys at all.
+    let mapped: MmapKeys = MmapKeys::open(
+        dir.write("none.offsets", &encode32(&[0])),
+        dir.write("none.data", b""),
+    )
+    .unwrap();
+    assert!(mapped.is_empty());
+}
+
+#[test]
+fn test_invalid_offsets() {
+    let dir = TempDir::new("mmap-invalid");
+    let data = dir.write("keys.data", b"abcdef");
+
+    let error_of = |offsets: &[u8]| {
+        let path = dir.write("keys.offsets", offsets);
+        let error = MmapKeys::<u32>::open(&path, &data).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        *error
+            .into_inner()
+            .unwrap()
+            .downcast::<OffsetsError>()
+            .unwrap()
+    };
+
+    assert_eq!(
+        error_of(&encode32(&[0, 3, 2, 6])),
+        OffsetsError::Decreasing { index: 2 }
+    );
+    assert_eq!(
+        error_of(&encode32(&[0, 3, 7])),
+        OffsetsError::PastEnd {
+            offset: 7,
+            data_len: 6
+        }
+    );
+    assert_eq!(
+        error_of(&[0, 0, 0, 0, 0]),
+        OffsetsError::InvalidLength { len: 5, width: 4 }
+    );
+    assert_eq!(
+        error_of(b""),
+        OffsetsError::InvalidLength { len: 0, width: 4 }
+    );
+
+    let error = MmapKeys::<u32>::open(dir.0.join("missing.offsets"), &data).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::NotFound);
+    assert!(error.to_string().contains("missing.offsets"));
+}
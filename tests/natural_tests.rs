@@ -0,0 +1,350 @@
+use orasort::collate::{Collator, orasort_collated, orasort_collated_mut};
+use orasort::natural::NaturalCollator;
+use orasort::prelude::*;
+use rand::Rng;
+use std::cmp::Ordering;
+
+/// Reference natural comparison: digit runs by numeric value, then, between otherwise equal
+/// keys, the leading zeros of each digit run (fewer first).
+fn natural_cmp(a: &[u8], b: &[u8]) -> Ordering {
+    let leading_zeros = |key: &[u8]| -> Vec<usize> {
+        key.split(|c| !c.is_ascii_digit())
+            .filter(|run| !run.is_empty())
+            .map(|run| run.iter().take_while(|&&c| c == b'0').count())
+            .collect()
+    };
+    numeric_cmp(a, b).then_with(|| leading_zeros(a).cmp(&leading_zeros(b)))
+}
+
+/// Compares digit runs by numeric value only.
+fn numeric_cmp(a: &[u8], b: &[u8]) -> Ordering {
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i].is_ascii_digit() && b[j].is_ascii_digit() {
+            let ea = i + a[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+            let eb = j + b[j..].iter().take_while(|c| c.is_ascii_digit()).count();
+            let za = a[i..ea].iter().take_while(|&&c| c == b'0').count();
+            let zb = b[j..eb].iter().take_while(|&&c| c == b'0').count();
+            let (na, nb) = (&a[i + za..ea], &b[j + zb..eb]);
+            let ord = na.len().cmp(&nb.len()).then(na.cmp(nb));
+            if ord != Ordering::Equal {
+                return ord;
+            }
+            i = ea;
+            j = eb;
+        } else if a[i].is_ascii_digit() || b[j].is_ascii_digit() {
+            return a[i].cmp(&b[j]);
+        } else {
+            if a[i] != b[j] {
+                return a[i].cmp(&b[j]);
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+    (a.len() - i).cmp(&(b.len() - j))
+}
+
+#[test]
+fn test_natural_filenames() {
+    let mut data = vec![
+        "file10.txt",
+        "file9.txt",
+        "file1.txt",
+        "file.txt",
+        "file100.txt",
+        "file09.txt",
+        "file1a.txt",
+    ];
+    orasort_natural_mut(&mut data);
+    assert_eq!(
+        data,
+        vec![
+            "file.txt",
+            "file1.txt",
+            "file1a.txt",
+            "file9.txt",
+            "file09.txt",
+            "file10.txt",
+            "file100.txt",
+        ]
+    );
+}
+
+#[test]
+fn test_natural_hostnames() {
+    let input: Vec<String> = (1..=200).rev().map(|i| format!("node-{}", i)).collect();
+    let indices = orasort_natural(&input);
+
+    let sorted: Vec<&String> = indices.iter().map(|&i| &input[i]).collect();
+    let expected: Vec<String> = (1..=200).map(|i| format!("node-{}", i)).collect();
+    assert_eq!(sorted, expected.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_natural_arbitrary_length_numbers() {
+    let mut data = vec![
+        "id-123456789012345678901234567890",
+        "id-99999999999999999999",
+        "id-0000000000000000000000000000000000001",
+        "id-123456789012345678901234567891",
+    ];
+    orasort_natural_mut(&mut data);
+    assert_eq!(
+        data,
+        vec![
+            "id-0000000000000000000000000000000000001",
+            "id-99999999999999999999",
+            "id-123456789012345678901234567890",
+            "id-123456789012345678901234567891",
+        ]
+    );
+}
+
+#[test]
+fn test_natural_matches_reference() {
+    let mut rng = rand::rng();
+    let alphabet = b"ab-\x000123456789";
+    let input: Vec<Vec<u8>> = (0..3_000)
+        .map(|_| {
+            let len = rng.random_range(0..24);
+            (0..len)
+                .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+                .collect()
+        })
+        .collect();
+
+    let indices = orasort_natural(&input);
+
+    let mut expected = input.clone();
+    expected.sort_by(|a, b| natural_cmp(a, b));
+
+    let actual: Vec<Vec<u8>> = indices.iter().map(|&i| input[i].clone()).collect();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_leading_zeros_break_ties_last() {
+    let mut data = vec![
+        "01a", "1b", "001a", "1a", "a01b1", "a1b01", "a1b1", "1a\0", "01a",
+    ];
+    orasort_natural_mut(&mut data);
+    assert_eq!(
+        data,
+        vec![
+            "1a", "01a", "01a", "001a", "1a\0", "1b", "a1b1", "a1b01", "a01b1"
+        ]
+    );
+
+    let data = vec!["1.0.0-rc.01", "01.0.0", "1.0.0", "1.0.0-rc.1+build"];
+    let indices = orasort_collated(&data, &NaturalCollator::semver());
+    assert_eq!(indices, vec![3, 0, 2, 1]);
+}
+
+#[test]
+fn test_semver_precedence() {
+    // Precedence example from the Semantic Versioning specification, shuffled.
+    let expected = vec![
+        "1.0.0-alpha",
+        "1.0.0-alpha.1",
+        "1.0.0-alpha.beta",
+        "1.0.0-beta",
+        "1.0.0-beta.2",
+        "1.0.0-beta.11",
+        "1.0.0-rc.1",
+        "1.0.0",
+        "1.0.1",
+        "1.2.0",
+        "1.10.0",
+        "2.0.0",
+    ];
+    let mut data = expected.clone();
+    data.reverse();
+    data.swap(2, 7);
+
+    orasort_collated_mut(&mut data, &NaturalCollator::semver());
+    assert_eq!(data, expected);
+}
+
+#[test]
+fn test_semver_pre_release_identifiers() {
+    let mut data = vec!["1.0.0-1a", "1.0.0-2"];
+    orasort_collated_mut(&mut data, &NaturalCollator::semver());
+    assert_eq!(data, vec!["1.0.0-2", "1.0.0-1a"]);
+
+    let mut data = vec!["1.0.0-x-y", "1.0.0-x.y", "1.0.0-x"];
+    orasort_collated_mut(&mut data, &NaturalCollator::semver());
+    assert_eq!(data, vec!["1.0.0-x", "1.0.0-x.y", "1.0.0-x-y"]);
+
+    // Alphanumeric identifiers compare bytewise, digits included.
+    let mut data = vec!["1.0.0-rc2", "1.0.0-rc10", "1.0.0-rc.2", "1.0.0-rc.10"];
+    orasort_collated_mut(&mut data, &NaturalCollator::semver());
+    assert_eq!(
+        data,
+        vec!["1.0.0-rc.2", "1.0.0-rc.10", "1.0.0-rc10", "1.0.0-rc2"]
+    );
+}
+
+/// Reference semver precedence for versions without leading zeros.
+fn semver_cmp(a: &str, b: &str) -> Ordering {
+    fn parse(version: &str) -> (Vec<u64>, Option<Vec<&str>>) {
+        let version = version.split('+').next().unwrap();
+        let (core, pre_release) = match version.split_once('-') {
+            Some((core, pre_release)) => (core, Some(pre_release.split('.').collect())),
+            None => (version, None),
+        };
+        (
+            core.split('.').map(|n| n.parse().unwrap()).collect(),
+            pre_release,
+        )
+    }
+    fn identifier_cmp(a: &str, b: &str) -> Ordering {
+        match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => a.cmp(b),
+        }
+    }
+
+    let ((core_a, pre_a), (core_b, pre_b)) = (parse(a), parse(b));
+    core_a.cmp(&core_b).then_with(|| match (pre_a, pre_b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(pre_a), Some(pre_b)) => pre_a
+            .iter()
+            .zip(&pre_b)
+            .map(|(a, b)| identifier_cmp(a, b))
+            .find(|ord| ord.is_ne())
+            .unwrap_or_else(|| pre_a.len().cmp(&pre_b.len())),
+    })
+}
+
+#[test]
+fn test_semver_matches_reference() {
+    let mut rng = rand::rng();
+    let identifiers = [
+        "0", "1", "2", "10", "a", "b", "1a", "a1", "x-y", "x", "alpha", "rc", "rc10", "rc2",
+    ];
+    let input: Vec<String> = (0..3_000)
+        .map(|_| {
+            let mut version = format!(
+                "{}.{}.{}",
+                rng.random_range(0..3),
+                rng.random_range(0..12),
+                rng.random_range(0..3)
+            );
+            if rng.random_bool(0.8) {
+                let count = rng.random_range(1..4);
+                let pre_release: Vec<&str> = (0..count)
+                    .map(|_| identifiers[rng.random_range(0..identifiers.len())])
+                    .collect();
+                version = format!("{version}-{}", pre_release.join("."));
+            }
+            if rng.random_bool(0.2) {
+                version.push_str("+build.7");
+            }
+            version
+        })
+        .collect();
+
+    let indices = orasort_collated(&input, &NaturalCollator::semver());
+
+    let actual: Vec<&String> = indices.iter().map(|&i| &input[i]).collect();
+    for pair in actual.windows(2) {
+        assert_ne!(semver_cmp(pair[0], pair[1]), Ordering::Greater, "{pair:?}");
+    }
+}
+
+#[test]
+fn test_semver_ignores_build_metadata() {
+    let data = vec!["1.0.0+build.9", "1.0.0-rc.1+build.1", "0.9.0+zzz"];
+    let indices = orasort_collated(&data, &NaturalCollator::semver());
+    assert_eq!(indices, vec![2, 1, 0]);
+}
+
+/// Generates the whole collation key of `key`, `chunk` bytes per call.
+fn collation_key<C: Collator>(collator: &C, key: &[u8], chunk: usize) -> Vec<u8> {
+    let mut state = C::State::default();
+    let mut buf = vec![0; chunk];
+    let mut out = Vec::new();
+    loop {
+        let n = collator.collation_bytes(key, &mut state, &mut buf);
+        out.extend_from_slice(&buf[..n]);
+        if n < chunk {
+            assert_eq!(collator.collation_bytes(key, &mut state, &mut buf), 0);
+            return out;
+        }
+    }
+}
+
+#[test]
+fn test_generation_resumes_at_any_split() {
+    let mut rng = rand::rng();
+    let alphabet = b"a-+.\x00000123456789";
+    for collator in [NaturalCollator::new(), NaturalCollator::semver()] {
+        for _ in 0..500 {
+            let len = rng.random_range(0..40);
+            let key: Vec<u8> = (0..len)
+                .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+                .collect();
+
+            let whole = collation_key(&collator, &key, 4096);
+            for chunk in (1..=10).chain([31, 32]) {
+                assert_eq!(collation_key(&collator, &key, chunk), whole, "{:?}", key);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_skipping_matches_generation() {
+    let mut rng = rand::rng();
+    let alphabet = b"a-+.\x00000123456789";
+    for collator in [NaturalCollator::new(), NaturalCollator::semver()] {
+        for _ in 0..300 {
+            let len = rng.random_range(0..40);
+            let key: Vec<u8> = (0..len)
+                .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+                .collect();
+
+            let whole = collation_key(&collator, &key, 4096);
+            for split in 0..=whole.len() + 2 {
+                // Skip in two steps, to also resume a skip that stopped inside a piece.
+                let mut state = Default::default();
+                let first = collator.skip_collation_bytes(&key, &mut state, split / 2);
+                let second = collator.skip_collation_bytes(&key, &mut state, split - split / 2);
+                assert_eq!(first + second, split.min(whole.len()), "{:?}", key);
+
+                let mut rest = vec![0; whole.len() + 1];
+                let n = collator.collation_bytes(&key, &mut state, &mut rest);
+                assert_eq!(rest[..n], whole[split.min(whole.len())..], "{:?}", key);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_long_ties() {
+    // Long digit runs and plain runs that tie far beyond one generated chunk.
+    let digits = "7".repeat(200_000);
+    let text = "x".repeat(200_000);
+    let mut data = vec![
+        format!("{digits}9"),
+        format!("{digits}8"),
+        format!("{text}b"),
+        format!("{text}a"),
+        format!("0{digits}8"),
+    ];
+    orasort_natural_mut(&mut data);
+
+    let expected = vec![
+        format!("{digits}8"),
+        format!("0{digits}8"),
+        format!("{digits}9"),
+        format!("{text}a"),
+        format!("{text}b"),
+    ];
+    assert!(data == expected);
+}
@@ -0,0 +1,64 @@
+use orasort::core::SortPtr;
+use orasort::{OrasortError, orasort_in, required_scratch};
+use rand::Rng;
+use std::mem::MaybeUninit;
+
+mod common;
+use common::{ANY_BYTE, allocations, assert_sorted, random_keys};
+
+#[global_allocator]
+static GLOBAL: common::CountingAlloc = common::CountingAlloc;
+
+#[test]
+fn test_orasort_in_without_allocating() {
+    for count in [0, 1, 31, 32, 33, 1_000, 20_000] {
+        let input = random_keys(&mut rand::rng(), count, 20, &ANY_BYTE);
+        let mut indices: Vec<usize> = (0..count).collect();
+        let mut scratch = vec![MaybeUninit::<SortPtr>::uninit(); required_scratch(count)];
+
+        let before = allocations();
+        orasort_in(&input, &mut indices, &mut scratch).unwrap();
+        assert_eq!(allocations(), before);
+
+        assert_sorted(&input, &indices);
+    }
+}
+
+#[test]
+fn test_orasort_in_long_common_prefixes() {
+    // Forces many nested radix passes, whose work stack lives in `indices`.
+    let mut rng = rand::rng();
+    let input: Vec<Vec<u8>> = (0..50_000)
+        .map(|_| {
+            let mut key = b"shared/prefix/".to_vec();
+            key.extend((0..rng.random_range(0..12)).map(|_| rng.random_range(b'a'..b'e')));
+            key
+        })
+        .collect();
+    let mut indices: Vec<usize> = (0..input.len()).rev().collect();
+    let mut scratch = vec![MaybeUninit::uninit(); required_scratch(input.len())];
+
+    orasort_in(&input, &mut indices, &mut scratch).unwrap();
+    assert_sorted(&input, &indices);
+}
+
+#[test]
+fn test_orasort_in_scratch_too_small() {
+    let input = random_keys(&mut rand::rng(), 100, 8, &ANY_BYTE);
+    let mut indices: Vec<usize> = (0..100).collect();
+    let mut scratch = vec![MaybeUninit::uninit(); 150];
+
+    let err = orasort_in(&input, &mut indices, &mut scratch).unwrap_err();
+    assert_eq!(
+        err,
+        OrasortError::ScratchTooSmall {
+            required: 200,
+            provided: 150
+        }
+    );
+    assert_eq!(indices, (0..100).collect::<Vec<_>>());
+
+    // Small inputs need no scratch at all.
+    let mut small: Vec<usize> = (0..10).collect();
+    orasort_in(&input, &mut small, &mut []).unwrap();
+}
@@ -0,0 +1,103 @@
+use orasort::prelude::*;
+use orasort::{OrasortError, Permutation};
+use rand::prelude::*;
+
+fn random_permutation(rng: &mut StdRng, len: usize) -> Permutation {
+    let mut indices: Vec<usize> = (0..len).collect();
+    indices.shuffle(rng);
+    Permutation::try_from(indices).unwrap()
+}
+
+#[test]
+fn test_apply_to_matches_gather() {
+    let mut rng = StdRng::seed_from_u64(34);
+
+    for &len in &[0, 1, 2, 63, 64, 65, 1000] {
+        let permutation = random_permutation(&mut rng, len);
+        let data: Vec<String> = (0..len).map(|i| format!("item-{}", i)).collect();
+
+        let gathered = permutation.gather(&data);
+        let mut applied = data.clone();
+        permutation.apply_to(&mut applied);
+
+        assert_eq!(applied, gathered);
+        for (i, &index) in permutation.iter().enumerate() {
+            assert_eq!(applied[i], data[index]);
+        }
+    }
+}
+
+#[test]
+fn test_apply_to_many_columns() {
+    let mut rng = StdRng::seed_from_u64(340);
+    let keys: Vec<String> = (0..500)
+        .map(|_| format!("{:04}", rng.random_range(0..10_000)))
+        .collect();
+    let mut ids: Vec<u32> = (0..500).collect();
+    let mut names = keys.clone();
+    let mut flags: Vec<bool> = (0..500).map(|i| i % 3 == 0).collect();
+    let expected_flags: Vec<bool> = flags.clone();
+
+    let permutation = orasort(&keys);
+    permutation.apply_to_many((&mut ids, &mut names, flags.as_mut_slice()));
+
+    assert!(names.windows(2).all(|w| w[0] <= w[1]));
+    for (i, &id) in ids.iter().enumerate() {
+        assert_eq!(names[i], keys[id as usize]);
+        assert_eq!(flags[i], expected_flags[id as usize]);
+    }
+}
+
+#[test]
+#[should_panic(expected = "same length")]
+fn test_apply_to_many_rejects_mismatched_columns() {
+    let permutation = Permutation::identity(3);
+    let mut a = vec![1, 2, 3];
+    let mut b = vec![1, 2];
+    permutation.apply_to_many((&mut a, &mut b));
+}
+
+#[test]
+fn test_inverse_gives_ranks() {
+    let data = vec!["delta", "alpha", "charlie", "bravo"];
+    let permutation = orasort(&data);
+    let ranks = permutation.inverse();
+
+    assert_eq!(ranks, vec![3, 0, 2, 1]);
+    assert!(permutation.compose(&ranks).is_identity());
+    assert!(ranks.compose(&permutation).is_identity());
+    assert_eq!(ranks.inverse(), permutation);
+}
+
+#[test]
+fn test_compose_applies_in_order() {
+    let mut rng = StdRng::seed_from_u64(3400);
+    let first = random_permutation(&mut rng, 300);
+    let second = random_permutation(&mut rng, 300);
+    let data: Vec<usize> = (0..300).map(|i| i * 7).collect();
+
+    let mut twice = data.clone();
+    first.apply_to(&mut twice);
+    second.apply_to(&mut twice);
+
+    assert_eq!(first.compose(&second).gather(&data), twice);
+}
+
+#[test]
+fn test_identity_and_validation() {
+    assert!(Permutation::identity(5).is_identity());
+    assert!(Permutation::default().is_identity());
+    assert!(!Permutation::try_from(vec![1, 0]).unwrap().is_identity());
+
+    assert_eq!(
+        Permutation::try_from(vec![0, 3, 1]),
+        Err(OrasortError::IndexOutOfRange { index: 3, len: 3 })
+    );
+    assert_eq!(
+        Permutation::try_from(vec![2, 0, 2]),
+        Err(OrasortError::DuplicateIndex { index: 2 })
+    );
+
+    let indices: Vec<usize> = orasort(&vec!["b", "a"]).into();
+    assert_eq!(indices, vec![1, 0]);
+}
@@ -0,0 +1,208 @@
+use orasort::persist::{PackedSlice, PermutationFile, PermutationView, fingerprint};
+use orasort::{PersistError, orasort, orasort_lcp};
+use rand::prelude::*;
+
+mod common;
+use common::random_keys;
+
+/// Copies `bytes` into a buffer at an address `offset` bytes past an 8-byte boundary.
+fn placed(bytes: &[u8], offset: usize) -> (Vec<u8>, usize) {
+    let mut buffer = vec![0; bytes.len() + 16];
+    let start = (0..8)
+        .find(|&s| (buffer.as_ptr() as usize + s) % 8 == offset)
+        .unwrap();
+    buffer[start..start + bytes.len()].copy_from_slice(bytes);
+    buffer.truncate(start + bytes.len());
+    (buffer, start)
+}
+
+const ALPHABET: &[u8] = b"abc";
+const MAX_LEN: usize = 20;
+
+#[test]
+fn test_round_trip() {
+    let mut rng = StdRng::seed_from_u64(46);
+
+    for count in [0, 1, 7, 1000] {
+        let keys = random_keys(&mut rng, count, MAX_LEN, ALPHABET);
+        let (order, lcp) = orasort_lcp(&keys);
+
+        for (prefixes, with_lcp) in [(false, false), (true, false), (false, true), (true, true)] {
+            let mut file = PermutationFile::new(&keys, &order);
+            if prefixes {
+                file = file.with_prefixes();
+            }
+            if with_lcp {
+                file = file.with_lcp(&lcp);
+            }
+            let bytes = file.to_vec();
+            assert_eq!(bytes.len() % 8, 0);
+
+            let mut written = Vec::new();
+            file.write(&mut written).unwrap();
+            assert_eq!(written, bytes);
+
+            let (buffer, start) = placed(&bytes, 0);
+            let view = PermutationView::new(&buffer[start..], &keys).unwrap();
+            assert_eq!(view.len(), count);
+            assert_eq!(view.fingerprint(), fingerprint(&keys));
+            assert_eq!(view.to_permutation(), order);
+            assert!(matches!(view.indices(), PackedSlice::U32(_)));
+
+            match view.prefixes() {
+                None => assert!(!prefixes),
+                Some(saved) => {
+                    let expected: Vec<u64> = order
+                        .iter()
+                        .map(|&i| {
+                            let mut prefix = [0; 8];
+                            let n = keys[i].len().min(8);
+                            prefix[..n].copy_from_slice(&keys[i][..n]);
+                            u64::from_be_bytes(prefix)
+                        })
+                        .collect();
+                    assert_eq!(saved, &expected[..]);
+                }
+            }
+            match view.lcp() {
+                None => assert!(!with_lcp),
+                Some(saved) => assert_eq!(saved.to_vec(), lcp),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_stale_files_are_refused() {
+    let keys = vec!["delta", "alpha", "charlie", "bravo"];
+    let bytes = PermutationFile::new(&keys, &orasort(&keys)).to_vec();
+    let (buffer, start) = placed(&bytes, 0);
+    let data = &buffer[start..];
+
+    assert!(PermutationView::new(data, &keys).is_ok());
+    for changed in [
+        vec!["delta", "alpha", "charlie", "bravO"],
+        vec!["delta", "alpha", "charlie"],
+        vec!["delta", "alpha", "charlie", "bravo", ""],
+        vec!["alpha", "delta", "charlie", "bravo"],
+        vec!["delt", "aalpha", "charlie", "bravo"],
+    ] {
+        assert_eq!(
+            PermutationView::new(data, &changed).unwrap_err(),
+            PersistError::Stale
+        );
+    }
+}
+
+#[test]
+fn test_damaged_files() {
+    let mut rng = StdRng::seed_from_u64(47);
+    let keys = random_keys(&mut rng, 300, MAX_LEN, ALPHABET);
+    let (order, lcp) = orasort_lcp(&keys);
+    let bytes = PermutationFile::new(&keys, &order)
+        .with_prefixes()
+        .with_lcp(&lcp)
+        .to_vec();
+    let load = |bytes: &[u8]| {
+        let (buffer, start) = placed(bytes, 0);
+        PermutationView::new(&buffer[start..], &keys).map(|_| ())
+    };
+
+    assert_eq!(load(b"short"), Err(PersistError::NotAPermutationFile));
+    assert_eq!(load(&bytes[..bytes.len() - 8]), Err(PersistError::Corrupt));
+
+    let mut damaged = bytes.clone();
+    damaged[8] = 2;
+    assert_eq!(load(&damaged), Err(PersistError::UnsupportedVersion(2)));
+
+    let mut damaged = bytes.clone();
+    damaged[12..16].reverse();
+    assert_eq!(load(&damaged), Err(PersistError::ByteOrderMismatch));
+
+    for _ in 0..200 {
+        let mut damaged = bytes.clone();
+        let at = rng.random_range(64..damaged.len());
+        damaged[at] ^= 1 << rng.random_range(0..8);
+        assert_eq!(load(&damaged), Err(PersistError::ChecksumMismatch));
+    }
+
+    let (buffer, start) = placed(&bytes, 4);
+    assert_eq!(
+        PermutationView::new(&buffer[start..], &keys).unwrap_err(),
+        PersistError::Misaligned
+    );
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_mapped_permutation() {
+    use orasort::persist::MappedPermutation;
+    use std::io::ErrorKind;
+
+    let mut rng = StdRng::seed_from_u64(48);
+    let keys = random_keys(&mut rng, 5000, MAX_LEN, ALPHABET);
+    let order = orasort(&keys);
+
+    let path = std::env::temp_dir().join(format!("orasort-{}.perm", std::process::id()));
+    PermutationFile::new(&keys, &order)
+        .write(std::fs::File::create(&path).unwrap())
+        .unwrap();
+
+    let saved = MappedPermutation::open(&path, &keys).unwrap();
+    assert_eq!(saved.view().to_permutation(), order);
+    assert!(saved.view().prefixes().is_none());
+
+    let mut changed = keys.clone();
+    changed[17].push(b'!');
+    let error = MappedPermutation::open(&path, &changed).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert_eq!(
+        error.get_ref().unwrap().downcast_ref::<PersistError>(),
+        Some(&PersistError::Stale)
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// CRC-32 (IEEE 802.3), as used for the file checksum.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[test]
+fn test_wide_indices_are_checked() {
+    let keys = vec!["b", "a"];
+    let bytes = PermutationFile::new(&keys, &orasort(&keys)).to_vec();
+
+    // Rewrite the file with 8-byte indices.
+    let wide = |indices: [u64; 2]| {
+        let mut file = bytes[..64].to_vec();
+        file[32] = 8;
+        file[40..44].fill(0);
+        for index in indices {
+            file.extend_from_slice(&index.to_ne_bytes());
+        }
+        let crc = crc32(&file);
+        file[40..44].copy_from_slice(&crc.to_ne_bytes());
+        file
+    };
+    let load = |bytes: &[u8]| {
+        let (buffer, start) = placed(bytes, 0);
+        PermutationView::new(&buffer[start..], &keys).map(|view| view.to_permutation())
+    };
+
+    assert_eq!(load(&wide([1, 0])), Ok(orasort(&keys)));
+    // Must not be read as index 1 where `usize` is 32 bits wide.
+    assert_eq!(load(&wide([(1 << 32) + 1, 0])), Err(PersistError::Corrupt));
+}
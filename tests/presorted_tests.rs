@@ -0,0 +1,141 @@
+use orasort::core::{KeyAccessor, SPLICE_PREFIX_SIZE};
+use orasort::orasort_slice;
+use orasort::prelude::*;
+use rand::Rng;
+use std::cell::Cell;
+
+mod common;
+use common::{ANY_BYTE, assert_sorted, random_keys};
+
+/// Keys stored as owned strings, counting full key fetches and prefix loads separately.
+struct CountingKeys {
+    keys: Vec<Vec<u8>>,
+    key_fetches: Cell<usize>,
+    prefix_loads: Cell<usize>,
+}
+
+impl CountingKeys {
+    fn new(keys: Vec<Vec<u8>>) -> Self {
+        Self {
+            keys,
+            key_fetches: Cell::new(0),
+            prefix_loads: Cell::new(0),
+        }
+    }
+}
+
+impl KeyAccessor for CountingKeys {
+    fn get_key(&self, index: usize) -> &[u8] {
+        self.key_fetches.set(self.key_fetches.get() + 1);
+        &self.keys[index]
+    }
+
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn get_u64_prefix(&self, index: usize, offset: usize) -> u64 {
+        self.prefix_loads.set(self.prefix_loads.get() + 1);
+        let key = &self.keys[index];
+        let mut buf = [0u8; SPLICE_PREFIX_SIZE];
+        if offset < key.len() {
+            let n = (key.len() - offset).min(SPLICE_PREFIX_SIZE);
+            buf[..n].copy_from_slice(&key[offset..offset + n]);
+        }
+        u64::from_be_bytes(buf)
+    }
+}
+
+#[test]
+fn test_sorted_input_is_one_linear_scan() {
+    let keys: Vec<Vec<u8>> = (0..10_000)
+        .map(|i| format!("{:08}", i).into_bytes())
+        .collect();
+    let provider = CountingKeys::new(keys);
+
+    let indices = orasort(&provider);
+
+    assert_eq!(indices, (0..10_000).collect::<Vec<_>>());
+    assert_eq!(provider.prefix_loads.get(), 10_000);
+    assert_eq!(provider.key_fetches.get(), 0);
+}
+
+#[test]
+fn test_reverse_sorted_input_is_one_linear_scan() {
+    let keys: Vec<Vec<u8>> = (0..10_000)
+        .rev()
+        .map(|i| format!("{:08}", i).into_bytes())
+        .collect();
+    let provider = CountingKeys::new(keys);
+
+    let indices = orasort(&provider);
+
+    assert_eq!(indices, (0..10_000).rev().collect::<Vec<_>>());
+    assert_eq!(provider.prefix_loads.get(), 10_000);
+    assert_eq!(provider.key_fetches.get(), 0);
+}
+
+#[test]
+fn test_sorted_with_duplicates_and_long_prefixes() {
+    let prefix = "p".repeat(40);
+    let input: Vec<Vec<u8>> = (0..5_000)
+        .map(|i| format!("{}{:05}", prefix, i / 3).into_bytes())
+        .collect();
+
+    let indices = orasort(&input);
+    assert_sorted(&input, &indices);
+
+    let mut reversed = input.clone();
+    reversed.reverse();
+    let indices = orasort(&reversed);
+    assert_sorted(&reversed, &indices);
+}
+
+#[test]
+fn test_appended_batch() {
+    let mut log: Vec<Vec<u8>> = random_keys(&mut rand::rng(), 20_000, 24, &ANY_BYTE);
+    log.sort();
+    // Append an unsorted batch interleaving with the existing log.
+    log.extend(random_keys(&mut rand::rng(), 1_000, 24, &ANY_BYTE));
+
+    let indices = orasort(&log);
+    assert_sorted(&log, &indices);
+}
+
+#[test]
+fn test_nearly_sorted() {
+    let mut rng = rand::rng();
+    let mut input = random_keys(&mut rand::rng(), 20_000, 16, &ANY_BYTE);
+    input.sort();
+    for _ in 0..3 {
+        let a = rng.random_range(0..input.len());
+        let b = rng.random_range(0..input.len());
+        input.swap(a, b);
+    }
+
+    let indices = orasort(&input);
+    assert_sorted(&input, &indices);
+}
+
+#[test]
+fn test_few_runs_mixed_directions() {
+    let mut input = Vec::new();
+    for run in 0..6 {
+        let mut chunk = random_keys(&mut rand::rng(), 3_000, 12, &ANY_BYTE);
+        chunk.sort();
+        if run % 2 == 1 {
+            chunk.reverse();
+        }
+        input.extend(chunk);
+    }
+
+    let indices = orasort(&input);
+    assert_sorted(&input, &indices);
+
+    let mut from_indices = orasort_from_indices(&input, (0..input.len()).collect(), 0);
+    assert_sorted(&input, &from_indices);
+
+    from_indices.reverse();
+    orasort_slice(&input, &mut from_indices, 0);
+    assert_sorted(&input, &from_indices);
+}
@@ -0,0 +1,112 @@
+use orasort::rank::{RankPolicy, orasort_percent_ranks, orasort_ranks};
+use rand::prelude::*;
+
+/// Reference ranks computed by sorting copies of the keys.
+fn model_ranks(keys: &[Vec<u8>], policy: RankPolicy) -> Vec<usize> {
+    let mut sorted: Vec<&Vec<u8>> = keys.iter().collect();
+    sorted.sort();
+    let mut distinct = sorted.clone();
+    distinct.dedup();
+
+    keys.iter()
+        .map(|key| match policy {
+            RankPolicy::Rank => sorted.partition_point(|k| *k < key) + 1,
+            RankPolicy::DenseRank => distinct.partition_point(|k| *k < key) + 1,
+            RankPolicy::RowNumber => unreachable!(),
+        })
+        .collect()
+}
+
+/// Returns `count` keys naming one of `distinct` ids each.
+fn random_id_keys(rng: &mut StdRng, count: usize, distinct: usize, prefix: &str) -> Vec<Vec<u8>> {
+    (0..count)
+        .map(|_| {
+            let id = rng.random_range(0..distinct);
+            // Vary the length so that ties also occur beyond the cached 8 bytes.
+            let mut key = format!("{}{}", prefix, id).into_bytes();
+            if id % 3 == 0 {
+                key.push(0);
+            }
+            key
+        })
+        .collect()
+}
+
+#[test]
+fn test_ranks_match_model() {
+    let mut rng = StdRng::seed_from_u64(35);
+
+    for &(count, distinct) in &[
+        (1, 1),
+        (10, 3),
+        (33, 33),
+        (500, 40),
+        (5000, 700),
+        (20000, 5),
+    ] {
+        for prefix in ["", "common-prefix-longer-than-cache/"] {
+            let keys = random_id_keys(&mut rng, count, distinct, prefix);
+
+            for policy in [RankPolicy::Rank, RankPolicy::DenseRank] {
+                assert_eq!(
+                    orasort_ranks(&keys, policy),
+                    model_ranks(&keys, policy),
+                    "{:?} with {} keys",
+                    policy,
+                    count
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_row_numbers_follow_sorted_order() {
+    let mut rng = StdRng::seed_from_u64(350);
+    let keys = random_id_keys(&mut rng, 3000, 100, "row-");
+    let row_numbers = orasort_ranks(&keys, RankPolicy::RowNumber);
+    let ranks = model_ranks(&keys, RankPolicy::Rank);
+
+    let mut seen = row_numbers.clone();
+    seen.sort();
+    assert_eq!(seen, (1..=3000).collect::<Vec<_>>());
+
+    // A row number lies within the block of positions its key occupies.
+    let counts = |key: &Vec<u8>| keys.iter().filter(|k| *k == key).count();
+    for (i, key) in keys.iter().enumerate().step_by(37) {
+        assert!(row_numbers[i] >= ranks[i]);
+        assert!(row_numbers[i] < ranks[i] + counts(key));
+    }
+}
+
+#[test]
+fn test_ranks_on_presorted_input_with_duplicates() {
+    // Presorted input is finished by run merging; ties must still be detected there.
+    let mut keys: Vec<String> = (0..4000).map(|i| format!("{:06}", i / 4)).collect();
+    keys.extend((0..200).map(|i| format!("{:06}", i * 5)));
+
+    let bytes: Vec<Vec<u8>> = keys.iter().map(|k| k.clone().into_bytes()).collect();
+    for policy in [RankPolicy::Rank, RankPolicy::DenseRank] {
+        assert_eq!(orasort_ranks(&keys, policy), model_ranks(&bytes, policy));
+    }
+
+    let mut descending = bytes.clone();
+    descending.reverse();
+    assert_eq!(
+        orasort_ranks(&descending, RankPolicy::DenseRank),
+        model_ranks(&descending, RankPolicy::DenseRank)
+    );
+}
+
+#[test]
+fn test_percent_ranks() {
+    let data = vec!["b", "a", "c", "b", "e", "d"];
+    assert_eq!(
+        orasort_percent_ranks(&data),
+        vec![0.2, 0.0, 0.6, 0.2, 1.0, 0.8]
+    );
+
+    assert_eq!(orasort_percent_ranks(&vec!["only"]), vec![0.0]);
+    assert!(orasort_percent_ranks(&Vec::<&str>::new()).is_empty());
+    assert_eq!(orasort_percent_ranks(&vec!["x", "x", "x"]), vec![0.0; 3]);
+}
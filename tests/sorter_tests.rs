@@ -0,0 +1,53 @@
+use orasort::prelude::*;
+
+mod common;
+use common::{ANY_BYTE, allocations, random_keys};
+
+#[global_allocator]
+static GLOBAL: common::CountingAlloc = common::CountingAlloc;
+
+#[test]
+fn test_sorter_matches_orasort() {
+    let mut sorter = Sorter::new();
+    for count in [0, 1, 5, 40, 3_000, 10] {
+        let batch = random_keys(&mut rand::rng(), count, 24, &ANY_BYTE);
+        let expected = orasort(&batch);
+        let indices = sorter.sort(&batch);
+
+        let actual: Vec<&Vec<u8>> = indices.iter().map(|&i| &batch[i]).collect();
+        let expected: Vec<&Vec<u8>> = expected.iter().map(|&i| &batch[i]).collect();
+        assert_eq!(actual, expected);
+    }
+}
+
+#[test]
+fn test_sorter_no_allocations_after_warm_up() {
+    // Large enough to take the radix path, and a presorted batch for the merge path.
+    let batches: Vec<Vec<Vec<u8>>> = (0..20)
+        .map(|_| random_keys(&mut rand::rng(), 5_000, 24, &ANY_BYTE))
+        .collect();
+    let mut presorted = random_keys(&mut rand::rng(), 5_000, 24, &ANY_BYTE);
+    presorted.sort();
+    presorted.extend(random_keys(&mut rand::rng(), 500, 24, &ANY_BYTE));
+
+    let mut sorter = Sorter::with_capacity(5_500);
+    sorter.sort(&presorted);
+    sorter.sort(&batches[0]);
+
+    let mut indices: Vec<usize> = (0..batches[1].len()).rev().collect();
+
+    let before = allocations();
+    for batch in &batches {
+        let indices = sorter.sort(batch);
+        assert!(indices.windows(2).all(|w| batch[w[0]] <= batch[w[1]]));
+    }
+    sorter.sort(&presorted);
+    sorter.sort_slice(&batches[1], &mut indices, 0);
+    assert_eq!(allocations(), before);
+
+    assert!(
+        indices
+            .windows(2)
+            .all(|w| batches[1][w[0]] <= batches[1][w[1]])
+    );
+}
@@ -0,0 +1,154 @@
+use orasort::SstError;
+use orasort::sst::{SstCursor, SstReader, SstWriter};
+use rand::prelude::*;
+
+fn collect(cursor: &mut SstCursor<'_, '_>) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let mut entries = Vec::new();
+    while cursor.is_valid() {
+        entries.push((cursor.key().to_vec(), cursor.value().to_vec()));
+        cursor.advance();
+    }
+    entries
+}
+
+fn random_entries(rng: &mut StdRng, count: usize) -> (Vec<Vec<u8>>, Vec<Vec<u8>>) {
+    let keys = (0..count)
+        .map(|_| {
+            let mut key = b"user/".to_vec();
+            let len = rng.random_range(0..10);
+            key.extend((0..len).map(|_| rng.random_range(b'a'..=b'e')));
+            key
+        })
+        .collect();
+    let values = (0..count).map(|i| i.to_string().into_bytes()).collect();
+    (keys, values)
+}
+
+#[test]
+fn test_round_trip_and_seek() {
+    let mut rng = StdRng::seed_from_u64(45);
+
+    for &(count, block_size, interval) in &[
+        (0, 4096, 16),
+        (1, 4096, 16),
+        (100, 64, 1),
+        (2000, 256, 4),
+        (5000, 4096, 16),
+        (5000, 0, 3),
+    ] {
+        let (keys, values) = random_entries(&mut rng, count);
+        let table = SstWriter::new()
+            .block_size(block_size)
+            .restart_interval(interval)
+            .build_with_values(&keys, &values);
+
+        let reader = SstReader::new(&table).unwrap();
+        assert_eq!(reader.len(), count);
+
+        let mut expected: Vec<(Vec<u8>, Vec<u8>)> =
+            keys.iter().cloned().zip(values.iter().cloned()).collect();
+        expected.sort_by(|a, b| a.0.cmp(&b.0));
+        let entries = collect(&mut reader.cursor());
+        let sorted_keys: Vec<&Vec<u8>> = entries.iter().map(|(k, _)| k).collect();
+        let expected_keys: Vec<&Vec<u8>> = expected.iter().map(|(k, _)| k).collect();
+        assert_eq!(sorted_keys, expected_keys);
+        for (key, value) in &entries {
+            let index: usize = std::str::from_utf8(value).unwrap().parse().unwrap();
+            assert_eq!(&keys[index], key);
+        }
+
+        // Seek to every key and to keys between them.
+        let mut cursor = reader.cursor();
+        for _ in 0..200.min(count * 2) {
+            let mut target = keys[rng.random_range(0..count)].clone();
+            if rng.random_bool(0.5) {
+                target.push(b'c');
+            }
+            cursor.seek(&target);
+            match expected_keys.iter().position(|k| **k >= target) {
+                Some(position) => assert_eq!(cursor.key(), &expected_keys[position][..]),
+                None => assert!(!cursor.is_valid()),
+            }
+        }
+        cursor.seek(b"zzz");
+        assert!(!cursor.is_valid());
+        cursor.seek(b"");
+        assert_eq!(cursor.is_valid(), count > 0);
+    }
+}
+
+#[test]
+fn test_get_and_duplicates() {
+    let keys = vec!["b", "a", "b", "c"];
+    let table = SstWriter::new().restart_interval(2).build(&keys);
+    let reader = SstReader::new(&table).unwrap();
+
+    assert_eq!(reader.get(b"b"), Some(&b""[..]));
+    assert_eq!(reader.get(b"bb"), None);
+    assert_eq!(reader.get(b""), None);
+    let keys: Vec<Vec<u8>> = collect(&mut reader.cursor())
+        .into_iter()
+        .map(|(k, _)| k)
+        .collect();
+    assert_eq!(keys, vec![b"a", b"b", b"b", b"c"]);
+
+    let empty = SstWriter::new().build(&Vec::<&[u8]>::new());
+    let reader = SstReader::new(&empty).unwrap();
+    assert!(reader.is_empty());
+    assert!(!reader.cursor().is_valid());
+    assert_eq!(reader.get(b"a"), None);
+}
+
+#[test]
+fn test_front_coding_and_writer() {
+    let keys: Vec<String> = (0..1000)
+        .map(|i| format!("/very/long/common/path/to/file-{:05}", i))
+        .collect();
+    let table = SstWriter::new().build(&keys);
+    let raw: usize = keys.iter().map(String::len).sum();
+    assert!(
+        table.len() < raw / 3,
+        "{} bytes for {} key bytes",
+        table.len(),
+        raw
+    );
+
+    let mut written = Vec::new();
+    SstWriter::new().write(&keys, &mut written).unwrap();
+    assert_eq!(written, table);
+}
+
+#[test]
+fn test_damaged_tables() {
+    let keys: Vec<String> = (0..500).map(|i| format!("key-{}", i)).collect();
+    let table = SstWriter::new()
+        .block_size(512)
+        .build_with_values(&keys, &keys);
+
+    assert_eq!(SstReader::new(b"short").unwrap_err(), SstError::NotATable);
+    assert_eq!(
+        SstReader::new(&table[..table.len() - 1]).unwrap_err(),
+        SstError::NotATable
+    );
+    assert!(SstReader::new(&table[1..]).is_err());
+
+    // Every single flipped byte is detected.
+    let mut rng = StdRng::seed_from_u64(7);
+    for _ in 0..500 {
+        let mut damaged = table.clone();
+        let at = rng.random_range(0..damaged.len() - 8);
+        damaged[at] ^= 1 << rng.random_range(0..8);
+        assert!(
+            SstReader::new(&damaged).is_err(),
+            "flip at {} undetected",
+            at
+        );
+    }
+
+    let mut damaged = table.clone();
+    damaged[3] ^= 0xff;
+    assert_eq!(
+        SstReader::new(&damaged).unwrap_err(),
+        SstError::ChecksumMismatch { offset: 0 }
+    );
+}
@@ -0,0 +1,84 @@
+use orasort::StreamSorter;
+
+mod common;
+use common::{allocations, random_keys};
+
+#[global_allocator]
+static GLOBAL: common::CountingAlloc = common::CountingAlloc;
+
+#[test]
+fn test_stream_sorter_matches_sorted_payloads() {
+    let mut sorter = StreamSorter::new();
+
+    for count in [0, 1, 31, 500, 5000] {
+        let keys = random_keys(&mut rand::rng(), count, 24, b"\x00\x01\x02\x03");
+        for (i, key) in keys.iter().enumerate() {
+            sorter.push_with_payload(key, i);
+        }
+        assert_eq!(sorter.len(), count);
+
+        let sorted: Vec<(&[u8], usize)> = sorter.finish().collect();
+
+        let mut expected: Vec<&[u8]> = keys.iter().map(|k| k.as_slice()).collect();
+        expected.sort();
+        let actual: Vec<&[u8]> = sorted.iter().map(|(k, _)| *k).collect();
+        assert_eq!(actual, expected);
+
+        // Every payload travels with its own key.
+        for (key, payload) in &sorted {
+            assert_eq!(*key, keys[*payload].as_slice());
+        }
+        assert!(sorter.is_empty());
+    }
+}
+
+#[test]
+fn test_dropped_iterator_and_reuse() {
+    let mut sorter = StreamSorter::new();
+    for key in ["c", "a", "b"] {
+        sorter.push_with_payload(key.as_bytes(), key.to_string());
+    }
+    let first = sorter.finish().next();
+    assert_eq!(first, Some((&b"a"[..], "a".to_string())));
+    assert!(sorter.is_empty());
+
+    sorter.push_with_payload(b"z", "z".to_string());
+    sorter.push_with_payload(b"y", "y".to_string());
+    let payloads: Vec<String> = sorter.finish().map(|(_, p)| p).collect();
+    assert_eq!(payloads, vec!["y", "z"]);
+
+    let mut plain: StreamSorter = StreamSorter::new();
+    plain.extend([&b"2"[..], b"10", b"1"]);
+    plain.push(b"0");
+    let keys: Vec<&[u8]> = plain.finish().map(|(k, ())| k).collect();
+    assert_eq!(keys, vec![&b"0"[..], b"1", b"10", b"2"]);
+}
+
+#[test]
+fn test_pushes_do_not_allocate_per_key() {
+    let keys = random_keys(&mut rand::rng(), 2000, 24, b"\x00\x01\x02\x03");
+    let mut sorter = StreamSorter::new();
+
+    // Warm up the buffers with a larger round.
+    for _ in 0..2 {
+        for key in &keys {
+            sorter.push_with_payload(key, 0u32);
+        }
+        sorter.finish().for_each(drop);
+    }
+    let high_water = sorter.high_water_bytes();
+    assert!(high_water >= sorter.memory_bytes());
+    assert!(high_water > keys.iter().map(|k| k.len()).sum::<usize>());
+
+    let before = allocations();
+    for key in &keys[..1000] {
+        sorter.push_with_payload(key, 1);
+    }
+    let sorted = sorter.finish().count();
+    let after = allocations();
+
+    assert_eq!(sorted, 1000);
+    // Only fixed per-round bookkeeping may allocate, never per key.
+    assert!(after - before <= 2, "{} allocations", after - before);
+    assert_eq!(sorter.high_water_bytes(), high_water);
+}